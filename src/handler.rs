@@ -10,13 +10,32 @@
 //! all had while this was a single file. Keep the tree one level deep: that
 //! is what makes `pub(super)` mean `crate::handler` everywhere.
 
+mod body_limits;
+mod body_stream;
 mod builder;
+mod byte_ranges;
+mod client_info;
+#[cfg(feature = "compression")]
+mod compression;
+mod content_coding;
 mod core;
+mod csp;
+#[cfg(feature = "compression")]
+mod decompression;
+mod error_pages;
+mod header_limits;
+mod http_date;
 mod http_util;
+mod multipart;
 mod policy;
+mod problem;
+mod redirects;
 mod render;
+mod request_id;
 mod routes;
+mod security_headers;
 mod server_fns;
+mod static_assets;
 #[cfg(test)]
 mod test_support;
 mod trace;
@@ -28,10 +47,31 @@ pub mod wasip3;
 
 // Reached from `crate::request::p2`, which is the only consumer outside
 // this module tree; Preview 3 calls it directly from `handler::wasip3`.
+pub use body_limits::BodyLimit;
+pub use client_info::{
+    ClientInfo, ForwardingHeaders, InvalidCidr, TrustedProxies,
+};
+#[cfg(feature = "compression")]
+pub use compression::ResponseCompression;
+pub use csp::ContentSecurityPolicy;
+#[cfg(feature = "compression")]
+pub use decompression::{
+    DEFAULT_MAX_DECOMPRESSION_RATIO, RequestDecompression,
+};
+pub use error_pages::{ErrorCause, ErrorPage, ErrorPages};
+pub use header_limits::HeaderLimits;
+pub use multipart::{DEFAULT_MAX_MULTIPART_PARTS, MultipartLimits};
 #[cfg(feature = "wasip2")]
 pub(crate) use policy::validate_content_length;
 pub use policy::{
     DEFAULT_MAX_REQUEST_BODY_SIZE, HandlerConfig, RegistrationError,
     RequestPolicyError,
 };
+pub use redirects::RedirectPolicy;
+pub use request_id::{RequestId, RequestIdFormat, RequestIdPolicy};
 pub use routes::validate_route_table;
+pub use security_headers::{
+    CrossOriginEmbedderPolicy, CrossOriginOpenerPolicy,
+    CrossOriginResourcePolicy, FrameOptions, Hsts, ReferrerPolicy,
+    SecurityHeaders,
+};
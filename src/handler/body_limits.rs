@@ -0,0 +1,150 @@
+//! Request body limits chosen by path.
+//!
+//! Both previews settle the body size limit and read budget before the body
+//! is read, so a path's limit has to be picked from the request line alone.
+//! `HandlerConfig::for_path` does that once, at the
+//! top of `build_with_config`, and everything after it, buffered or
+//! streamed, sees one limit.
+
+/// The body size limit and read budget for the requests under one path,
+/// configured through
+/// [`HandlerConfig::with_path_body_limit`](super::HandlerConfig::with_path_body_limit)
+/// and its prefix and server function variants.
+///
+/// A limit may be larger or smaller than the handler-wide one. Without a
+/// read budget of its own, the handler-wide budget applies.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{BodyLimit, HandlerConfig};
+///
+/// let config = HandlerConfig::default()
+///     .with_max_request_body_size(16 * 1024)
+///     .with_path_body_limit(
+///         "/api/upload_avatar",
+///         BodyLimit::new(8 * 1024 * 1024).with_timeout_ns(30_000_000_000),
+///     )
+///     .with_prefix_body_limit("/api/admin", BodyLimit::new(1024));
+///
+/// let avatar = config.body_limit_for("/api/upload_avatar");
+/// assert_eq!(avatar.max_size(), 8 * 1024 * 1024);
+/// assert_eq!(config.body_limit_for("/api/admin/rename").max_size(), 1024);
+/// assert_eq!(config.body_limit_for("/api/other").max_size(), 16 * 1024);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BodyLimit {
+    size: usize,
+    timeout_ns: Option<u64>,
+}
+
+impl BodyLimit {
+    /// Returns a limit of `bytes`, with the handler-wide read budget.
+    #[must_use]
+    pub const fn new(bytes: usize) -> Self {
+        Self {
+            size: bytes,
+            timeout_ns: None,
+        }
+    }
+
+    /// Returns a copy that abandons a body taking longer than `nanoseconds`
+    /// to arrive in full.
+    #[must_use]
+    pub const fn with_timeout_ns(mut self, nanoseconds: u64) -> Self {
+        self.timeout_ns = Some(nanoseconds);
+        self
+    }
+
+    /// Returns the maximum body size in bytes.
+    #[must_use]
+    pub const fn max_size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the whole-body read budget in nanoseconds, if any.
+    #[must_use]
+    pub const fn timeout_ns(&self) -> Option<u64> {
+        self.timeout_ns
+    }
+}
+
+/// The paths a [`BodyLimit`] applies to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) enum PathPattern {
+    /// One path, such as a server function's.
+    Exact(String),
+    /// A path and everything below it, matched by whole segments.
+    Prefix(String),
+}
+
+impl PathPattern {
+    /// Returns how specific a match of `path` is, or `None` when it does not
+    /// match. An exact path beats any prefix, and a longer prefix a shorter
+    /// one.
+    fn specificity(&self, path: &str) -> Option<usize> {
+        match self {
+            Self::Exact(exact) => (exact == path).then_some(usize::MAX),
+            Self::Prefix(prefix) => {
+                let rest = path.strip_prefix(prefix.as_str())?;
+                (rest.is_empty()
+                    || rest.starts_with('/')
+                    || prefix.ends_with('/'))
+                .then_some(prefix.len())
+            }
+        }
+    }
+}
+
+/// Returns the limit of the most specific pattern matching `path`.
+pub(super) fn limit_for(
+    limits: &[(PathPattern, BodyLimit)],
+    path: &str,
+) -> Option<BodyLimit> {
+    limits
+        .iter()
+        .filter_map(|(pattern, limit)| {
+            Some((pattern.specificity(path)?, *limit))
+        })
+        .max_by_key(|&(specificity, _)| specificity)
+        .map(|(_, limit)| limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> Vec<(PathPattern, BodyLimit)> {
+        vec![
+            (PathPattern::Prefix("/api".to_owned()), BodyLimit::new(1)),
+            (
+                PathPattern::Prefix("/api/files/".to_owned()),
+                BodyLimit::new(2),
+            ),
+            (
+                PathPattern::Exact("/api/files/avatar".to_owned()),
+                BodyLimit::new(3),
+            ),
+        ]
+    }
+
+    fn size_for(path: &str) -> Option<usize> {
+        limit_for(&limits(), path).map(|limit| limit.max_size())
+    }
+
+    #[test]
+    fn the_most_specific_pattern_wins() {
+        assert_eq!(size_for("/api/files/avatar"), Some(3));
+        assert_eq!(size_for("/api/files/avatar/more"), Some(2));
+        assert_eq!(size_for("/api/files/other"), Some(2));
+        assert_eq!(size_for("/api/echo"), Some(1));
+        assert_eq!(size_for("/api"), Some(1));
+    }
+
+    #[test]
+    fn prefixes_match_whole_segments() {
+        assert_eq!(size_for("/apiary"), None);
+        assert_eq!(size_for("/api/files"), Some(1));
+        assert_eq!(size_for("/"), None);
+    }
+}
@@ -0,0 +1,388 @@
+//! Request bodies handed to a server function as they arrive, instead of
+//! being buffered by `build_with_config`.
+//!
+//! Each preview turns its host body into a [`ChunkStream`] and a deadline
+//! future; everything after that is shared. The size and time budgets are
+//! checked as chunks arrive rather than once at the end, and a breach is
+//! reported through a [`BreachSlot`] so the handler can still answer with
+//! the same `413` or `408` a buffered body would have received.
+
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::Stream;
+use http::{HeaderMap, header::CONTENT_TYPE};
+use parking_lot::Mutex;
+
+use super::multipart::{PartScanner, multipart_boundary};
+use super::policy::{HandlerConfig, RequestPolicyError};
+use crate::__private::FromChunkStream;
+
+/// The body chunks a preview reads from its host.
+pub(super) use crate::__private::ChunkStream;
+
+/// Resolves when the whole-body read budget has run out.
+pub(super) type Deadline = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Where a budget breach in a stream already handed to a server function is
+/// recorded.
+pub(super) type BreachSlot = Arc<Mutex<Option<RequestPolicyError>>>;
+
+/// Why a body could not be handed to its server function.
+#[derive(Debug)]
+pub(super) enum BodyError {
+    /// The body breached one of its budgets.
+    Policy(RequestPolicyError),
+    /// The host failed to deliver the body.
+    Io(io::Error),
+}
+
+/// The content type both `server_fn` streaming input encodings,
+/// `Streaming` and `StreamingText`, send their body with.
+const STREAMING_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Returns whether a request's body was encoded by a streaming `server_fn`
+/// input encoding, judged by its `Content-Type`.
+fn is_streaming_body(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|essence| {
+            essence.trim().eq_ignore_ascii_case(STREAMING_CONTENT_TYPE)
+        })
+}
+
+/// Returns whether `build_with_config` leaves a request's body unread for
+/// its server function under `config`.
+pub(super) fn is_streamed(config: &HandlerConfig, headers: &HeaderMap) -> bool {
+    // An encoded body is decoded, which needs all of it.
+    #[cfg(feature = "compression")]
+    if config.request_decompression().is_some()
+        && headers.contains_key(http::header::CONTENT_ENCODING)
+    {
+        return false;
+    }
+    (config.request_body_streaming() && is_streaming_body(headers))
+        || (config.multipart_limits().is_some()
+            && multipart_boundary(headers).is_some())
+}
+
+/// A request body that has not been read yet, with the budgets it must be
+/// read under.
+pub(super) struct StreamingBody {
+    chunks: ChunkStream,
+    limit: usize,
+    deadline: Option<(u64, Deadline)>,
+    parts: Option<PartScanner>,
+    preview: &'static str,
+}
+
+impl StreamingBody {
+    pub(super) fn new(
+        chunks: ChunkStream,
+        limit: usize,
+        deadline: Option<(u64, Deadline)>,
+        preview: &'static str,
+    ) -> Self {
+        Self {
+            chunks,
+            limit,
+            deadline,
+            parts: None,
+            preview,
+        }
+    }
+
+    /// Returns a copy that also enforces `config`'s multipart limits, when
+    /// `headers` declare a multipart body.
+    pub(super) fn with_multipart_limits(
+        mut self,
+        config: &HandlerConfig,
+        headers: &HeaderMap,
+    ) -> Self {
+        if let Some(limits) = config.multipart_limits()
+            && let Some(boundary) = multipart_boundary(headers)
+        {
+            self.limit = self.limit.min(limits.max_total_size());
+            self.parts = Some(PartScanner::new(&boundary, *limits));
+        }
+        self
+    }
+
+    /// Returns the preview the body is read from, for tracing a breach.
+    pub(super) const fn preview(&self) -> &'static str {
+        self.preview
+    }
+
+    /// Converts the body into the request body type `B` of a server
+    /// function's backend.
+    ///
+    /// The Axum-compatible body Leptos generates for server functions wraps
+    /// the stream, so it receives the chunks as they arrive and any breach is
+    /// recorded in the returned slot. The generic backend's `Bytes` body is
+    /// read in full under the same budgets first.
+    pub(super) async fn into_body<B>(self) -> Result<(B, BreachSlot), BodyError>
+    where
+        B: FromChunkStream,
+    {
+        let breach = BreachSlot::default();
+        let budgeted = Budgeted {
+            body: Some(self),
+            read: 0,
+            breach: Arc::clone(&breach),
+        };
+        match B::from_chunk_stream(Box::pin(budgeted)).await {
+            Ok(body) => Ok((body, breach)),
+            Err(error) => Err(match breach.lock().take() {
+                Some(error) => BodyError::Policy(error),
+                None => BodyError::Io(error),
+            }),
+        }
+    }
+}
+
+/// The stream a server function reads, counting bytes against the size
+/// budget and racing every read against the deadline.
+struct Budgeted {
+    body: Option<StreamingBody>,
+    read: usize,
+    breach: BreachSlot,
+}
+
+impl Budgeted {
+    /// Ends the stream with `error`, releasing the host body immediately.
+    fn breach(
+        &mut self,
+        error: RequestPolicyError,
+    ) -> Poll<Option<Result<Bytes, io::Error>>> {
+        self.body = None;
+        *self.breach.lock() = Some(error.clone());
+        Poll::Ready(Some(Err(io::Error::other(error))))
+    }
+}
+
+impl Stream for Budgeted {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(body) = this.body.as_mut() else {
+            return Poll::Ready(None);
+        };
+        if let Some((nanoseconds, deadline)) = body.deadline.as_mut()
+            && deadline.as_mut().poll(cx).is_ready()
+        {
+            let nanoseconds = *nanoseconds;
+            return this
+                .breach(RequestPolicyError::BodyReadTimeout { nanoseconds });
+        }
+        match body.chunks.as_mut().poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Ok(chunk))) => {
+                let limit = body.limit;
+                this.read = this.read.saturating_add(chunk.len());
+                if this.read > limit {
+                    return this
+                        .breach(RequestPolicyError::BodyTooLarge { limit });
+                }
+                if let Some(parts) = body.parts.as_mut()
+                    && let Err(error) = parts.feed(&chunk)
+                {
+                    return this.breach(error);
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(error))) => {
+                this.body = None;
+                Poll::Ready(Some(Err(error)))
+            }
+            Poll::Ready(None) => {
+                this.body = None;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{
+        StreamExt,
+        future::{pending, ready},
+        stream,
+    };
+    use http::HeaderValue;
+
+    use super::super::multipart::MultipartLimits;
+    use super::*;
+
+    fn body_of(
+        chunks: &[&'static [u8]],
+        limit: usize,
+        deadline: Option<Deadline>,
+    ) -> StreamingBody {
+        let chunks: Vec<Result<Bytes, io::Error>> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect();
+        StreamingBody::new(
+            Box::pin(stream::iter(chunks)),
+            limit,
+            deadline.map(|deadline| (7, deadline)),
+            "test",
+        )
+    }
+
+    async fn drain(body: axum_core::body::Body) -> Vec<Result<Bytes, String>> {
+        body.into_data_stream()
+            .map(|chunk| chunk.map_err(|error| error.to_string()))
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn streaming_encodings_are_recognized_by_content_type() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_streaming_body(&headers));
+
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("Application/Octet-Stream; x=1"),
+        );
+        assert!(is_streaming_body(&headers));
+
+        headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert!(!is_streaming_body(&headers));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn axum_bodies_receive_chunks_as_they_arrive() {
+        let (body, breach) = body_of(&[b"ab", b"cd"], 4, None)
+            .into_body::<axum_core::body::Body>()
+            .await
+            .expect("the body should be accepted");
+
+        let chunks = drain(body).await;
+
+        assert_eq!(
+            chunks,
+            [Ok(Bytes::from_static(b"ab")), Ok(Bytes::from_static(b"cd"))]
+        );
+        assert_eq!(*breach.lock(), None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn the_size_budget_is_enforced_as_chunks_arrive() {
+        let (body, breach) = body_of(&[b"ab", b"cd", b"ef"], 3, None)
+            .into_body::<axum_core::body::Body>()
+            .await
+            .expect("the body should be accepted");
+
+        let chunks = drain(body).await;
+
+        assert_eq!(chunks.len(), 2, "the stream ends at the breach");
+        assert!(chunks[1].is_err());
+        assert_eq!(
+            *breach.lock(),
+            Some(RequestPolicyError::BodyTooLarge { limit: 3 })
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn an_expired_deadline_ends_the_stream() {
+        let (body, breach) = body_of(&[b"ab"], 16, Some(Box::pin(ready(()))))
+            .into_body::<axum_core::body::Body>()
+            .await
+            .expect("the body should be accepted");
+
+        let chunks = drain(body).await;
+
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_err());
+        assert_eq!(
+            *breach.lock(),
+            Some(RequestPolicyError::BodyReadTimeout { nanoseconds: 7 })
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn multipart_limits_apply_to_a_declared_multipart_body() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("multipart/form-data; boundary=x"),
+        );
+        let config = HandlerConfig::default()
+            .with_multipart_limits(MultipartLimits::new().with_max_parts(1));
+        assert!(is_streamed(&config, &headers));
+        assert!(!is_streamed(&HandlerConfig::default(), &headers));
+
+        let error =
+            body_of(&[b"--x\r\n\r\na\r\n", b"--x\r\n\r\nb\r\n--x--"], 64, None)
+                .with_multipart_limits(&config, &headers)
+                .into_body::<Bytes>()
+                .await
+                .map(|_| ())
+                .expect_err("the body has two parts");
+
+        assert!(matches!(
+            error,
+            BodyError::Policy(RequestPolicyError::TooManyMultipartParts {
+                limit: 1
+            })
+        ));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn other_body_types_are_buffered_under_the_same_budgets() {
+        let (bytes, breach) =
+            body_of(&[b"ab", b"cd"], 4, Some(Box::pin(pending())))
+                .into_body::<Bytes>()
+                .await
+                .expect("the body fits");
+        assert_eq!(bytes, Bytes::from_static(b"abcd"));
+        assert_eq!(*breach.lock(), None);
+
+        let error = body_of(&[b"ab", b"cd"], 3, None)
+            .into_body::<Bytes>()
+            .await
+            .map(|_| ())
+            .expect_err("the body is too large");
+        assert!(matches!(
+            error,
+            BodyError::Policy(RequestPolicyError::BodyTooLarge { limit: 3 })
+        ));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn a_host_read_failure_is_not_buffered_as_a_truncated_body() {
+        let chunks = vec![
+            Ok(Bytes::from_static(b"ab")),
+            Err(io::Error::other("connection reset")),
+            Ok(Bytes::from_static(b"cd")),
+        ];
+        let error = StreamingBody::new(
+            Box::pin(stream::iter(chunks)),
+            16,
+            None,
+            "test",
+        )
+        .into_body::<Bytes>()
+        .await
+        .map(|_| ())
+        .expect_err("the body did not arrive in full");
+
+        assert!(matches!(error, BodyError::Io(_)));
+    }
+}
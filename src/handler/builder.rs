@@ -17,7 +17,7 @@ macro_rules! common_handler_methods {
                     T::InputStreamError,
                     T::OutputStreamError,
                 >,
-            ReqBody<T>: From<Bytes> + Send + 'static,
+            ReqBody<T>: FromChunkStream + Send + 'static,
             ResBody<T>: Into<Body> + Send + 'static,
         {
             self.core = self.core.with_server_fn::<T>();
@@ -26,6 +26,9 @@ macro_rules! common_handler_methods {
 
         /// Registers a static-file callback for one URI prefix.
         ///
+        /// Responses use [`StaticRouteOptions::default`]; see
+        /// [`Self::static_files_handler_with_options`].
+        ///
         /// # Errors
         ///
         /// Returns [`RegistrationError::InvalidStaticPrefix`] if `prefix`
@@ -43,6 +46,84 @@ macro_rules! common_handler_methods {
             Ok(self)
         }
 
+        /// Registers a static-file callback for one URI prefix with
+        /// explicit response options, such as its `Cache-Control` policy.
+        ///
+        /// # Errors
+        ///
+        /// Returns [`RegistrationError::InvalidStaticPrefix`] if `prefix`
+        /// is not a usable URI path.
+        pub fn static_files_handler_with_options<T>(
+            mut self,
+            prefix: T,
+            options: &StaticRouteOptions,
+            handler: impl Fn(String) -> Option<Body> + 'static + Send + Clone,
+        ) -> Result<Self, RegistrationError>
+        where
+            T: TryInto<Uri>,
+            <T as TryInto<Uri>>::Error: std::error::Error,
+        {
+            self.core = self
+                .core
+                .static_files_handler_with_options(prefix, options, handler)?;
+            Ok(self)
+        }
+
+        /// Registers a static-asset callback for one URI prefix.
+        ///
+        /// Like [`Self::static_files_handler`], but the callback returns a
+        /// [`StaticAsset`] that can carry its own `ETag` and `Last-Modified`
+        /// validators. Either way, a conditional `GET` or `HEAD` whose
+        /// validators still match is answered with `304 Not Modified` and no
+        /// body. Responses use [`StaticRouteOptions::default`]; see
+        /// [`Self::static_assets_handler_with_options`].
+        ///
+        /// # Errors
+        ///
+        /// Returns [`RegistrationError::InvalidStaticPrefix`] if `prefix`
+        /// is not a usable URI path.
+        pub fn static_assets_handler<T>(
+            mut self,
+            prefix: T,
+            handler: impl Fn(String) -> Option<StaticAsset>
+            + 'static
+            + Send
+            + Clone,
+        ) -> Result<Self, RegistrationError>
+        where
+            T: TryInto<Uri>,
+            <T as TryInto<Uri>>::Error: std::error::Error,
+        {
+            self.core = self.core.static_assets_handler(prefix, handler)?;
+            Ok(self)
+        }
+
+        /// Registers a static-asset callback for one URI prefix with
+        /// explicit response options, such as its `Cache-Control` policy.
+        ///
+        /// # Errors
+        ///
+        /// Returns [`RegistrationError::InvalidStaticPrefix`] if `prefix`
+        /// is not a usable URI path.
+        pub fn static_assets_handler_with_options<T>(
+            mut self,
+            prefix: T,
+            options: &StaticRouteOptions,
+            handler: impl Fn(String) -> Option<StaticAsset>
+            + 'static
+            + Send
+            + Clone,
+        ) -> Result<Self, RegistrationError>
+        where
+            T: TryInto<Uri>,
+            <T as TryInto<Uri>>::Error: std::error::Error,
+        {
+            self.core = self
+                .core
+                .static_assets_handler_with_options(prefix, options, handler)?;
+            Ok(self)
+        }
+
         /// Generates Leptos routes for the application.
         ///
         /// # Errors
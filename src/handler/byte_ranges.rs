@@ -0,0 +1,328 @@
+//! `Range` and `If-Range` evaluation for static assets.
+//!
+//! Only the `bytes` unit exists, and only `GET` defines range handling, so
+//! the caller decides whether to consult this module at all. Everything here
+//! works on a representation length and the asset's validators; assembling
+//! the `206` body is left to [`super::static_assets`].
+
+use std::{ops::Range, time::SystemTime};
+
+use http::{
+    HeaderMap,
+    header::{IF_RANGE, RANGE},
+};
+
+use super::http_date::{parse_http_date, unix_seconds};
+
+/// The most ranges one request may ask for after coalescing.
+///
+/// RFC 9110 section 14.2 lets a server ignore a range set it considers
+/// abusive. Many small ranges cost a part header each and turn one read into
+/// many, so a request past this count is answered with the whole asset.
+const MAX_RANGES: usize = 16;
+
+/// What a `GET` for an asset of known length should answer with.
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum RangeOutcome {
+    /// No usable `Range`, or a stale `If-Range`: send the whole asset.
+    Full,
+    /// `206 Partial Content` with these ranges, sorted and non-overlapping.
+    Partial(Vec<Range<u64>>),
+    /// `416 Range Not Satisfiable`.
+    Unsatisfiable,
+}
+
+/// Evaluates the request's `Range` and `If-Range` against an asset of
+/// `length` bytes with the given validators.
+///
+/// A malformed `Range`, a unit other than `bytes`, or more than
+/// [`MAX_RANGES`] ranges are ignored rather than rejected, as RFC 9110
+/// allows, so the client gets the whole asset instead of an error.
+pub(super) fn evaluate_range(
+    headers: &HeaderMap,
+    length: u64,
+    etag: Option<&str>,
+    last_modified: Option<SystemTime>,
+) -> RangeOutcome {
+    let Some(range) = headers.get(RANGE).and_then(|value| value.to_str().ok())
+    else {
+        return RangeOutcome::Full;
+    };
+    if let Some(if_range) = headers.get(IF_RANGE)
+        && !if_range
+            .to_str()
+            .is_ok_and(|value| if_range_matches(value, etag, last_modified))
+    {
+        return RangeOutcome::Full;
+    }
+    let Some(specs) = parse_range(range) else {
+        return RangeOutcome::Full;
+    };
+
+    let mut ranges = specs
+        .into_iter()
+        .filter_map(|spec| spec.resolve(length))
+        .collect::<Vec<_>>();
+    if ranges.is_empty() {
+        return RangeOutcome::Unsatisfiable;
+    }
+    ranges.sort_by_key(|range| range.start);
+    let mut coalesced: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end => {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+    if coalesced.len() > MAX_RANGES {
+        return RangeOutcome::Full;
+    }
+    RangeOutcome::Partial(coalesced)
+}
+
+/// Whether `If-Range` still describes the current representation.
+///
+/// An entity tag must match by the strong comparison, so a weak tag never
+/// does. A date must equal `Last-Modified` exactly; an unparseable value
+/// matches nothing, which makes the client receive the whole asset.
+fn if_range_matches(
+    value: &str,
+    etag: Option<&str>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    let value = value.trim();
+    if value.starts_with("W/") {
+        return false;
+    }
+    if let Some(quoted) = value.strip_prefix('"') {
+        return quoted
+            .strip_suffix('"')
+            .is_some_and(|tag| etag == Some(tag));
+    }
+    match (last_modified, parse_http_date(value)) {
+        (Some(modified), Some(date)) => {
+            unix_seconds(modified) == unix_seconds(date)
+        }
+        _ => false,
+    }
+}
+
+/// One `range-spec` as written, before it is resolved against a length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RangeSpec {
+    /// `first-` or `first-last`.
+    From { first: u64, last: Option<u64> },
+    /// `-length`: the final `length` bytes.
+    Suffix(u64),
+}
+
+impl RangeSpec {
+    /// The half-open byte range this spec selects, or `None` when it is
+    /// unsatisfiable for `length`.
+    fn resolve(self, length: u64) -> Option<Range<u64>> {
+        match self {
+            Self::From { first, last } if first < length => {
+                let end = last
+                    .map_or(length, |last| last.saturating_add(1).min(length));
+                Some(first..end)
+            }
+            Self::From { .. } | Self::Suffix(0) => None,
+            Self::Suffix(suffix) if length > 0 => {
+                Some(length.saturating_sub(suffix)..length)
+            }
+            Self::Suffix(_) => None,
+        }
+    }
+}
+
+/// Parses a `Range` value into its specs, or `None` when it is not a
+/// well-formed `bytes` range set.
+fn parse_range(value: &str) -> Option<Vec<RangeSpec>> {
+    let (unit, set) = value.trim().split_once('=')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    let mut specs = Vec::new();
+    // `1#range-spec` tolerates empty list elements, but not a list made of
+    // nothing else.
+    for spec in set
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        let (first, last) = spec.split_once('-')?;
+        let spec = if first.is_empty() {
+            RangeSpec::Suffix(parse_digits(last)?)
+        } else {
+            let first = parse_digits(first)?;
+            let last = if last.is_empty() {
+                None
+            } else {
+                Some(parse_digits(last)?)
+            };
+            if last.is_some_and(|last| last < first) {
+                return None;
+            }
+            RangeSpec::From { first, last }
+        };
+        specs.push(spec);
+    }
+    (!specs.is_empty()).then_some(specs)
+}
+
+/// Parses `1*DIGIT`, saturating a value too large for `u64`, which can only
+/// ever mean "past the end" for a representation length.
+fn parse_digits(value: &str) -> Option<u64> {
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some(value.parse().unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use http::HeaderValue;
+
+    use super::*;
+
+    const MODIFIED: u64 = 784_111_777;
+
+    fn evaluate(headers: &[(&str, &str)], length: u64) -> RangeOutcome {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                http::HeaderName::from_bytes(name.as_bytes())
+                    .expect("test header name should be valid"),
+                HeaderValue::from_str(value)
+                    .expect("test header value should be valid"),
+            );
+        }
+        evaluate_range(
+            &map,
+            length,
+            Some("v1"),
+            Some(UNIX_EPOCH + Duration::from_secs(MODIFIED)),
+        )
+    }
+
+    /// `(start, end)` pairs rather than ranges, which clippy would read as
+    /// a one-element array of a whole range.
+    fn partial(ranges: &[(u64, u64)]) -> RangeOutcome {
+        RangeOutcome::Partial(
+            ranges.iter().map(|&(start, end)| start..end).collect(),
+        )
+    }
+
+    #[test]
+    fn resolves_each_range_form() {
+        let cases = [
+            ("bytes=0-0", partial(&[(0, 1)])),
+            ("bytes=10-19", partial(&[(10, 20)])),
+            ("bytes=90-", partial(&[(90, 100)])),
+            ("bytes=-5", partial(&[(95, 100)])),
+            ("bytes=-500", partial(&[(0, 100)])),
+            ("bytes=95-500", partial(&[(95, 100)])),
+            ("BYTES = 0-0", RangeOutcome::Full),
+            ("Bytes=0-0", partial(&[(0, 1)])),
+            ("bytes=0-99999999999999999999999", partial(&[(0, 100)])),
+        ];
+        for (range, expected) in cases {
+            assert_eq!(evaluate(&[("range", range)], 100), expected, "{range}");
+        }
+    }
+
+    #[test]
+    fn malformed_or_foreign_ranges_are_ignored() {
+        for range in [
+            "bytes=",
+            "bytes=,",
+            "bytes=5",
+            "bytes=9-5",
+            "bytes=a-b",
+            "bytes=-",
+            "bytes=+1-2",
+            "items=0-5",
+            "0-5",
+        ] {
+            assert_eq!(
+                evaluate(&[("range", range)], 100),
+                RangeOutcome::Full,
+                "{range}"
+            );
+        }
+    }
+
+    #[test]
+    fn ranges_past_the_end_are_unsatisfiable() {
+        for range in
+            ["bytes=100-", "bytes=100-200", "bytes=-0", "bytes=200-,-0"]
+        {
+            assert_eq!(
+                evaluate(&[("range", range)], 100),
+                RangeOutcome::Unsatisfiable,
+                "{range}"
+            );
+        }
+        assert_eq!(
+            evaluate(&[("range", "bytes=-5")], 0),
+            RangeOutcome::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_members_of_a_set_are_dropped() {
+        assert_eq!(
+            evaluate(&[("range", "bytes=200-300, 0-9")], 100),
+            partial(&[(0, 10)])
+        );
+    }
+
+    #[test]
+    fn overlapping_and_adjacent_ranges_are_coalesced_in_order() {
+        assert_eq!(
+            evaluate(&[("range", "bytes=50-59, 0-9, 5-19, 20-24, -10")], 100),
+            partial(&[(0, 25), (50, 60), (90, 100)])
+        );
+    }
+
+    #[test]
+    fn too_many_ranges_are_answered_in_full() {
+        let many = (0..=MAX_RANGES)
+            .map(|index| format!("{}-{}", index * 2, index * 2))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(
+            evaluate(&[("range", &format!("bytes={many}"))], 100),
+            RangeOutcome::Full
+        );
+    }
+
+    #[test]
+    fn if_range_requires_a_strong_match() {
+        let cases = [
+            ("\"v1\"", partial(&[(0, 1)])),
+            ("\"v2\"", RangeOutcome::Full),
+            ("W/\"v1\"", RangeOutcome::Full),
+            ("Sun, 06 Nov 1994 08:49:37 GMT", partial(&[(0, 1)])),
+            ("Sun, 06 Nov 1994 08:49:38 GMT", RangeOutcome::Full),
+            ("Sun, 06 Nov 1994 08:49:36 GMT", RangeOutcome::Full),
+            ("not a validator", RangeOutcome::Full),
+        ];
+        for (if_range, expected) in cases {
+            assert_eq!(
+                evaluate(&[("range", "bytes=0-0"), ("if-range", if_range)], 10),
+                expected,
+                "{if_range}"
+            );
+        }
+    }
+
+    #[test]
+    fn if_range_without_range_changes_nothing() {
+        assert_eq!(evaluate(&[("if-range", "\"v2\"")], 10), RangeOutcome::Full);
+    }
+}
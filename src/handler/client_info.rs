@@ -0,0 +1,630 @@
+//! The client behind a chain of trusted reverse proxies.
+//!
+//! A WASI component never sees the peer address, and behind an ingress the
+//! scheme and authority the host reports are the proxy's. [`TrustedProxies`]
+//! says which forwarding headers the deployment's proxies write and how many
+//! of the hops recorded there they added; [`ClientInfo`] is what those hops
+//! say about the client, with anything a client could have written itself
+//! left out.
+
+use std::net::IpAddr;
+
+use http::{HeaderMap, Uri, header::HOST, uri::Authority};
+use leptos_router::location::RequestUrl;
+use thiserror::Error;
+
+use super::redirects::origin_of;
+
+const FORWARDED: &str = "forwarded";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// Error returned when a network is not in CIDR notation.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[error("`{0}` is not an IP network in CIDR notation")]
+pub struct InvalidCidr(String);
+
+/// Which forwarding headers a deployment's proxies write.
+///
+/// Only the selected family is read. The other is ignored completely, since
+/// a proxy that does not write a header passes along whatever the client
+/// sent in it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ForwardingHeaders {
+    /// `X-Forwarded-For`, with every trusted proxy appending to
+    /// `X-Forwarded-Proto` and `X-Forwarded-Host` in step with it. A scheme
+    /// or host is read only when its list is exactly as long as
+    /// `X-Forwarded-For`, from the client's position.
+    #[default]
+    XForwarded,
+    /// `X-Forwarded-For`, with the nearest proxy replacing any
+    /// `X-Forwarded-Proto` and `X-Forwarded-Host` the client sent. A scheme
+    /// or host is read only when its header carries a single value.
+    XForwardedOverwritten,
+    /// RFC 7239 `Forwarded`.
+    Forwarded,
+}
+
+/// The reverse proxies whose forwarding headers are believed, configured
+/// through [`HandlerConfig::with_trusted_proxies`](super::HandlerConfig::with_trusted_proxies).
+///
+/// The hops recorded in the selected [`ForwardingHeaders`], `X-Forwarded-*`
+/// by default, are walked from the nearest. The nearest `hops` entries
+/// were appended by trusted proxies, the ingress in front of the component
+/// being the first; beyond those, an entry whose address lies in a trusted
+/// network is skipped as another proxy. The first entry left is the client.
+/// Entries further out were written by the client or by proxies nobody
+/// vouches for, and are never read.
+///
+/// With `hops` at zero the forwarding headers are ignored entirely, and the
+/// client is described by the request itself.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, TrustedProxies};
+///
+/// use leptos_wasi::ForwardingHeaders;
+///
+/// let proxies = TrustedProxies::hops(1)
+///     .with_headers(ForwardingHeaders::Forwarded)
+///     .trust_cidr("10.0.0.0/8")?
+///     .trust_cidr("fd00::/8")?;
+/// let config = HandlerConfig::default().with_trusted_proxies(proxies);
+/// # Ok::<(), leptos_wasi::InvalidCidr>(())
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrustedProxies {
+    hops: usize,
+    networks: Vec<Network>,
+    headers: ForwardingHeaders,
+}
+
+impl TrustedProxies {
+    /// Trusts the nearest `count` forwarding hops of `X-Forwarded-*`.
+    #[must_use]
+    pub const fn hops(count: usize) -> Self {
+        Self {
+            hops: count,
+            networks: Vec::new(),
+            headers: ForwardingHeaders::XForwarded,
+        }
+    }
+
+    /// Returns a copy that reads `headers` and ignores the other family.
+    #[must_use]
+    pub const fn with_headers(mut self, headers: ForwardingHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Returns a copy that also skips hops whose address lies in `cidr`,
+    /// such as `10.0.0.0/8` or `2001:db8::/32`. A bare address trusts that
+    /// address alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidCidr`] if `cidr` is not an address with an optional
+    /// prefix length that fits it.
+    pub fn trust_cidr(mut self, cidr: &str) -> Result<Self, InvalidCidr> {
+        let network =
+            Network::parse(cidr).ok_or_else(|| InvalidCidr(cidr.to_owned()))?;
+        if !self.networks.contains(&network) {
+            self.networks.push(network);
+        }
+        Ok(self)
+    }
+
+    /// Returns the number of trusted hops.
+    #[must_use]
+    pub const fn hop_count(&self) -> usize {
+        self.hops
+    }
+
+    /// Returns the forwarding headers that are read.
+    #[must_use]
+    pub const fn headers(&self) -> ForwardingHeaders {
+        self.headers
+    }
+
+    /// Describes the client of a request with `headers` and `uri`.
+    pub(super) fn client_info(
+        &self,
+        headers: &HeaderMap,
+        uri: &Uri,
+    ) -> ClientInfo {
+        let mut client = ClientInfo {
+            ip: None,
+            scheme: uri.scheme_str().unwrap_or("http").to_ascii_lowercase(),
+            host: uri
+                .authority()
+                .map(Authority::as_str)
+                .or_else(|| headers.get(HOST)?.to_str().ok())
+                .and_then(parse_host),
+        };
+        if self.hops == 0 {
+            return client;
+        }
+
+        let (hop, proto, host) = if self.headers == ForwardingHeaders::Forwarded
+        {
+            let elements = header_list(headers, FORWARDED)
+                .iter()
+                .map(|element| Element::parse(element))
+                .collect::<Vec<_>>();
+            let Some(position) =
+                self.client_position(elements.iter().map(|element| element.ip))
+            else {
+                return client;
+            };
+            let element = &elements[position];
+            (element.ip, element.proto.clone(), element.host.clone())
+        } else {
+            let hops = header_list(headers, X_FORWARDED_FOR);
+            let Some(position) = self
+                .client_position(hops.iter().map(|entry| parse_node(entry)))
+            else {
+                return client;
+            };
+            (
+                parse_node(&hops[position]),
+                self.x_forwarded(
+                    headers,
+                    X_FORWARDED_PROTO,
+                    position,
+                    hops.len(),
+                ),
+                self.x_forwarded(
+                    headers,
+                    X_FORWARDED_HOST,
+                    position,
+                    hops.len(),
+                ),
+            )
+        };
+
+        client.ip = hop;
+        if let Some(proto) = proto.as_deref().and_then(parse_proto) {
+            client.scheme = proto;
+        }
+        if let Some(host) = host.as_deref().and_then(parse_host) {
+            client.host = Some(host);
+        }
+        client
+    }
+
+    /// Returns the `name` value recorded for the client at `position` of
+    /// `count` hops, or `None` when the header cannot be attributed to a
+    /// trusted proxy.
+    fn x_forwarded(
+        &self,
+        headers: &HeaderMap,
+        name: &str,
+        position: usize,
+        count: usize,
+    ) -> Option<String> {
+        let values = header_list(headers, name);
+        match self.headers {
+            ForwardingHeaders::XForwarded if values.len() == count => {
+                values.into_iter().nth(position)
+            }
+            ForwardingHeaders::XForwardedOverwritten if values.len() == 1 => {
+                values.into_iter().next()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the client among `hops`, listed farthest first,
+    /// or `None` when there are none.
+    fn client_position(
+        &self,
+        hops: impl DoubleEndedIterator<Item = Option<IpAddr>> + ExactSizeIterator,
+    ) -> Option<usize> {
+        let count = hops.len();
+        let mut client = None;
+        for (from_nearest, ip) in hops.rev().enumerate() {
+            client = Some(count - 1 - from_nearest);
+            let is_proxy = from_nearest + 1 < self.hops
+                || ip.is_some_and(|ip| {
+                    self.networks.iter().any(|network| network.contains(ip))
+                });
+            if !is_proxy {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// The client of the current request, as reported by trusted proxies.
+///
+/// Provided as a context when
+/// [`HandlerConfig::with_trusted_proxies`](super::HandlerConfig::with_trusted_proxies)
+/// is set. Read it with `use_context::<ClientInfo>()` in a component or
+/// server function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClientInfo {
+    ip: Option<IpAddr>,
+    scheme: String,
+    host: Option<String>,
+}
+
+impl ClientInfo {
+    /// Returns the client's address, or `None` when the trusted hops did not
+    /// record one, or recorded it as `unknown` or an obfuscated identifier.
+    #[must_use]
+    pub const fn ip(&self) -> Option<IpAddr> {
+        self.ip
+    }
+
+    /// Returns the scheme the client used, `http` or `https`.
+    #[must_use]
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Returns the host and optional port the client addressed.
+    #[must_use]
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Returns the public origin the client addressed, normalized to
+    /// `scheme://host[:port]`.
+    #[must_use]
+    pub fn origin(&self) -> Option<String> {
+        let host = self.host.as_deref()?;
+        format!("{}://{host}", self.scheme)
+            .parse::<Uri>()
+            .ok()
+            .and_then(|uri| origin_of(&uri))
+    }
+
+    /// Returns the absolute URL of a request for `uri` on the client's
+    /// origin.
+    pub(super) fn request_url(&self, uri: &Uri) -> Option<RequestUrl> {
+        let path = uri
+            .path_and_query()
+            .map_or("/", http::uri::PathAndQuery::as_str);
+        Some(RequestUrl::new(&format!("{}{path}", self.origin()?)))
+    }
+}
+
+/// One element of a `Forwarded` header.
+#[derive(Debug, Default)]
+struct Element {
+    ip: Option<IpAddr>,
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+impl Element {
+    fn parse(element: &str) -> Self {
+        let mut parsed = Self::default();
+        for pair in split_unquoted(element, ';') {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let value = unquote(value.trim());
+            match name.trim().to_ascii_lowercase().as_str() {
+                "for" => parsed.ip = parse_node(value),
+                "proto" => parsed.proto = Some(value.to_owned()),
+                "host" => parsed.host = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+/// An IP network in CIDR notation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Network {
+    address: IpAddr,
+    prefix: u32,
+}
+
+impl Network {
+    fn parse(cidr: &str) -> Option<Self> {
+        let (address, prefix) = match cidr.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (cidr.trim(), None),
+        };
+        let address = address.parse::<IpAddr>().ok()?.to_canonical();
+        let bits = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => {
+                prefix.parse::<u32>().ok().filter(|&p| p <= bits)?
+            }
+            None => bits,
+        };
+        Some(Self { address, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.address, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask =
+                    u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Every comma-separated entry of every `name` header, in order.
+fn header_list(headers: &HeaderMap, name: &str) -> Vec<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| split_unquoted(value, ','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Splits `value` at `separator`, except inside a quoted string.
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, character) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted && character == '\\' {
+            escaped = true;
+        } else if character == '"' {
+            quoted = !quoted;
+        } else if !quoted && character == separator {
+            parts.push(&value[start..index]);
+            start = index + 1;
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Parses a node address: a bare address, `[v6]` or `v4` with an optional
+/// port. `unknown` and obfuscated identifiers have no address.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = unquote(node.trim());
+    if let Some(rest) = node.strip_prefix('[') {
+        let (address, _) = rest.split_once(']')?;
+        return address.parse().ok();
+    }
+    if let Ok(address) = node.parse() {
+        return Some(address);
+    }
+    let (address, _) = node.split_once(':')?;
+    address.parse::<std::net::Ipv4Addr>().ok().map(IpAddr::V4)
+}
+
+fn parse_proto(proto: &str) -> Option<String> {
+    let proto = proto.trim().to_ascii_lowercase();
+    matches!(proto.as_str(), "http" | "https").then_some(proto)
+}
+
+fn parse_host(host: &str) -> Option<String> {
+    let authority = host.trim().parse::<Authority>().ok()?;
+    (!authority.as_str().contains('@') && !authority.host().is_empty())
+        .then(|| authority.as_str().to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn client(
+        proxies: &TrustedProxies,
+        headers: &[(&'static str, &'static str)],
+    ) -> ClientInfo {
+        let mut map = HeaderMap::new();
+        for &(name, value) in headers {
+            map.append(name, HeaderValue::from_static(value));
+        }
+        let uri = Uri::from_static("http://ingress.internal:8080/page?x=1");
+        proxies.client_info(&map, &uri)
+    }
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().expect("test address should be valid")
+    }
+
+    #[test]
+    fn without_trusted_hops_the_request_describes_the_client() {
+        let info = client(
+            &TrustedProxies::hops(0),
+            &[
+                ("x-forwarded-for", "203.0.113.7"),
+                ("x-forwarded-proto", "https"),
+            ],
+        );
+
+        assert_eq!(info.ip(), None);
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), Some("ingress.internal:8080"));
+        assert_eq!(
+            info.origin().as_deref(),
+            Some("http://ingress.internal:8080")
+        );
+    }
+
+    #[test]
+    fn forwarded_is_read_from_the_nearest_trusted_element() {
+        let info = client(
+            &TrustedProxies::hops(1).with_headers(ForwardingHeaders::Forwarded),
+            &[(
+                "forwarded",
+                "for=198.51.100.1;proto=http, \
+                 for=\"[2001:db8::7]:4711\";proto=HTTPS;host=\"Shop.Example\"",
+            )],
+        );
+
+        assert_eq!(info.ip(), Some(ip("2001:db8::7")));
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), Some("shop.example"));
+        assert_eq!(info.origin().as_deref(), Some("https://shop.example"));
+    }
+
+    #[test]
+    fn the_unselected_header_family_is_ignored() {
+        let headers = [
+            ("forwarded", "for=1.2.3.4;proto=https;host=evil.example"),
+            ("x-forwarded-for", "203.0.113.7"),
+        ];
+
+        let info = client(&TrustedProxies::hops(1), &headers);
+        assert_eq!(info.ip(), Some(ip("203.0.113.7")));
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), Some("ingress.internal:8080"));
+
+        let info = client(
+            &TrustedProxies::hops(1).with_headers(ForwardingHeaders::Forwarded),
+            &[("x-forwarded-for", "203.0.113.7")],
+        );
+        assert_eq!(info.ip(), None);
+    }
+
+    #[test]
+    fn spoofed_entries_beyond_the_trusted_hops_are_ignored() {
+        let headers = [
+            ("x-forwarded-for", "6.6.6.6, 203.0.113.7, 10.1.2.3"),
+            ("x-forwarded-proto", "gopher, https, http"),
+            ("x-forwarded-host", "evil.example, shop.example, ingress"),
+        ];
+
+        let info = client(&TrustedProxies::hops(2), &headers);
+        assert_eq!(info.ip(), Some(ip("203.0.113.7")));
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), Some("shop.example"));
+
+        let info = client(&TrustedProxies::hops(1), &headers);
+        assert_eq!(info.ip(), Some(ip("10.1.2.3")));
+        assert_eq!(info.host(), Some("ingress"));
+    }
+
+    #[test]
+    fn a_client_sent_host_is_not_taken_for_the_proxy_s() {
+        // The ingress appends to `X-Forwarded-For` but never writes
+        // `X-Forwarded-Host`, so the only value is the client's own.
+        let headers = [
+            ("x-forwarded-for", "6.6.6.6, 203.0.113.7"),
+            ("x-forwarded-host", "evil.example"),
+        ];
+
+        let info = client(&TrustedProxies::hops(1), &headers);
+        assert_eq!(info.ip(), Some(ip("203.0.113.7")));
+        assert_eq!(info.host(), Some("ingress.internal:8080"));
+
+        // An ingress declared to overwrite the header is believed when it
+        // leaves exactly one value.
+        let overwritten = TrustedProxies::hops(1)
+            .with_headers(ForwardingHeaders::XForwardedOverwritten);
+        let info = client(
+            &overwritten,
+            &[
+                ("x-forwarded-for", "6.6.6.6, 203.0.113.7"),
+                ("x-forwarded-host", "shop.example"),
+            ],
+        );
+        assert_eq!(info.host(), Some("shop.example"));
+        let info = client(
+            &overwritten,
+            &[
+                ("x-forwarded-for", "203.0.113.7"),
+                ("x-forwarded-host", "evil.example, shop.example"),
+            ],
+        );
+        assert_eq!(info.host(), Some("ingress.internal:8080"));
+    }
+
+    #[test]
+    fn trusted_networks_are_skipped_as_proxies() {
+        let proxies = TrustedProxies::hops(1)
+            .trust_cidr("10.0.0.0/8")
+            .expect("the network is valid");
+
+        let info = client(
+            &proxies,
+            &[(
+                "x-forwarded-for",
+                "6.6.6.6, 203.0.113.7, 10.9.9.9, 10.1.2.3",
+            )],
+        );
+        assert_eq!(info.ip(), Some(ip("203.0.113.7")));
+
+        let info =
+            client(&proxies, &[("x-forwarded-for", "10.9.9.9:1234, 10.1.2.3")]);
+        assert_eq!(info.ip(), Some(ip("10.9.9.9")));
+    }
+
+    #[test]
+    fn unusable_forwarded_values_fall_back_to_the_request() {
+        let info = client(
+            &TrustedProxies::hops(1).with_headers(ForwardingHeaders::Forwarded),
+            &[("forwarded", "for=unknown;proto=gopher;host=\"a@b\"")],
+        );
+
+        assert_eq!(info.ip(), None);
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), Some("ingress.internal:8080"));
+    }
+
+    #[test]
+    fn the_request_url_is_absolute_on_the_client_origin() {
+        let info = client(
+            &TrustedProxies::hops(1).with_headers(ForwardingHeaders::Forwarded),
+            &[(
+                "forwarded",
+                "for=192.0.2.60;proto=https;host=shop.example:443",
+            )],
+        );
+        let url = info
+            .request_url(&Uri::from_static("/cart?step=2"))
+            .expect("the origin is known");
+
+        assert_eq!(url.as_ref(), "https://shop.example/cart?step=2");
+        let url = url.parse().expect("the URL is valid");
+        assert_eq!(url.origin(), "https://shop.example");
+        assert_eq!(url.path(), "/cart");
+    }
+
+    #[test]
+    fn networks_parse_and_match_by_prefix() {
+        let network = Network::parse("192.0.2.0/24").expect("valid network");
+        assert!(network.contains(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 200))));
+        assert!(!network.contains(IpAddr::V4(Ipv4Addr::new(192, 0, 3, 1))));
+        assert!(network.contains("::ffff:192.0.2.1".parse().expect("valid")));
+
+        let everything = Network::parse("::/0").expect("valid network");
+        assert!(everything.contains("2001:db8::1".parse().expect("valid")));
+
+        for invalid in ["10.0.0.0/33", "example.com/8", "10.0.0.0/x"] {
+            assert_eq!(
+                TrustedProxies::hops(1).trust_cidr(invalid),
+                Err(InvalidCidr(invalid.to_owned()))
+            );
+        }
+    }
+}
@@ -0,0 +1,691 @@
+//! Opt-in response compression.
+//!
+//! [`HandlerCore::render`](super::core::HandlerCore) hands every response to
+//! [`compress_response`] once, after the application and the static handler
+//! have finished with it. Streaming bodies are compressed chunk by chunk and
+//! flushed after each one, so an out-of-order SSR stream still reaches the
+//! browser as it is produced.
+
+use std::io::{self, Write};
+
+use brotli::CompressorWriter;
+use bytes::Bytes;
+use flate2::{
+    Compression,
+    write::{GzEncoder, ZlibEncoder},
+};
+use futures::StreamExt;
+use http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{
+        ACCEPT_RANGES, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH,
+        CONTENT_RANGE, CONTENT_TYPE, ETAG, VARY,
+    },
+};
+
+use super::content_coding::coding_quality;
+use crate::{
+    integration::PinnedStream,
+    response::{Body, Response},
+};
+
+/// The smallest body compressed by default, in bytes.
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// Brotli quality for on-the-fly compression. The maximum, 11, is meant for
+/// build-time precompression and is far too slow per request.
+const BROTLI_QUALITY: u32 = 5;
+
+/// Brotli window size, as a base-2 logarithm.
+const BROTLI_WINDOW: u32 = 22;
+
+/// Response compression settings, enabled through
+/// [`HandlerConfig::with_response_compression`](super::HandlerConfig::with_response_compression).
+///
+/// A response is compressed when the request's `Accept-Encoding` accepts one
+/// of the enabled codings and the response
+///
+/// - has a compressible `Content-Type`: `text/*`, JavaScript, JSON, XML,
+///   WebAssembly, or a `+json` or `+xml` type;
+/// - is not already encoded and has no `Cache-Control: no-transform`;
+/// - is not a `204`, `206`, or `304`;
+/// - is at least [`Self::with_min_size`] bytes, when its length is known.
+///
+/// Such a response carries `Vary: Accept-Encoding` whether or not this client
+/// accepted a coding. Its `ETag` is weakened, since the compressed bytes are
+/// a different representation, and `Content-Length` and `Accept-Ranges` are
+/// dropped for a streaming body. Brotli is preferred, then gzip, then
+/// deflate, unless the client's quality values say otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, ResponseCompression};
+///
+/// let config = HandlerConfig::default().with_response_compression(
+///     ResponseCompression::default()
+///         .without_deflate()
+///         .with_min_size(2048),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResponseCompression {
+    brotli: bool,
+    gzip: bool,
+    deflate: bool,
+    min_size: usize,
+}
+
+impl Default for ResponseCompression {
+    fn default() -> Self {
+        Self {
+            brotli: true,
+            gzip: true,
+            deflate: true,
+            min_size: DEFAULT_MIN_SIZE,
+        }
+    }
+}
+
+impl ResponseCompression {
+    /// Returns a copy that never answers with `br`.
+    #[must_use]
+    pub const fn without_brotli(mut self) -> Self {
+        self.brotli = false;
+        self
+    }
+
+    /// Returns a copy that never answers with `gzip`.
+    #[must_use]
+    pub const fn without_gzip(mut self) -> Self {
+        self.gzip = false;
+        self
+    }
+
+    /// Returns a copy that never answers with `deflate`.
+    #[must_use]
+    pub const fn without_deflate(mut self) -> Self {
+        self.deflate = false;
+        self
+    }
+
+    /// Returns a copy that leaves bodies shorter than `bytes` uncompressed.
+    ///
+    /// The default is 1 KiB, below which the coding's framing and the CPU
+    /// spent outweigh the bytes saved. A streaming body of unknown length is
+    /// always eligible.
+    #[must_use]
+    pub const fn with_min_size(mut self, bytes: usize) -> Self {
+        self.min_size = bytes;
+        self
+    }
+
+    /// The enabled coding this request accepts with the highest quality, in
+    /// server preference order among equals.
+    pub(super) fn negotiate(self, headers: &HeaderMap) -> Option<Coding> {
+        let mut best: Option<(Coding, u16)> = None;
+        for coding in [Coding::Brotli, Coding::Gzip, Coding::Deflate] {
+            let enabled = match coding {
+                Coding::Brotli => self.brotli,
+                Coding::Gzip => self.gzip,
+                Coding::Deflate => self.deflate,
+            };
+            let quality =
+                coding_quality(headers, coding.token(), coding.aliases())
+                    .filter(|&quality| enabled && quality > 0);
+            if let Some(quality) = quality
+                && best.is_none_or(|(_, best)| quality > best)
+            {
+                best = Some((coding, quality));
+            }
+        }
+        best.map(|(coding, _)| coding)
+    }
+}
+
+/// A content coding the handler can apply on the fly.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Coding {
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl Coding {
+    /// Returns the coding `token` names, matching aliases and ignoring case.
+    pub(super) fn from_token(token: &str) -> Option<Self> {
+        [Self::Brotli, Self::Gzip, Self::Deflate]
+            .into_iter()
+            .find(|coding| {
+                token.eq_ignore_ascii_case(coding.token())
+                    || coding
+                        .aliases()
+                        .iter()
+                        .any(|alias| token.eq_ignore_ascii_case(alias))
+            })
+    }
+
+    const fn token(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    const fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Self::Gzip => &["x-gzip"],
+            Self::Brotli | Self::Deflate => &[],
+        }
+    }
+}
+
+/// Compresses `response` with `coding` when it is eligible under `config`,
+/// and marks every eligible response as varying on `Accept-Encoding`.
+///
+/// `coding` is what the request negotiated, or `None` when it accepts none
+/// of the enabled codings. A `HEAD` response receives the headers its `GET`
+/// would have, without compressing a body nobody will read.
+pub(super) fn compress_response(
+    response: &mut Response,
+    config: ResponseCompression,
+    coding: Option<Coding>,
+    is_head: bool,
+) {
+    let response = &mut response.0;
+    if !is_compressible(response.status(), response.headers()) {
+        return;
+    }
+    // A declared length also describes the `GET` body a `HEAD` stands for.
+    let known_length = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .or(match response.body() {
+            Body::Sync(bytes) => Some(bytes.len()),
+            Body::Async(_) => None,
+        });
+    if known_length.is_some_and(|length| length < config.min_size) {
+        return;
+    }
+    add_vary(response.headers_mut());
+    let Some(coding) = coding else {
+        return;
+    };
+
+    if !is_head {
+        let body =
+            std::mem::replace(response.body_mut(), Body::Sync(Bytes::new()));
+        let body = match body {
+            Body::Sync(bytes) => {
+                let Ok(encoded) = encode_all(coding, &bytes) else {
+                    // An in-memory encoder has nowhere to fail; should one
+                    // anyway, the identity response is still correct.
+                    *response.body_mut() = Body::Sync(bytes);
+                    return;
+                };
+                Body::Sync(encoded)
+            }
+            Body::Async(stream) => Body::Async(encode_stream(coding, stream)),
+        };
+        *response.body_mut() = body;
+    }
+
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_ENCODING, HeaderValue::from_static(coding.token()));
+    // `render` recomputes the length of a synchronous body; a stream's
+    // declared length described the identity bytes.
+    headers.remove(CONTENT_LENGTH);
+    headers.remove(ACCEPT_RANGES);
+    if let Some(etag) = headers.get(ETAG)
+        && etag.as_bytes().starts_with(b"\"")
+        && let Ok(weak) =
+            HeaderValue::from_bytes(&[b"W/", etag.as_bytes()].concat())
+    {
+        headers.insert(ETAG, weak);
+    }
+}
+
+/// Whether a response's status and headers allow compressing its body.
+fn is_compressible(status: StatusCode, headers: &HeaderMap) -> bool {
+    if status.is_informational()
+        || matches!(
+            status,
+            StatusCode::NO_CONTENT
+                | StatusCode::PARTIAL_CONTENT
+                | StatusCode::NOT_MODIFIED
+        )
+        || headers.contains_key(CONTENT_ENCODING)
+        || headers.contains_key(CONTENT_RANGE)
+    {
+        return false;
+    }
+    let no_transform = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"));
+    !no_transform
+        && headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(is_compressible_type)
+}
+
+/// Whether a `Content-Type` names a format that is not already compressed.
+fn is_compressible_type(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    media_type.starts_with("text/")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type.as_str(),
+            "application/javascript"
+                | "application/x-javascript"
+                | "application/json"
+                | "application/xml"
+                | "application/wasm"
+        )
+}
+
+/// Adds `accept-encoding` to `Vary` unless it is already covered.
+fn add_vary(headers: &mut HeaderMap) {
+    let covered = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|field| {
+            field == "*" || field.eq_ignore_ascii_case("accept-encoding")
+        });
+    if !covered {
+        headers.append(VARY, HeaderValue::from_static("accept-encoding"));
+    }
+}
+
+/// An encoder writing into a buffer the caller drains after each chunk.
+enum Encoder {
+    Brotli(Box<CompressorWriter<Vec<u8>>>),
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(coding: Coding) -> Self {
+        match coding {
+            Coding::Brotli => Self::Brotli(Box::new(CompressorWriter::new(
+                Vec::new(),
+                4096,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            ))),
+            Coding::Gzip => {
+                Self::Gzip(GzEncoder::new(Vec::new(), Compression::default()))
+            }
+            // HTTP's `deflate` is the zlib format of RFC 1950, not a raw
+            // deflate stream.
+            Coding::Deflate => Self::Deflate(ZlibEncoder::new(
+                Vec::new(),
+                Compression::default(),
+            )),
+        }
+    }
+
+    /// Compresses `chunk` and flushes, returning every byte produced so far
+    /// so the receiver can decode the chunk without waiting for the next.
+    fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        let buffer = match self {
+            Self::Brotli(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            Self::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            Self::Deflate(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+        };
+        Ok(Bytes::from(std::mem::take(buffer)))
+    }
+
+    /// Ends the stream, returning the remaining bytes and trailer.
+    fn finish(self) -> io::Result<Bytes> {
+        let buffer = match self {
+            Self::Brotli(encoder) => encoder.into_inner(),
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Deflate(encoder) => encoder.finish()?,
+        };
+        Ok(Bytes::from(buffer))
+    }
+}
+
+fn encode_all(coding: Coding, bytes: &[u8]) -> io::Result<Bytes> {
+    let mut encoder = Encoder::new(coding);
+    let head = encoder.write_chunk(bytes)?;
+    let tail = encoder.finish()?;
+    Ok([head, tail].concat().into())
+}
+
+fn encode_stream(
+    coding: Coding,
+    mut stream: PinnedStream<Result<Bytes, throw_error::Error>>,
+) -> PinnedStream<Result<Bytes, throw_error::Error>> {
+    Box::pin(async_stream::stream! {
+        let mut encoder = Encoder::new(coding);
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(error) => {
+                    yield Err(error);
+                    return;
+                }
+            };
+            // Flushing an empty chunk would only emit an empty block.
+            if chunk.is_empty() {
+                continue;
+            }
+            match encoder.write_chunk(&chunk) {
+                Ok(output) => yield Ok(output),
+                Err(error) => {
+                    yield Err(error.into());
+                    return;
+                }
+            }
+        }
+        match encoder.finish() {
+            Ok(output) => yield Ok(output),
+            Err(error) => yield Err(error.into()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use futures::stream;
+    use http::{Method, Request};
+    use leptos::prelude::view;
+
+    use super::super::core::HandlerCore;
+    use super::super::policy::HandlerConfig;
+    use super::*;
+
+    const TEXT: &str = "<p>compressible compressible compressible</p>";
+
+    fn accepting(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::ACCEPT_ENCODING,
+            HeaderValue::from_static(value),
+        );
+        headers
+    }
+
+    fn html_response(body: Body) -> Response {
+        let mut response = http::Response::new(body);
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        response
+            .headers_mut()
+            .insert(ETAG, HeaderValue::from_static("\"v1\""));
+        response.into()
+    }
+
+    fn large_text() -> Bytes {
+        Bytes::from(TEXT.repeat(64))
+    }
+
+    fn decode(coding: Coding, bytes: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        match coding {
+            Coding::Brotli => {
+                brotli::Decompressor::new(bytes, 4096)
+                    .read_to_end(&mut decoded)
+                    .expect("brotli output should decode");
+            }
+            Coding::Gzip => {
+                flate2::read::GzDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .expect("gzip output should decode");
+            }
+            Coding::Deflate => {
+                flate2::read::ZlibDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .expect("deflate output should decode");
+            }
+        }
+        decoded
+    }
+
+    fn sync_body(response: &Response) -> Option<&Bytes> {
+        match response.0.body() {
+            Body::Sync(bytes) => Some(bytes),
+            Body::Async(_) => None,
+        }
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+        response
+            .0
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    #[test]
+    fn negotiation_follows_quality_then_server_preference() {
+        let config = ResponseCompression::default();
+        let cases = [
+            ("gzip, deflate, br", Some(Coding::Brotli)),
+            ("gzip;q=1, br;q=0.5", Some(Coding::Gzip)),
+            ("x-gzip", Some(Coding::Gzip)),
+            ("deflate", Some(Coding::Deflate)),
+            ("*", Some(Coding::Brotli)),
+            ("identity", None),
+            ("br;q=0", None),
+        ];
+        for (accept, expected) in cases {
+            assert_eq!(
+                config.negotiate(&accepting(accept)),
+                expected,
+                "{accept}"
+            );
+        }
+        assert_eq!(
+            config
+                .without_brotli()
+                .negotiate(&accepting("br, gzip;q=0.1")),
+            Some(Coding::Gzip)
+        );
+        assert_eq!(config.negotiate(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn synchronous_bodies_round_trip_through_each_coding() {
+        for coding in [Coding::Brotli, Coding::Gzip, Coding::Deflate] {
+            let mut response = html_response(Body::Sync(large_text()));
+            compress_response(
+                &mut response,
+                ResponseCompression::default(),
+                Some(coding),
+                false,
+            );
+
+            assert_eq!(
+                header(&response, "content-encoding"),
+                Some(coding.token())
+            );
+            assert_eq!(header(&response, "vary"), Some("accept-encoding"));
+            assert_eq!(header(&response, "etag"), Some("W/\"v1\""));
+            let bytes = sync_body(&response)
+                .expect("a synchronous body should stay synchronous");
+            assert!(bytes.len() < large_text().len(), "{coding:?}");
+            assert_eq!(decode(coding, bytes), large_text(), "{coding:?}");
+        }
+    }
+
+    #[test]
+    fn ineligible_responses_are_left_alone() {
+        type Adjust = fn(&mut Response);
+        let cases: [(&str, Adjust); 5] = [
+            ("tiny", |response| {
+                *response.0.body_mut() = Body::Sync(Bytes::from_static(b"hi"));
+            }),
+            ("image", |response| {
+                response.0.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("image/png"),
+                );
+            }),
+            ("encoded", |response| {
+                response
+                    .0
+                    .headers_mut()
+                    .insert(CONTENT_ENCODING, HeaderValue::from_static("br"));
+            }),
+            ("no-transform", |response| {
+                response.0.headers_mut().insert(
+                    CACHE_CONTROL,
+                    HeaderValue::from_static("public, no-transform"),
+                );
+            }),
+            ("partial", |response| {
+                *response.0.status_mut() = StatusCode::PARTIAL_CONTENT;
+            }),
+        ];
+        for (case, adjust) in cases {
+            let mut response = html_response(Body::Sync(large_text()));
+            adjust(&mut response);
+            compress_response(
+                &mut response,
+                ResponseCompression::default(),
+                Some(Coding::Gzip),
+                false,
+            );
+
+            assert_ne!(
+                header(&response, "content-encoding"),
+                Some("gzip"),
+                "{case}"
+            );
+            assert_eq!(header(&response, "vary"), None, "{case}");
+            assert_eq!(header(&response, "etag"), Some("\"v1\""), "{case}");
+        }
+    }
+
+    #[test]
+    fn eligible_responses_vary_even_when_sent_as_identity() {
+        let mut response = html_response(Body::Sync(large_text()));
+        compress_response(
+            &mut response,
+            ResponseCompression::default(),
+            None,
+            false,
+        );
+
+        assert_eq!(header(&response, "vary"), Some("accept-encoding"));
+        assert_eq!(header(&response, "content-encoding"), None);
+        assert_eq!(header(&response, "etag"), Some("\"v1\""));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn streaming_bodies_flush_every_chunk() {
+        let chunks = ["<p>first</p>", "<p>second</p>"]
+            .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())));
+        let mut response =
+            html_response(Body::Async(Box::pin(stream::iter(chunks))));
+        compress_response(
+            &mut response,
+            ResponseCompression::default(),
+            Some(Coding::Gzip),
+            false,
+        );
+        let mut stream = match std::mem::replace(
+            response.0.body_mut(),
+            Body::Sync(Bytes::new()),
+        ) {
+            Body::Async(stream) => Some(stream),
+            Body::Sync(_) => None,
+        }
+        .expect("a streaming body should stay streaming");
+
+        // A write-side decoder decodes whatever has arrived so far, which is
+        // exactly what a browser does with a flushed chunk.
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+        let first = stream
+            .next()
+            .await
+            .expect("the first chunk should be emitted")
+            .expect("the first chunk should encode");
+        decoder.write_all(&first).expect("the chunk should decode");
+        decoder.flush().expect("the decoder should flush");
+        assert_eq!(decoder.get_ref().as_slice(), b"<p>first</p>");
+
+        while let Some(chunk) = stream.next().await {
+            decoder
+                .write_all(&chunk.expect("later chunks should encode"))
+                .expect("later chunks should decode");
+        }
+        assert_eq!(
+            decoder.finish().expect("the stream should be complete"),
+            b"<p>first</p><p>second</p>"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn render_compresses_when_configured() {
+        let serve = |method: Method| {
+            HandlerCore::new(
+                Request::builder()
+                    .method(method)
+                    .uri("/static/app.js")
+                    .header(http::header::ACCEPT_ENCODING, "gzip")
+                    .body(Bytes::new())
+                    .expect("test request should be valid"),
+                HandlerConfig::default()
+                    .with_response_compression(ResponseCompression::default()),
+            )
+            .static_files_handler("/static", |_| {
+                Some(Body::Sync(Bytes::from("console.log(1);\n".repeat(128))))
+            })
+            .expect("static registration should succeed")
+            .render(|| view! { "unused" }, || {})
+        };
+
+        let response = serve(Method::GET).await;
+        assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+        assert_eq!(header(&response, "accept-ranges"), None);
+        let bytes = sync_body(&response)
+            .expect("a static body should stay synchronous");
+        assert_eq!(
+            header(&response, "content-length"),
+            Some(bytes.len().to_string().as_str())
+        );
+        assert_eq!(
+            decode(Coding::Gzip, bytes),
+            "console.log(1);\n".repeat(128).as_bytes()
+        );
+
+        let head = serve(Method::HEAD).await;
+        assert_eq!(header(&head, "content-encoding"), Some("gzip"));
+        assert!(matches!(head.0.body(), Body::Sync(bytes) if bytes.is_empty()));
+    }
+}
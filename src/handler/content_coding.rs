@@ -0,0 +1,126 @@
+//! `Accept-Encoding` parsing.
+//!
+//! A request states which content codings it accepts, each with an optional
+//! quality value. This module only reads that preference; deciding which
+//! coding a response actually uses is up to the caller, which knows what it
+//! has available.
+
+use http::{HeaderMap, header::ACCEPT_ENCODING};
+
+/// The quality of one content coding for this request, in thousandths.
+///
+/// Returns `None` when the request has no `Accept-Encoding` at all, which
+/// this crate answers with the unencoded representation, and `Some(0)` when
+/// the coding is absent from the list or explicitly refused. An explicit
+/// entry wins over `*`. `aliases` are matched like `coding`, for the `x-`
+/// forms RFC 9110 section 8.4.1.3 asks recipients to treat as equivalent.
+pub(super) fn coding_quality(
+    headers: &HeaderMap,
+    coding: &str,
+    aliases: &[&str],
+) -> Option<u16> {
+    let mut values = headers.get_all(ACCEPT_ENCODING).iter().peekable();
+    values.peek()?;
+    let mut explicit = None;
+    let mut wildcard = None;
+    for entry in values
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+    {
+        let mut params = entry.split(';');
+        let name = params.next().unwrap_or_default().trim();
+        if name.is_empty() {
+            continue;
+        }
+        let mut quality = 1_000;
+        for param in params {
+            if let Some((key, value)) = param.split_once('=')
+                && key.trim().eq_ignore_ascii_case("q")
+            {
+                // An unparseable weight makes the entry unusable rather than
+                // silently preferred.
+                quality = parse_quality(value.trim()).unwrap_or(0);
+            }
+        }
+        if name == "*" {
+            wildcard = Some(quality);
+        } else if name.eq_ignore_ascii_case(coding)
+            || aliases.iter().any(|alias| name.eq_ignore_ascii_case(alias))
+        {
+            explicit =
+                Some(explicit.map_or(quality, |seen: u16| seen.max(quality)));
+        }
+    }
+    Some(explicit.or(wildcard).unwrap_or(0))
+}
+
+/// Parses a `qvalue`: `0` or `1` with up to three decimals, at most `1`.
+fn parse_quality(value: &str) -> Option<u16> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 3 || !fraction.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut thousandths = fraction
+        .bytes()
+        .fold(0_u16, |total, digit| total * 10 + u16::from(digit - b'0'));
+    for _ in fraction.len()..3 {
+        thousandths *= 10;
+    }
+    match whole {
+        "0" => Some(thousandths),
+        "1" if thousandths == 0 => Some(1_000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn quality(accept: &[&'static str], coding: &str) -> Option<u16> {
+        let mut headers = HeaderMap::new();
+        for value in accept {
+            headers.append(ACCEPT_ENCODING, HeaderValue::from_static(value));
+        }
+        coding_quality(&headers, coding, &["x-gzip"])
+    }
+
+    #[test]
+    fn reads_explicit_qualities() {
+        let accept = ["gzip;q=0.5, br, zstd;q=0"];
+        assert_eq!(quality(&accept, "br"), Some(1_000));
+        assert_eq!(quality(&accept, "gzip"), Some(500));
+        assert_eq!(quality(&accept, "zstd"), Some(0));
+        assert_eq!(quality(&accept, "deflate"), Some(0));
+    }
+
+    #[test]
+    fn wildcards_cover_only_unlisted_codings() {
+        let accept = ["*;q=0.3, br;q=0"];
+        assert_eq!(quality(&accept, "gzip"), Some(300));
+        assert_eq!(quality(&accept, "br"), Some(0));
+    }
+
+    #[test]
+    fn combines_repeated_fields_and_aliases() {
+        assert_eq!(quality(&["br", "X-GZIP;Q=0.25"], "gzip"), Some(250));
+    }
+
+    #[test]
+    fn absent_and_empty_headers_differ() {
+        assert_eq!(quality(&[], "gzip"), None);
+        assert_eq!(quality(&[""], "gzip"), Some(0));
+    }
+
+    #[test]
+    fn malformed_weights_refuse_the_coding() {
+        for value in ["gzip;q=2", "gzip;q=1.5", "gzip;q=0.1234", "gzip;q=x"] {
+            assert_eq!(quality(&[value], "gzip"), Some(0), "{value}");
+        }
+        assert_eq!(quality(&["gzip;q=1.000"], "gzip"), Some(1_000));
+        assert_eq!(quality(&["gzip;q=0.05"], "gzip"), Some(50));
+    }
+}
@@ -11,39 +11,44 @@
 use std::time::Instant;
 
 use bytes::Bytes;
-use http::{
-    HeaderValue, Method, Request, StatusCode, Uri,
-    header::{ALLOW, CONTENT_LENGTH, CONTENT_TYPE},
-};
+use http::{HeaderValue, Method, Request, StatusCode, Uri, header::ALLOW};
 use leptos::IntoView;
 use leptos_router::RouteListing;
-use mime_guess::MimeGuess;
 use routefinder::Router;
 use server_fn::{
     Protocol, ServerFn, error::FromServerFnError, middleware::BoxedService,
 };
 
-use super::policy::{HandlerConfig, RegistrationError, plain_response};
+use super::body_stream::{BodyError, StreamingBody};
+use super::error_pages::{ErrorCause, error_response};
+use super::policy::{
+    HandlerConfig, RegistrationError, RequestPolicyError, policy_response,
+};
 use super::routes::validated_route_table;
 use super::server_fns::{
     ReqBody, ResBody, ServerFnHandler, TypedServerFnService,
 };
+use super::static_assets::{directory_redirect, route_response};
 #[cfg(feature = "tracing")]
 use super::trace::RequestTrace;
 use super::trace::TraceHandle;
+#[cfg(feature = "tracing")]
+use super::trace::trace_policy_rejection;
 use crate::{
-    __private::ServerWithBody,
+    __private::{FromChunkStream, ServerWithBody},
     response::{Body, Response},
+    static_files::{StaticAsset, StaticRouteOptions},
 };
 
 pub(super) struct HandlerCore {
     pub(super) req: Request<Bytes>,
     pub(super) server_fn: Option<ServerFnHandler>,
     pub(super) preset_res: Option<Response>,
+    pub(super) streaming_body: Option<StreamingBody>,
     pub(super) should_404: bool,
     pub(super) ssr_router: Router<RouteListing>,
     routes_registered: bool,
-    config: HandlerConfig,
+    pub(super) config: HandlerConfig,
     #[cfg(feature = "tracing")]
     pub(super) request_started: Instant,
     #[cfg(feature = "tracing")]
@@ -53,11 +58,15 @@ pub(super) struct HandlerCore {
 }
 
 impl HandlerCore {
-    pub(super) fn new(req: Request<Bytes>, config: HandlerConfig) -> Self {
+    pub(super) fn new(mut req: Request<Bytes>, config: HandlerConfig) -> Self {
+        if let Some(policy) = config.request_ids() {
+            policy.resolve(req.headers_mut());
+        }
         Self {
             req,
             server_fn: None,
             preset_res: None,
+            streaming_body: None,
             should_404: false,
             ssr_router: Router::new(),
             routes_registered: false,
@@ -92,6 +101,13 @@ impl HandlerCore {
         self
     }
 
+    /// Hands the unread request body to a matching server function, instead
+    /// of the empty buffered body the request carries.
+    pub(super) fn with_streaming_body(mut self, body: StreamingBody) -> Self {
+        self.streaming_body = Some(body);
+        self
+    }
+
     #[cfg(feature = "tracing")]
     pub(super) fn request_trace(&self, preview: &'static str) -> TraceHandle {
         RequestTrace::new(self, preview)
@@ -118,7 +134,7 @@ impl HandlerCore {
         T: ServerFn + 'static,
         T::Server:
             ServerWithBody<T::Error, T::InputStreamError, T::OutputStreamError>,
-        ReqBody<T>: From<Bytes> + Send + 'static,
+        ReqBody<T>: FromChunkStream + Send + 'static,
         ResBody<T>: Into<Body> + Send + 'static,
     {
         if self.shortcut() {
@@ -136,22 +152,34 @@ impl HandlerCore {
         >>::METHOD;
 
         if self.req.method() == method && self.req.uri().path() == T::PATH {
-            let limit = self.config.max_request_body_size();
-            self.server_fn = Some(Box::new(move |request| {
+            let limit = self.config.buffered_body_limit();
+            self.server_fn = Some(Box::new(move |request, streaming| {
                 Box::pin(async move {
                     let (parts, bytes) = request.into_parts();
                     if bytes.len() > limit {
-                        return plain_response(
-                            StatusCode::PAYLOAD_TOO_LARGE,
-                            format!(
-                                "request body exceeds limit of {limit} bytes"
-                            ),
+                        return policy_response(
+                            &RequestPolicyError::BodyTooLarge { limit },
                         )
                         .0;
                     }
 
-                    let request =
-                        Request::from_parts(parts, ReqBody::<T>::from(bytes));
+                    let preview =
+                        streaming.as_ref().map_or("", StreamingBody::preview);
+                    let (body, breach) = match streaming {
+                        None => (ReqBody::<T>::from(bytes), None),
+                        Some(streaming) => {
+                            match streaming.into_body::<ReqBody<T>>().await {
+                                Ok((body, breach)) => (body, Some(breach)),
+                                Err(BodyError::Policy(error)) => {
+                                    return rejected_stream(preview, &error);
+                                }
+                                Err(BodyError::Io(error)) => {
+                                    return unreadable_stream(preview, &error);
+                                }
+                            }
+                        }
+                    };
+                    let request = Request::from_parts(parts, body);
                     let mut service = BoxedService::new(
                         |error| T::Error::from_server_fn_error(error).ser(),
                         TypedServerFnService::<T>::default(),
@@ -159,7 +187,14 @@ impl HandlerCore {
                     for middleware in T::middlewares() {
                         service = middleware.layer(service);
                     }
-                    service.run(request).await.map(Into::into)
+                    let response = service.run(request).await.map(Into::into);
+                    // The server function saw its input end early, and
+                    // whatever it answered with is replaced by the status a
+                    // buffered body would have received.
+                    match breach.and_then(|breach| breach.lock().take()) {
+                        Some(error) => rejected_stream(preview, &error),
+                        None => response,
+                    }
                 })
             }));
         }
@@ -168,10 +203,58 @@ impl HandlerCore {
     }
 
     pub(super) fn static_files_handler<T>(
-        mut self,
+        self,
+        prefix: T,
+        handler: impl Fn(String) -> Option<Body> + 'static + Send + Clone,
+    ) -> Result<Self, RegistrationError>
+    where
+        T: TryInto<Uri>,
+        <T as TryInto<Uri>>::Error: std::error::Error,
+    {
+        self.static_files_handler_with_options(
+            prefix,
+            &StaticRouteOptions::default(),
+            handler,
+        )
+    }
+
+    pub(super) fn static_files_handler_with_options<T>(
+        self,
         prefix: T,
+        options: &StaticRouteOptions,
         handler: impl Fn(String) -> Option<Body> + 'static + Send + Clone,
     ) -> Result<Self, RegistrationError>
+    where
+        T: TryInto<Uri>,
+        <T as TryInto<Uri>>::Error: std::error::Error,
+    {
+        self.static_assets_handler_with_options(prefix, options, move |path| {
+            handler(path).map(StaticAsset::new)
+        })
+    }
+
+    pub(super) fn static_assets_handler<T>(
+        self,
+        prefix: T,
+        handler: impl Fn(String) -> Option<StaticAsset> + 'static + Send + Clone,
+    ) -> Result<Self, RegistrationError>
+    where
+        T: TryInto<Uri>,
+        <T as TryInto<Uri>>::Error: std::error::Error,
+    {
+        self.static_assets_handler_with_options(
+            prefix,
+            &StaticRouteOptions::default(),
+            handler,
+        )
+    }
+
+    pub(super) fn static_assets_handler_with_options<T>(
+        mut self,
+        prefix: T,
+        options: &StaticRouteOptions,
+        handler: impl Fn(String) -> Option<StaticAsset> + 'static + Send + Clone,
+    ) -> Result<Self, RegistrationError>
     where
         T: TryInto<Uri>,
         <T as TryInto<Uri>>::Error: std::error::Error,
@@ -212,9 +295,10 @@ impl HandlerCore {
         }
 
         if !matches!(self.req.method(), &Method::GET | &Method::HEAD) {
-            let mut response = plain_response(
+            let mut response = error_response(
                 StatusCode::METHOD_NOT_ALLOWED,
                 "method not allowed",
+                ErrorCause::MethodNotAllowed,
             );
             response
                 .0
@@ -230,6 +314,15 @@ impl HandlerCore {
         } else {
             stripped.strip_prefix('/').unwrap_or(stripped)
         };
+        if req_path == prefix_path
+            && !prefix_path.ends_with('/')
+            && options.directory_redirect()
+            && let Some(response) = directory_redirect(&self.req)
+        {
+            self.preset_res = Some(response);
+            return Ok(self);
+        }
+        let directory = raw.is_empty() || req_path.ends_with('/');
         let Ok(decoded) = crate::static_files::normalize_static_path(raw)
         else {
             self.should_404 = true;
@@ -247,36 +340,9 @@ impl HandlerCore {
             });
         }
 
-        match handler(decoded.clone()) {
+        match route_response(&self.req, &decoded, directory, options, handler) {
             None => self.should_404 = true,
-            Some(mut body) => {
-                let original_length = match &body {
-                    Body::Sync(bytes) => Some(bytes.len()),
-                    Body::Async(_) => None,
-                };
-                if self.req.method() == Method::HEAD {
-                    body = Body::Sync(Bytes::new());
-                }
-                let mut response = http::Response::new(body);
-                let mime = MimeGuess::from_path(&decoded)
-                    .first_or_octet_stream()
-                    .to_string();
-                response.headers_mut().insert(
-                    CONTENT_TYPE,
-                    HeaderValue::from_str(&mime).unwrap_or_else(|_| {
-                        HeaderValue::from_static("application/octet-stream")
-                    }),
-                );
-                // `nosniff` is applied centrally in `HandlerCore::render`,
-                // which every static response also funnels through.
-                if let Some(length) = original_length
-                    && let Ok(length) =
-                        HeaderValue::from_str(&length.to_string())
-                {
-                    response.headers_mut().insert(CONTENT_LENGTH, length);
-                }
-                self.preset_res = Some(response.into());
-            }
+            Some(response) => self.preset_res = Some(response),
         }
         Ok(self)
     }
@@ -331,6 +397,42 @@ impl HandlerCore {
     }
 }
 
+/// Answers a streamed body that breached its budget the way a buffered one
+/// would have been answered.
+fn rejected_stream(
+    preview: &'static str,
+    error: &RequestPolicyError,
+) -> http::Response<Body> {
+    #[cfg(feature = "tracing")]
+    trace_policy_rejection(preview, error);
+    #[cfg(not(feature = "tracing"))]
+    let _ = preview;
+    policy_response(error).0
+}
+
+/// Answers a body the host failed to deliver the way a buffered read fails
+/// the request, so the server function never runs on part of its input.
+fn unreadable_stream(
+    preview: &'static str,
+    error: &std::io::Error,
+) -> http::Response<Body> {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        runtime = "wasi",
+        preview,
+        %error,
+        "request body could not be read"
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = (preview, error);
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "request body could not be read",
+        ErrorCause::BodyUnreadable,
+    )
+    .0
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{AtomicUsize, Ordering};
@@ -341,6 +443,7 @@ mod tests {
         path,
     };
 
+    use super::super::policy::plain_response;
     use super::super::test_support::static_route_app;
     use super::*;
 
@@ -0,0 +1,417 @@
+//! Declarative, nonce-based `Content-Security-Policy`.
+//!
+//! The policy is written into the request's
+//! [`ResponseOptions`](crate::response::ResponseOptions) as soon as the
+//! standard contexts are installed for an SSR route, before the application
+//! component runs. `ResponseOptions` is merged into the response before the
+//! first body chunk is sent, so the header always reaches the client, and an
+//! application that writes its own value while rendering replaces it.
+
+use http::{HeaderName, HeaderValue, header::CONTENT_SECURITY_POLICY};
+
+const CONTENT_SECURITY_POLICY_REPORT_ONLY: &str =
+    "content-security-policy-report-only";
+const REPORTING_ENDPOINTS: &str = "reporting-endpoints";
+const DEFAULT_SRC: &str = "default-src";
+const SCRIPT_SRC: &str = "script-src";
+const STYLE_SRC: &str = "style-src";
+const WASM_UNSAFE_EVAL: &str = "'wasm-unsafe-eval'";
+
+/// A `Content-Security-Policy` sent with every SSR response, enabled through
+/// [`HandlerConfig::with_content_security_policy`](super::HandlerConfig::with_content_security_policy).
+///
+/// The per-request Leptos nonce is added to `script-src` and `style-src` as
+/// `'nonce-…'`, so the hydration and streaming scripts the handler emits are
+/// allowed without `'unsafe-inline'`. While hydration is on, which is the
+/// default, `script-src` always carries `'wasm-unsafe-eval'`, since hydration
+/// compiles WebAssembly from JavaScript. Without a `script-src` or
+/// `style-src`, scripts or styles fall back to `default-src`, which then
+/// carries the nonce, and for scripts `'wasm-unsafe-eval'`, in its place.
+///
+/// [`ContentSecurityPolicy::new`] starts from
+///
+/// ```text
+/// default-src 'self'; script-src 'self'; style-src 'self';
+/// connect-src 'self'; object-src 'none'; base-uri 'self';
+/// frame-ancestors 'none'
+/// ```
+///
+/// plus the nonce and `'wasm-unsafe-eval'`. `style-src` keeps `'self'`
+/// because `leptos_meta`'s `Stylesheet` and `Link` components cannot carry a
+/// nonce: a nonce-only `style-src` would block them.
+///
+/// Names and sources that would break the header's syntax, such as ones
+/// containing `;`, `,`, or whitespace, are ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{ContentSecurityPolicy, HandlerConfig};
+///
+/// let config = HandlerConfig::default().with_content_security_policy(
+///     ContentSecurityPolicy::new()
+///         .with_source("img-src", "https://images.example.com")
+///         .report_only()
+///         .with_report_to("csp", "https://example.com/csp-reports"),
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContentSecurityPolicy {
+    directives: Vec<(String, Vec<String>)>,
+    hydration: bool,
+    report_only: bool,
+    report_to: Option<(String, String)>,
+}
+
+impl Default for ContentSecurityPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentSecurityPolicy {
+    /// Returns the baseline policy described on [`ContentSecurityPolicy`].
+    #[must_use]
+    pub fn new() -> Self {
+        let directive = |name: &str, source: &str| {
+            (name.to_string(), vec![source.to_string()])
+        };
+        Self {
+            directives: vec![
+                directive(DEFAULT_SRC, "'self'"),
+                directive(SCRIPT_SRC, "'self'"),
+                directive(STYLE_SRC, "'self'"),
+                directive("connect-src", "'self'"),
+                directive("object-src", "'none'"),
+                directive("base-uri", "'self'"),
+                directive("frame-ancestors", "'none'"),
+            ],
+            hydration: true,
+            report_only: false,
+            report_to: None,
+        }
+    }
+
+    /// Returns a copy whose `name` directive has exactly `sources`.
+    ///
+    /// A directive not yet in the policy is added after the existing ones.
+    /// Replacing `script-src` or `style-src` does not remove the nonce, which
+    /// is added when the header is written.
+    #[must_use]
+    pub fn with_directive<I, S>(mut self, name: &str, sources: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if !is_token(name) {
+            return self;
+        }
+        let sources = sources
+            .into_iter()
+            .map(Into::into)
+            .filter(|source| is_token(source))
+            .collect();
+        let name = name.to_ascii_lowercase();
+        match self
+            .directives
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some((_, existing)) => *existing = sources,
+            None => self.directives.push((name, sources)),
+        }
+        self
+    }
+
+    /// Returns a copy with `source` added to the `name` directive, which is
+    /// created if it is not in the policy yet.
+    #[must_use]
+    pub fn with_source(mut self, name: &str, source: &str) -> Self {
+        if !is_token(name) || !is_token(source) {
+            return self;
+        }
+        let name = name.to_ascii_lowercase();
+        match self
+            .directives
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some((_, sources)) => {
+                if !sources.iter().any(|existing| existing == source) {
+                    sources.push(source.to_string());
+                }
+            }
+            None => self.directives.push((name, vec![source.to_string()])),
+        }
+        self
+    }
+
+    /// Returns a copy without the `name` directive.
+    ///
+    /// Removing `script-src` moves the nonce and `'wasm-unsafe-eval'` onto
+    /// `default-src`, which scripts fall back to, and removing `style-src`
+    /// moves the nonce there for styles.
+    #[must_use]
+    pub fn without_directive(mut self, name: &str) -> Self {
+        self.directives
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        self
+    }
+
+    /// Returns a copy for an application that does not hydrate, which leaves
+    /// `'wasm-unsafe-eval'` out of `script-src`.
+    #[must_use]
+    pub const fn without_hydration(mut self) -> Self {
+        self.hydration = false;
+        self
+    }
+
+    /// Returns a copy sent as `Content-Security-Policy-Report-Only`, which
+    /// reports violations without blocking anything.
+    #[must_use]
+    pub const fn report_only(mut self) -> Self {
+        self.report_only = true;
+        self
+    }
+
+    /// Returns a copy that reports violations to `endpoint`.
+    ///
+    /// This adds a `report-to group` directive and a
+    /// `Reporting-Endpoints: group="endpoint"` header, and a `report-uri`
+    /// directive for browsers that do not implement the Reporting API yet.
+    /// An invalid group name or endpoint is ignored.
+    #[must_use]
+    pub fn with_report_to(mut self, group: &str, endpoint: &str) -> Self {
+        if is_token(group) && is_token(endpoint) && !endpoint.contains('"') {
+            self.report_to = Some((group.to_string(), endpoint.to_string()));
+        }
+        self
+    }
+
+    /// Returns whether the policy is sent as report-only.
+    #[must_use]
+    pub const fn is_report_only(&self) -> bool {
+        self.report_only
+    }
+
+    /// Returns the header name the policy is sent under.
+    pub(super) fn header_name(&self) -> HeaderName {
+        if self.report_only {
+            HeaderName::from_static(CONTENT_SECURITY_POLICY_REPORT_ONLY)
+        } else {
+            CONTENT_SECURITY_POLICY
+        }
+    }
+
+    /// Renders the policy for one request, with `nonce` as its nonce.
+    pub(super) fn header_value(
+        &self,
+        nonce: Option<&str>,
+    ) -> Option<HeaderValue> {
+        let nonce = nonce
+            .filter(|nonce| is_token(nonce))
+            .map(|nonce| format!("'nonce-{nonce}'"));
+        // Scripts and styles fall back to `default-src` when their own
+        // directive is absent, so it carries the nonce and what hydration
+        // needs instead.
+        let governing = |directive: &'static str| {
+            if self.directives.iter().any(|(name, _)| name == directive) {
+                directive
+            } else {
+                DEFAULT_SRC
+            }
+        };
+        let script_directive = governing(SCRIPT_SRC);
+        let style_directive = governing(STYLE_SRC);
+        let mut directives = Vec::with_capacity(self.directives.len() + 2);
+        for (name, sources) in &self.directives {
+            let mut directive = name.clone();
+            for source in sources {
+                directive.push(' ');
+                directive.push_str(source);
+            }
+            let is_script = name == script_directive;
+            if let Some(nonce) = &nonce
+                && (is_script || name == style_directive)
+            {
+                directive.push(' ');
+                directive.push_str(nonce);
+            }
+            if is_script
+                && self.hydration
+                && !sources.iter().any(|source| source == WASM_UNSAFE_EVAL)
+            {
+                directive.push(' ');
+                directive.push_str(WASM_UNSAFE_EVAL);
+            }
+            directives.push(directive);
+        }
+        if let Some((group, endpoint)) = &self.report_to {
+            directives.push(format!("report-uri {endpoint}"));
+            directives.push(format!("report-to {group}"));
+        }
+        HeaderValue::from_str(&directives.join("; ")).ok()
+    }
+
+    /// Renders the `Reporting-Endpoints` header, when reporting is on.
+    pub(super) fn reporting_endpoints(
+        &self,
+    ) -> Option<(HeaderName, HeaderValue)> {
+        let (group, endpoint) = self.report_to.as_ref()?;
+        let value =
+            HeaderValue::from_str(&format!("{group}=\"{endpoint}\"")).ok()?;
+        Some((HeaderName::from_static(REPORTING_ENDPOINTS), value))
+    }
+}
+
+/// Whether `value` can stand as one directive name or source: non-empty
+/// visible ASCII with none of the policy's own separators.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() && byte != b';' && byte != b',')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(policy: &ContentSecurityPolicy, nonce: Option<&str>) -> String {
+        policy
+            .header_value(nonce)
+            .and_then(|value| value.to_str().ok().map(str::to_string))
+            .expect("policy should render")
+    }
+
+    #[test]
+    fn the_baseline_carries_the_nonce_and_wasm_eval() {
+        let policy = ContentSecurityPolicy::new();
+
+        assert_eq!(
+            rendered(&policy, Some("abc")),
+            "default-src 'self'; \
+             script-src 'self' 'nonce-abc' 'wasm-unsafe-eval'; \
+             style-src 'self' 'nonce-abc'; connect-src 'self'; \
+             object-src 'none'; base-uri 'self'; frame-ancestors 'none'"
+        );
+        assert_eq!(policy.header_name(), CONTENT_SECURITY_POLICY);
+    }
+
+    #[test]
+    fn wasm_eval_survives_a_replaced_script_src() {
+        let policy = ContentSecurityPolicy::new()
+            .with_directive("script-src", ["'strict-dynamic'"]);
+
+        assert!(rendered(&policy, Some("n")).contains(
+            "script-src 'strict-dynamic' 'nonce-n' 'wasm-unsafe-eval';"
+        ));
+    }
+
+    #[test]
+    fn default_src_stands_in_for_a_removed_script_src() {
+        let policy =
+            ContentSecurityPolicy::new().without_directive("script-src");
+
+        assert_eq!(
+            rendered(&policy, Some("abc")),
+            "default-src 'self' 'nonce-abc' 'wasm-unsafe-eval'; \
+             style-src 'self' 'nonce-abc'; connect-src 'self'; \
+             object-src 'none'; base-uri 'self'; frame-ancestors 'none'"
+        );
+        assert_eq!(
+            rendered(&policy.without_hydration(), None),
+            "default-src 'self'; style-src 'self'; connect-src 'self'; \
+             object-src 'none'; base-uri 'self'; frame-ancestors 'none'"
+        );
+    }
+
+    #[test]
+    fn default_src_stands_in_for_a_removed_style_src() {
+        let policy =
+            ContentSecurityPolicy::new().without_directive("style-src");
+
+        assert_eq!(
+            rendered(&policy, Some("abc")),
+            "default-src 'self' 'nonce-abc'; \
+             script-src 'self' 'nonce-abc' 'wasm-unsafe-eval'; \
+             connect-src 'self'; object-src 'none'; base-uri 'self'; \
+             frame-ancestors 'none'"
+        );
+        assert_eq!(
+            rendered(&policy.without_directive("script-src"), Some("abc")),
+            "default-src 'self' 'nonce-abc' 'wasm-unsafe-eval'; \
+             connect-src 'self'; object-src 'none'; base-uri 'self'; \
+             frame-ancestors 'none'"
+        );
+    }
+
+    #[test]
+    fn wasm_eval_is_not_repeated_or_added_without_hydration() {
+        let explicit = ContentSecurityPolicy::new()
+            .with_source("script-src", "'wasm-unsafe-eval'");
+        assert_eq!(
+            rendered(&explicit, None)
+                .matches("'wasm-unsafe-eval'")
+                .count(),
+            1
+        );
+
+        let static_site = ContentSecurityPolicy::new().without_hydration();
+        assert!(!rendered(&static_site, None).contains("wasm-unsafe-eval"));
+    }
+
+    #[test]
+    fn sources_and_directives_can_be_edited() {
+        let policy = ContentSecurityPolicy::new()
+            .with_source("img-src", "https://images.example.com")
+            .with_source("IMG-SRC", "data:")
+            .with_source("img-src", "data:")
+            .without_directive("frame-ancestors")
+            .with_directive("object-src", Vec::<String>::new());
+        let value = rendered(&policy, None);
+
+        assert!(value.ends_with("img-src https://images.example.com data:"));
+        assert!(!value.contains("frame-ancestors"));
+        assert!(value.contains("; object-src; "));
+    }
+
+    #[test]
+    fn syntax_breaking_input_is_ignored() {
+        let policy = ContentSecurityPolicy::new()
+            .with_source("script-src", "'self'; script-src *")
+            .with_source("img src", "data:")
+            .with_directive("style-src", ["https://a.example, *"]);
+        let value = rendered(&policy, None);
+
+        assert!(!value.contains('*'));
+        assert!(!value.contains("img"));
+        assert!(value.contains("style-src;"));
+    }
+
+    #[test]
+    fn report_only_policies_name_their_endpoint() {
+        let policy = ContentSecurityPolicy::new()
+            .report_only()
+            .with_report_to("csp", "https://example.com/reports");
+
+        assert!(policy.is_report_only());
+        assert_eq!(
+            policy.header_name().as_str(),
+            "content-security-policy-report-only"
+        );
+        assert!(rendered(&policy, None).ends_with(
+            "; report-uri https://example.com/reports; report-to csp"
+        ));
+        let (name, value) = policy
+            .reporting_endpoints()
+            .expect("reporting endpoint should be rendered");
+        assert_eq!(name.as_str(), "reporting-endpoints");
+        assert_eq!(value, "csp=\"https://example.com/reports\"");
+    }
+
+    #[test]
+    fn there_is_no_reporting_endpoint_by_default() {
+        assert!(ContentSecurityPolicy::new().reporting_endpoints().is_none());
+    }
+}
@@ -0,0 +1,404 @@
+//! Opt-in decoding of `Content-Encoding` request bodies.
+//!
+//! Both previews buffer a request body under `max_request_body_size` and then
+//! hand it to [`decode_configured`], so the decoded-size and ratio caps mean the
+//! same thing on each. The size limit bounds the bytes on the wire; the caps
+//! here bound what those bytes may expand to.
+
+use std::io::Read;
+
+use brotli::Decompressor;
+use bytes::Bytes;
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use http::{
+    HeaderValue,
+    header::{CONTENT_ENCODING, CONTENT_LENGTH},
+    request::Parts,
+};
+
+use super::compression::Coding;
+use super::policy::{
+    DEFAULT_MAX_REQUEST_BODY_SIZE, HandlerConfig, RequestPolicyError,
+};
+
+/// The default cap on how many times larger a decoded body may be than the
+/// bytes received.
+pub const DEFAULT_MAX_DECOMPRESSION_RATIO: u32 = 100;
+
+/// Brotli's internal read buffer size.
+const BROTLI_BUFFER: usize = 4096;
+
+/// Request body decoding settings, enabled through
+/// [`HandlerConfig::with_request_decompression`](super::HandlerConfig::with_request_decompression).
+///
+/// A body with `Content-Encoding: gzip`, `deflate`, or `br` is decoded before
+/// the application sees it, and the header is removed. Decoding stops as
+/// soon as the output passes either cap, so a small body that expands to
+/// gigabytes costs no more memory than the cap:
+///
+/// - the decoded size, answered with `413` when exceeded;
+/// - the ratio of decoded to received bytes, also answered with `413`.
+///
+/// A coding that is unknown or disabled is answered with
+/// `415 Unsupported Media Type`, and a body that does not decode with `400`.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, RequestDecompression};
+///
+/// let config = HandlerConfig::default()
+///     .with_max_request_body_size(1024 * 1024)
+///     .with_request_decompression(
+///         RequestDecompression::new()
+///             .with_max_decoded_size(8 * 1024 * 1024)
+///             .with_max_ratio(20)
+///             .without_brotli(),
+///     );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RequestDecompression {
+    brotli: bool,
+    gzip: bool,
+    deflate: bool,
+    decoded_size: usize,
+    ratio: u32,
+}
+
+impl Default for RequestDecompression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestDecompression {
+    /// Returns settings that decode all three codings, up to 16 MiB and a
+    /// ratio of 100.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            brotli: true,
+            gzip: true,
+            deflate: true,
+            decoded_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            ratio: DEFAULT_MAX_DECOMPRESSION_RATIO,
+        }
+    }
+
+    /// Returns a copy that answers `br` bodies with `415`.
+    #[must_use]
+    pub const fn without_brotli(mut self) -> Self {
+        self.brotli = false;
+        self
+    }
+
+    /// Returns a copy that answers `gzip` bodies with `415`.
+    #[must_use]
+    pub const fn without_gzip(mut self) -> Self {
+        self.gzip = false;
+        self
+    }
+
+    /// Returns a copy that answers `deflate` bodies with `415`.
+    #[must_use]
+    pub const fn without_deflate(mut self) -> Self {
+        self.deflate = false;
+        self
+    }
+
+    /// Returns a copy that accepts decoded bodies of at most `bytes`.
+    #[must_use]
+    pub const fn with_max_decoded_size(mut self, bytes: usize) -> Self {
+        self.decoded_size = bytes;
+        self
+    }
+
+    /// Returns a copy that accepts decoded bodies at most `ratio` times the
+    /// size of the received body.
+    #[must_use]
+    pub const fn with_max_ratio(mut self, ratio: u32) -> Self {
+        self.ratio = ratio;
+        self
+    }
+
+    /// Returns the maximum decoded body size in bytes.
+    #[must_use]
+    pub const fn max_decoded_size(&self) -> usize {
+        self.decoded_size
+    }
+
+    /// Returns the maximum ratio of decoded to received bytes.
+    #[must_use]
+    pub const fn max_ratio(&self) -> u32 {
+        self.ratio
+    }
+
+    /// Returns the enabled coding named `token`, if any.
+    fn coding(self, token: &str) -> Option<Coding> {
+        let coding = Coding::from_token(token)?;
+        let enabled = match coding {
+            Coding::Brotli => self.brotli,
+            Coding::Gzip => self.gzip,
+            Coding::Deflate => self.deflate,
+        };
+        enabled.then_some(coding)
+    }
+}
+
+/// Decodes `body` under `config`'s request decompression, if enabled.
+pub(super) fn decode_configured(
+    parts: &mut Parts,
+    body: Bytes,
+    config: &HandlerConfig,
+) -> Result<Bytes, RequestPolicyError> {
+    match config.request_decompression() {
+        Some(decompression) => decode_body(parts, body, decompression),
+        None => Ok(body),
+    }
+}
+
+/// Decodes `body` under `config`, removing `Content-Encoding` from `parts`
+/// and correcting `Content-Length`.
+///
+/// A body without a coding, or with only `identity`, is returned as is.
+fn decode_body(
+    parts: &mut Parts,
+    mut body: Bytes,
+    config: RequestDecompression,
+) -> Result<Bytes, RequestPolicyError> {
+    if !parts.headers.contains_key(CONTENT_ENCODING) {
+        return Ok(body);
+    }
+    let mut codings = Vec::new();
+    for value in parts.headers.get_all(CONTENT_ENCODING) {
+        // A value that is not visible ASCII names no coding this handler
+        // knows, so the body cannot be decoded.
+        let value = value
+            .to_str()
+            .map_err(|_| RequestPolicyError::UnsupportedContentEncoding)?;
+        for token in value.split(',').map(str::trim).filter(|token| {
+            !token.is_empty() && !token.eq_ignore_ascii_case("identity")
+        }) {
+            codings.push(
+                config
+                    .coding(token)
+                    .ok_or(RequestPolicyError::UnsupportedContentEncoding)?,
+            );
+        }
+    }
+
+    if !body.is_empty() {
+        let ratio_cap = body.len().saturating_mul(
+            usize::try_from(config.ratio).unwrap_or(usize::MAX),
+        );
+        // Codings are listed in the order they were applied.
+        for &coding in codings.iter().rev() {
+            body = decode(
+                coding,
+                &body,
+                config.decoded_size,
+                ratio_cap,
+                config.ratio,
+            )?;
+        }
+    }
+    parts.headers.remove(CONTENT_ENCODING);
+    parts
+        .headers
+        .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+    Ok(body)
+}
+
+/// Decodes one coding, reading at most one byte past the tighter cap.
+fn decode(
+    coding: Coding,
+    input: &[u8],
+    size_cap: usize,
+    ratio_cap: usize,
+    ratio: u32,
+) -> Result<Bytes, RequestPolicyError> {
+    let reader: Box<dyn Read + '_> = match coding {
+        Coding::Brotli => Box::new(Decompressor::new(input, BROTLI_BUFFER)),
+        Coding::Gzip => Box::new(MultiGzDecoder::new(input)),
+        Coding::Deflate => Box::new(ZlibDecoder::new(input)),
+    };
+    let cap = size_cap.min(ratio_cap);
+    let mut decoded = Vec::new();
+    reader
+        .take(u64::try_from(cap).unwrap_or(u64::MAX).saturating_add(1))
+        .read_to_end(&mut decoded)
+        .map_err(|_| RequestPolicyError::InvalidEncodedBody)?;
+    if decoded.len() > size_cap {
+        return Err(RequestPolicyError::DecodedBodyTooLarge {
+            limit: size_cap,
+        });
+    }
+    if decoded.len() > ratio_cap {
+        return Err(RequestPolicyError::DecompressionRatioExceeded {
+            limit: ratio,
+        });
+    }
+    Ok(Bytes::from(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{
+        Compression,
+        write::{GzEncoder, ZlibEncoder},
+    };
+
+    use http::Request;
+
+    use super::*;
+
+    fn decode_request(
+        request: Request<Bytes>,
+        config: RequestDecompression,
+    ) -> Result<Request<Bytes>, RequestPolicyError> {
+        let (mut parts, body) = request.into_parts();
+        let body = decode_body(&mut parts, body, config)?;
+        Ok(Request::from_parts(parts, body))
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).expect("writing to a Vec succeeds");
+        encoder.finish().expect("writing to a Vec succeeds")
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).expect("writing to a Vec succeeds");
+        encoder.finish().expect("writing to a Vec succeeds")
+    }
+
+    fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        encoder.write_all(data).expect("writing to a Vec succeeds");
+        encoder.into_inner()
+    }
+
+    fn encoded(coding: &'static str, body: Vec<u8>) -> Request<Bytes> {
+        Request::builder()
+            .header(CONTENT_ENCODING, coding)
+            .header(CONTENT_LENGTH, body.len())
+            .body(Bytes::from(body))
+            .expect("test request should be valid")
+    }
+
+    #[test]
+    fn each_coding_is_decoded_and_its_headers_corrected() {
+        let data = b"hello hello hello hello";
+        for (coding, body) in [
+            ("gzip", gzip(data)),
+            ("x-gzip", gzip(data)),
+            ("deflate", deflate(data)),
+            ("br", brotli(data)),
+        ] {
+            let request = decode_request(
+                encoded(coding, body),
+                RequestDecompression::new(),
+            )
+            .expect("the body decodes");
+
+            assert_eq!(request.body().as_ref(), data, "{coding}");
+            assert!(!request.headers().contains_key(CONTENT_ENCODING));
+            assert_eq!(
+                request.headers().get(CONTENT_LENGTH),
+                Some(&HeaderValue::from(data.len()))
+            );
+        }
+    }
+
+    #[test]
+    fn stacked_codings_are_removed_in_reverse() {
+        let body = brotli(&gzip(b"layered"));
+
+        let request = decode_request(
+            encoded("gzip, br", body),
+            RequestDecompression::new(),
+        )
+        .expect("the body decodes");
+
+        assert_eq!(request.body().as_ref(), b"layered");
+    }
+
+    #[test]
+    fn unencoded_bodies_pass_through() {
+        let request = Request::new(Bytes::from_static(b"plain"));
+
+        let request = decode_request(request, RequestDecompression::new())
+            .expect("nothing to decode");
+
+        assert_eq!(request.body().as_ref(), b"plain");
+        assert!(!request.headers().contains_key(CONTENT_LENGTH));
+    }
+
+    #[test]
+    fn unknown_or_disabled_codings_are_unsupported() {
+        for (coding, config) in [
+            ("zstd", RequestDecompression::new()),
+            ("gzip", RequestDecompression::new().without_gzip()),
+        ] {
+            assert_eq!(
+                decode_request(encoded(coding, b"x".to_vec()), config)
+                    .map(|_| ()),
+                Err(RequestPolicyError::UnsupportedContentEncoding)
+            );
+        }
+    }
+
+    #[test]
+    fn an_unreadable_coding_is_unsupported_rather_than_skipped() {
+        let body = gzip(b"hello");
+        let request = Request::builder()
+            .header(
+                CONTENT_ENCODING,
+                HeaderValue::from_bytes(b"gzip\xff")
+                    .expect("obs-text is a valid header value"),
+            )
+            .body(Bytes::from(body))
+            .expect("test request should be valid");
+
+        assert_eq!(
+            decode_request(request, RequestDecompression::new()).map(|_| ()),
+            Err(RequestPolicyError::UnsupportedContentEncoding)
+        );
+    }
+
+    #[test]
+    fn a_bomb_is_stopped_at_the_tighter_cap() {
+        let bomb = gzip(&vec![0; 1024 * 1024]);
+        assert!(bomb.len() < 2048, "the fixture must compress well");
+
+        assert_eq!(
+            decode_request(
+                encoded("gzip", bomb.clone()),
+                RequestDecompression::new().with_max_decoded_size(4096)
+            )
+            .map(|_| ()),
+            Err(RequestPolicyError::DecodedBodyTooLarge { limit: 4096 })
+        );
+        assert_eq!(
+            decode_request(encoded("gzip", bomb), RequestDecompression::new())
+                .map(|_| ()),
+            Err(RequestPolicyError::DecompressionRatioExceeded { limit: 100 })
+        );
+    }
+
+    #[test]
+    fn a_corrupt_body_is_invalid() {
+        assert_eq!(
+            decode_request(
+                encoded("gzip", b"not gzip".to_vec()),
+                RequestDecompression::new()
+            )
+            .map(|_| ()),
+            Err(RequestPolicyError::InvalidEncodedBody)
+        );
+    }
+}
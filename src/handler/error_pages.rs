@@ -0,0 +1,328 @@
+//! Application-rendered pages for the crate's own error responses.
+//!
+//! Every error response the handler generates itself, such as the not-found
+//! fallback, the static `405`, and request policy rejections, carries an
+//! [`ErrorCause`] in its extensions. [`HandlerCore::render`](super::core::HandlerCore)
+//! hands a response carrying one to the configured [`ErrorPages`] when the
+//! request accepts HTML; every other client keeps the plain-text body.
+
+use std::{fmt, sync::Arc};
+
+use bytes::Bytes;
+use http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+};
+use leptos::{
+    IntoView,
+    prelude::{Owner, RenderHtml},
+};
+
+use super::{
+    http_util::accepts_html,
+    policy::{RegistrationError, RequestPolicyError, plain_response},
+};
+use crate::response::{Body, Response};
+
+/// Why the handler produced an error response.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ErrorCause {
+    /// No static prefix, server function, or SSR route matched the request.
+    NotFound,
+    /// A static prefix matched, but the method was not `GET` or `HEAD`.
+    MethodNotAllowed,
+    /// The request breached the configured [`HandlerConfig`](super::HandlerConfig).
+    Policy(RequestPolicyError),
+    /// The host failed to deliver a request body that was read for its
+    /// server function.
+    BodyUnreadable,
+    /// Registering a route or static prefix failed, passed to
+    /// [`ErrorPages::registration_response`].
+    Registration(RegistrationError),
+}
+
+/// An error response about to be sent, as seen by an [`ErrorPages`]
+/// renderer.
+#[derive(Clone, Debug)]
+pub struct ErrorPage {
+    status: StatusCode,
+    cause: ErrorCause,
+    message: String,
+}
+
+impl ErrorPage {
+    /// Returns the response status.
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns why the response is an error.
+    #[must_use]
+    pub const fn cause(&self) -> &ErrorCause {
+        &self.cause
+    }
+
+    /// Returns the plain-text sentence an API client receives instead.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+type Renderer = dyn Fn(&ErrorPage) -> String + Send + Sync;
+
+/// Renders the HTML page sent in place of a crate-generated error, enabled
+/// through [`HandlerConfig::with_error_pages`](super::HandlerConfig::with_error_pages).
+///
+/// Only requests whose `Accept` header takes `text/html` see the page; API
+/// clients keep the plain-text body. The status and every other header,
+/// such as `Allow` on a `405`, are unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos::prelude::*;
+/// use leptos_wasi::{ErrorPages, HandlerConfig};
+///
+/// let config = HandlerConfig::default().with_error_pages(ErrorPages::from_view(
+///     |page| {
+///         view! {
+///             <!DOCTYPE html>
+///             <html>
+///                 <body>
+///                     <h1>{page.status().as_u16()}</h1>
+///                     <p>{page.message().to_string()}</p>
+///                 </body>
+///             </html>
+///         }
+///     },
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct ErrorPages {
+    render: Arc<Renderer>,
+}
+
+impl ErrorPages {
+    /// Renders pages with a closure that returns the HTML document.
+    pub fn from_fn(
+        render: impl Fn(&ErrorPage) -> String + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            render: Arc::new(render),
+        }
+    }
+
+    /// Renders pages with a Leptos view function.
+    ///
+    /// The view is rendered synchronously in its own reactive owner, without
+    /// the request's contexts, so it cannot wait on resources: anything
+    /// under a `Suspense` renders its fallback.
+    pub fn from_view<IV>(
+        view: impl Fn(ErrorPage) -> IV + Send + Sync + 'static,
+    ) -> Self
+    where
+        IV: IntoView + 'static,
+    {
+        Self::from_fn(move |page| {
+            let owner = Owner::new();
+            let html = owner.with(|| view(page.clone()).into_view().to_html());
+            owner.unset_with_forced_cleanup();
+            html
+        })
+    }
+
+    /// Builds the `500 Internal Server Error` response for a registration
+    /// failure, rendered as a page when `request_headers` accept HTML.
+    ///
+    /// A registration method returns its error to the application rather
+    /// than answering the request, so this is how an application answers
+    /// with the same page as every other error.
+    #[must_use]
+    pub fn registration_response(
+        &self,
+        request_headers: &HeaderMap,
+        error: RegistrationError,
+    ) -> Response {
+        let mut response = error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal server error",
+            ErrorCause::Registration(error),
+        );
+        if accepts_html(request_headers) {
+            self.apply(&mut response);
+        }
+        response
+    }
+
+    /// Replaces the body of `response` with a rendered page, if the handler
+    /// produced it as an error.
+    pub(super) fn apply(&self, response: &mut Response) {
+        let Some(cause) = response.0.extensions().get::<ErrorCause>().cloned()
+        else {
+            return;
+        };
+        let message = match response.0.body() {
+            Body::Sync(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            Body::Async(_) => return,
+        };
+        let page = ErrorPage {
+            status: response.0.status(),
+            cause,
+            message,
+        };
+        let html = (self.render)(&page);
+        *response.0.body_mut() = Body::Sync(Bytes::from(html));
+        let headers = response.0.headers_mut();
+        headers.remove(CONTENT_LENGTH);
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+    }
+}
+
+impl fmt::Debug for ErrorPages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorPages").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for ErrorPages {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.render, &other.render)
+    }
+}
+
+impl Eq for ErrorPages {}
+
+/// Builds one of the crate's own error responses, classified by `cause` so
+/// that a configured [`ErrorPages`] can replace its body.
+pub(super) fn error_response(
+    status: StatusCode,
+    message: impl Into<Bytes>,
+    cause: ErrorCause,
+) -> Response {
+    let mut response = plain_response(status, message);
+    response.0.extensions_mut().insert(cause);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::{ACCEPT, ALLOW};
+    use leptos::prelude::{ElementChild, view};
+
+    use super::*;
+
+    fn body_of(response: &Response) -> &[u8] {
+        match response.0.body() {
+            Body::Sync(bytes) => bytes,
+            Body::Async(_) => b"",
+        }
+    }
+
+    fn html_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("text/html"));
+        headers
+    }
+
+    #[test]
+    fn a_closure_sees_the_status_cause_and_message() {
+        let pages = ErrorPages::from_fn(|page| {
+            format!(
+                "{}|{:?}|{}",
+                page.status().as_u16(),
+                page.cause(),
+                page.message()
+            )
+        });
+        let mut response = error_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "too big",
+            ErrorCause::Policy(RequestPolicyError::BodyTooLarge { limit: 8 }),
+        );
+
+        pages.apply(&mut response);
+
+        assert_eq!(response.0.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            body_of(&response),
+            b"413|Policy(BodyTooLarge { limit: 8 })|too big"
+        );
+        assert_eq!(
+            response.0.headers().get(CONTENT_TYPE),
+            Some(&HeaderValue::from_static("text/html; charset=utf-8"))
+        );
+    }
+
+    #[test]
+    fn a_view_function_renders_to_html() {
+        let pages = ErrorPages::from_view(|page| {
+            view! { <h1>{page.status().as_u16()}</h1> }
+        });
+        let mut response =
+            error_response(StatusCode::NOT_FOUND, "gone", ErrorCause::NotFound);
+
+        pages.apply(&mut response);
+
+        assert_eq!(body_of(&response), b"<h1>404</h1>");
+    }
+
+    #[test]
+    fn unclassified_responses_are_left_alone() {
+        let pages = ErrorPages::from_fn(|_| String::from("page"));
+        let mut response = plain_response(StatusCode::NOT_FOUND, "app 404");
+
+        pages.apply(&mut response);
+
+        assert_eq!(body_of(&response), b"app 404");
+    }
+
+    #[test]
+    fn headers_other_than_the_type_survive() {
+        let pages = ErrorPages::from_fn(|_| String::from("page"));
+        let mut response = error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method not allowed",
+            ErrorCause::MethodNotAllowed,
+        );
+        response
+            .0
+            .headers_mut()
+            .insert(ALLOW, HeaderValue::from_static("GET, HEAD"));
+
+        pages.apply(&mut response);
+
+        assert_eq!(
+            response.0.headers().get(ALLOW),
+            Some(&HeaderValue::from_static("GET, HEAD"))
+        );
+    }
+
+    #[test]
+    fn registration_failures_are_rendered_for_browsers_only() {
+        let pages = ErrorPages::from_fn(|page| format!("{:?}", page.cause()));
+        let error = RegistrationError::RoutesAlreadyGenerated;
+
+        let browser =
+            pages.registration_response(&html_headers(), error.clone());
+        let api = pages.registration_response(&HeaderMap::new(), error);
+
+        assert_eq!(browser.0.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body_of(&browser), b"Registration(RoutesAlreadyGenerated)");
+        assert_eq!(api.0.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body_of(&api), b"internal server error");
+    }
+
+    #[test]
+    fn error_pages_compare_by_identity() {
+        let pages = ErrorPages::from_fn(|_| String::new());
+
+        assert_eq!(pages, pages.clone());
+        assert_ne!(pages, ErrorPages::from_fn(|_| String::new()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Limits on the request line and header section.
+//!
+//! These are checked before the header section is copied into an
+//! [`http::Request`]: Preview 2 checks the host's header entries inside
+//! `request_parts`, and Preview 3, whose request arrives already converted,
+//! checks it before the body is read. On a breach, Preview 2 still copies the
+//! method, the path cut to its limit, and the headers admitted before the
+//! breach, so both previews answer the rejection against the real request.
+
+use super::policy::RequestPolicyError;
+
+/// Limits applied to the header section and the path with query, enabled
+/// through [`HandlerConfig::with_header_limits`](super::HandlerConfig::with_header_limits).
+///
+/// The size of the header section is the sum of every field's name and value
+/// length, so a repeated header counts once per occurrence. A breach of a
+/// header limit is answered with `431 Request Header Fields Too Large`, and
+/// a path with query over its limit with `414 URI Too Long`.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, HeaderLimits};
+///
+/// let config = HandlerConfig::default().with_header_limits(
+///     HeaderLimits::new()
+///         .with_max_headers(64)
+///         .with_max_header_bytes(16 * 1024)
+///         .with_max_header_value_size(4 * 1024)
+///         .with_max_uri_length(2048),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HeaderLimits {
+    count: usize,
+    total_size: usize,
+    value_size: usize,
+    uri_length: usize,
+}
+
+impl Default for HeaderLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeaderLimits {
+    /// Returns limits of 100 headers, 64 KiB of header section, 8 KiB per
+    /// header value, and 8 KiB of path with query.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            count: 100,
+            total_size: 64 * 1024,
+            value_size: 8 * 1024,
+            uri_length: 8 * 1024,
+        }
+    }
+
+    /// Returns a copy that accepts at most `count` header fields.
+    #[must_use]
+    pub const fn with_max_headers(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Returns a copy that accepts a header section of at most `bytes`.
+    #[must_use]
+    pub const fn with_max_header_bytes(mut self, bytes: usize) -> Self {
+        self.total_size = bytes;
+        self
+    }
+
+    /// Returns a copy that accepts header values of at most `bytes` each.
+    #[must_use]
+    pub const fn with_max_header_value_size(mut self, bytes: usize) -> Self {
+        self.value_size = bytes;
+        self
+    }
+
+    /// Returns a copy that accepts a path with query of at most `bytes`.
+    #[must_use]
+    pub const fn with_max_uri_length(mut self, bytes: usize) -> Self {
+        self.uri_length = bytes;
+        self
+    }
+
+    /// Returns the maximum number of header fields.
+    #[must_use]
+    pub const fn max_headers(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the maximum size of the header section in bytes.
+    #[must_use]
+    pub const fn max_header_bytes(&self) -> usize {
+        self.total_size
+    }
+
+    /// Returns the maximum size of one header value in bytes.
+    #[must_use]
+    pub const fn max_header_value_size(&self) -> usize {
+        self.value_size
+    }
+
+    /// Returns the maximum length of the path with query in bytes.
+    #[must_use]
+    pub const fn max_uri_length(&self) -> usize {
+        self.uri_length
+    }
+
+    /// Checks the length of a request's path with query.
+    pub(crate) fn check_uri(
+        &self,
+        path_with_query: Option<&str>,
+    ) -> Result<(), RequestPolicyError> {
+        if path_with_query.map_or(0, str::len) > self.uri_length {
+            return Err(RequestPolicyError::UriTooLong {
+                limit: self.uri_length,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks a request's header fields in order, returning how many of them
+    /// were admitted before the first limit breached, and that breach.
+    pub(crate) fn admit<'a>(
+        &self,
+        headers: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) -> (usize, Result<(), RequestPolicyError>) {
+        let mut count = 0_usize;
+        let mut total = 0_usize;
+        for (name, value) in headers {
+            if count == self.count {
+                return (
+                    count,
+                    Err(RequestPolicyError::TooManyHeaders {
+                        limit: self.count,
+                    }),
+                );
+            }
+            if value.len() > self.value_size {
+                return (
+                    count,
+                    Err(RequestPolicyError::HeaderValueTooLarge {
+                        limit: self.value_size,
+                    }),
+                );
+            }
+            total = total.saturating_add(name.len() + value.len());
+            if total > self.total_size {
+                return (
+                    count,
+                    Err(RequestPolicyError::HeadersTooLarge {
+                        limit: self.total_size,
+                    }),
+                );
+            }
+            count += 1;
+        }
+        (count, Ok(()))
+    }
+
+    /// Checks the request line and headers already converted into `parts`,
+    /// returning the first limit breached.
+    #[cfg(any(feature = "wasip3", test))]
+    pub(super) fn check_parts(
+        &self,
+        parts: &http::request::Parts,
+    ) -> Result<(), RequestPolicyError> {
+        self.check_uri(
+            parts
+                .uri
+                .path_and_query()
+                .map(http::uri::PathAndQuery::as_str),
+        )?;
+        self.admit(
+            parts
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )
+        .1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{Request, StatusCode, request::Parts};
+
+    use super::*;
+
+    fn parts(uri: &str, headers: &[(&str, &str)]) -> Parts {
+        let mut builder = Request::builder().uri(uri);
+        for &(name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(())
+            .expect("test request should be valid")
+            .into_parts()
+            .0
+    }
+
+    #[test]
+    fn a_request_within_its_limits_passes() {
+        let limits = HeaderLimits::new()
+            .with_max_headers(2)
+            .with_max_header_bytes(12)
+            .with_max_header_value_size(5)
+            .with_max_uri_length(9);
+
+        assert_eq!(
+            limits.check_parts(&parts(
+                "/a?b=cdef",
+                &[("x", "12345"), ("y", "1")]
+            )),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn each_limit_is_reported_with_its_status() {
+        let limits = HeaderLimits::new()
+            .with_max_headers(2)
+            .with_max_header_bytes(8)
+            .with_max_header_value_size(5)
+            .with_max_uri_length(8);
+
+        for (request, error, status) in [
+            (
+                parts("/a?b=cdef", &[]),
+                RequestPolicyError::UriTooLong { limit: 8 },
+                StatusCode::URI_TOO_LONG,
+            ),
+            (
+                parts("/", &[("x", "1"), ("y", "2"), ("x", "3")]),
+                RequestPolicyError::TooManyHeaders { limit: 2 },
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            ),
+            (
+                parts("/", &[("x", "123456")]),
+                RequestPolicyError::HeaderValueTooLarge { limit: 5 },
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            ),
+            (
+                parts("/", &[("x", "12345"), ("yy", "1")]),
+                RequestPolicyError::HeadersTooLarge { limit: 8 },
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            ),
+        ] {
+            assert_eq!(limits.check_parts(&request), Err(error.clone()));
+            assert_eq!(error.status(), status);
+        }
+    }
+
+    #[test]
+    fn only_the_headers_before_a_breach_are_admitted() {
+        let limits = HeaderLimits::new()
+            .with_max_headers(2)
+            .with_max_header_value_size(3);
+        let admitted = |headers: &[(&'static str, &'static str)]| {
+            limits.admit(
+                headers
+                    .iter()
+                    .map(|&(name, value)| (name, value.as_bytes())),
+            )
+        };
+
+        assert_eq!(admitted(&[("a", "1"), ("b", "2")]), (2, Ok(())));
+        assert_eq!(
+            admitted(&[("a", "1"), ("b", "2"), ("c", "3")]),
+            (2, Err(RequestPolicyError::TooManyHeaders { limit: 2 }))
+        );
+        assert_eq!(
+            admitted(&[("a", "1"), ("b", "1234")]),
+            (1, Err(RequestPolicyError::HeaderValueTooLarge { limit: 3 }))
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! HTTP-date formatting and parsing for the validators static assets carry.
+//!
+//! Responses always use the preferred IMF-fixdate form. Requests are parsed in
+//! all three forms RFC 9110 section 5.6.7 obliges a recipient to accept,
+//! because `If-Modified-Since` is echoed back from whatever a client or cache
+//! stored.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct",
+    "Nov", "Dec",
+];
+
+/// Formats `time` as an IMF-fixdate, truncated to whole seconds.
+///
+/// A time before the Unix epoch is clamped to it; no asset this crate serves
+/// can meaningfully claim to predate 1970.
+pub(super) fn format_http_date(time: SystemTime) -> String {
+    let seconds = unix_seconds(time);
+    let days = seconds / SECONDS_PER_DAY;
+    let of_day = seconds % SECONDS_PER_DAY;
+    let (year, month, day) = civil_from_days(days);
+    let weekday = WEEKDAYS
+        .get(usize::try_from(days % 7).unwrap_or_default())
+        .copied()
+        .unwrap_or_default();
+    let month = MONTHS
+        .get(usize::try_from(month - 1).unwrap_or_default())
+        .copied()
+        .unwrap_or_default();
+    format!(
+        "{weekday}, {day:02} {month} {year:04} {:02}:{:02}:{:02} GMT",
+        of_day / 3_600,
+        of_day % 3_600 / 60,
+        of_day % 60,
+    )
+}
+
+/// Parses an HTTP-date in IMF-fixdate, RFC 850, or asctime form.
+///
+/// Returns `None` for anything else, including a date before the Unix epoch.
+/// Callers treat an unparseable date as an absent header, which is what RFC
+/// 9110 requires of `If-Modified-Since` and `If-Range`.
+pub(super) fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let fields = value.split_ascii_whitespace().collect::<Vec<_>>();
+    let (day, month, year, time) = match fields.as_slice() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        [weekday, day, month, year, time, "GMT"] if weekday.ends_with(',') => {
+            (*day, *month, parse_number(year)?, *time)
+        }
+        // Sunday, 06-Nov-94 08:49:37 GMT
+        [weekday, date, time, "GMT"] if weekday.ends_with(',') => {
+            let mut parts = date.split('-');
+            let (Some(day), Some(month), Some(year), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return None;
+            };
+            if year.len() != 2 {
+                return None;
+            }
+            let year = parse_number(year)?;
+            // RFC 9110 resolves a two-digit year against the current
+            // century; for the dates a validator can hold, that is 19xx
+            // from 70 upwards and 20xx below it.
+            let year = if year >= 70 {
+                1_900 + year
+            } else {
+                2_000 + year
+            };
+            (day, month, year, *time)
+        }
+        // Sun Nov  6 08:49:37 1994
+        [_weekday, month, day, time, year] => {
+            (*day, *month, parse_number(year)?, *time)
+        }
+        _ => return None,
+    };
+
+    let day = parse_number(day)?;
+    let month = MONTHS
+        .iter()
+        .position(|name| *name == month)
+        .and_then(|index| u64::try_from(index + 1).ok())?;
+    if year < 1_970 || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut clock = time.split(':');
+    let (Some(hour), Some(minute), Some(second), None) =
+        (clock.next(), clock.next(), clock.next(), clock.next())
+    else {
+        return None;
+    };
+    let (hour, minute, second) = (
+        parse_number(hour)?,
+        parse_number(minute)?,
+        parse_number(second)?,
+    );
+    // 60 is a leap second, which IMF-fixdate permits.
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY
+        + hour * 3_600
+        + minute * 60
+        + second;
+    UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
+/// Whole seconds since the Unix epoch, which is the resolution every
+/// HTTP-date comparison is made at.
+pub(super) fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn parse_number(value: &str) -> Option<u64> {
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+const fn is_leap_year(year: u64) -> bool {
+    year.is_multiple_of(4)
+        && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+const fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's civil-calendar algorithms, restricted to the proleptic
+// Gregorian dates from 1970 onwards, which keeps every term unsigned.
+const fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example RFC 9110 uses for all three forms.
+    const RFC_EXAMPLE: u64 = 784_111_777;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn formats_the_rfc_example_as_imf_fixdate() {
+        assert_eq!(
+            format_http_date(at(RFC_EXAMPLE)),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+    }
+
+    #[test]
+    fn formats_the_epoch_and_a_leap_day() {
+        assert_eq!(
+            format_http_date(UNIX_EPOCH),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+        assert_eq!(
+            format_http_date(at(951_782_400)),
+            "Tue, 29 Feb 2000 00:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn formatting_drops_subsecond_precision() {
+        let time = at(RFC_EXAMPLE) + Duration::from_millis(999);
+        assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test]
+    fn parses_all_three_required_forms() {
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(
+                parse_http_date(value),
+                Some(at(RFC_EXAMPLE)),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_across_a_range_of_dates() {
+        // Every 13 days and 7 hours across sixty years, so month ends, leap
+        // days, and century rules all get crossed.
+        let step = 13 * SECONDS_PER_DAY + 7 * 3_600 + 11;
+        let mut seconds = 0;
+        while seconds < 60 * 365 * SECONDS_PER_DAY {
+            let formatted = format_http_date(at(seconds));
+            assert_eq!(
+                parse_http_date(&formatted),
+                Some(at(seconds)),
+                "{formatted}"
+            );
+            seconds += step;
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_and_out_of_range_dates() {
+        for value in [
+            "",
+            "yesterday",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 06 Nov 1994 08:49 GMT",
+            "Sun, 31 Nov 1994 08:49:37 GMT",
+            "Thu, 29 Feb 2001 08:49:37 GMT",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1969 08:49:37 GMT",
+            "Sun, +6 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-1994 08:49:37 GMT",
+        ] {
+            assert_eq!(parse_http_date(value), None, "{value}");
+        }
+    }
+}
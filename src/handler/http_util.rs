@@ -2,7 +2,8 @@
 //! and referrer sanitizing.
 
 use http::{
-    HeaderMap, HeaderValue, Request, Uri, header::ACCEPT, request::Parts,
+    HeaderMap, HeaderName, HeaderValue, Request, Uri, header::ACCEPT,
+    request::Parts,
 };
 use leptos::prelude::provide_context;
 use leptos_router::{
@@ -29,6 +30,16 @@ pub(super) fn provide_standard_contexts(
 }
 
 pub(super) fn accepts_html(headers: &HeaderMap) -> bool {
+    accepts_any(headers, &["text/html", "application/xhtml+xml"])
+}
+
+/// Whether the client asked for JSON by name. `*/*` does not count, so a
+/// browser navigation keeps its HTML or plain-text error.
+pub(super) fn accepts_problem_json(headers: &HeaderMap) -> bool {
+    accepts_any(headers, &["application/problem+json", "application/json"])
+}
+
+fn accepts_any(headers: &HeaderMap, media_types: &[&str]) -> bool {
     headers
         .get_all(ACCEPT)
         .iter()
@@ -42,7 +53,26 @@ pub(super) fn accepts_html(headers: &HeaderMap) -> bool {
                 .find_map(|value| value.parse::<f32>().ok())
                 .unwrap_or(1.0);
             quality > 0.0
-                && matches!(media_type, "text/html" | "application/xhtml+xml")
+                && media_types
+                    .iter()
+                    .any(|candidate| media_type.eq_ignore_ascii_case(candidate))
+        })
+}
+
+/// Returns the client-supplied request ID under `name`, if it is short
+/// printable ASCII. Anything else could forge log lines or bloat every record.
+pub(super) fn request_id<'a>(
+    headers: &'a HeaderMap,
+    name: &HeaderName,
+) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            value.len() <= 128
+                && value
+                    .bytes()
+                    .all(|byte| byte.is_ascii_graphic() || byte == b' ')
         })
 }
 
@@ -0,0 +1,366 @@
+//! Limits on `multipart/form-data` request bodies.
+//!
+//! A multipart body is streamed to its server function like a
+//! [`StreamingBody`](super::body_stream::StreamingBody), and a [`PartScanner`]
+//! watches the bytes go past. It only finds the boundary delimiters, so it
+//! can count parts and their sizes without parsing them: the parsing is
+//! left to the server function, which sees a body that ends at the first
+//! breach.
+
+use http::{HeaderMap, header::CONTENT_TYPE};
+
+use super::policy::{DEFAULT_MAX_REQUEST_BODY_SIZE, RequestPolicyError};
+
+/// Default maximum number of parts in a multipart body.
+pub const DEFAULT_MAX_MULTIPART_PARTS: usize = 64;
+
+/// Limits applied to `multipart/form-data` request bodies, enabled through
+/// [`HandlerConfig::with_multipart_limits`](super::HandlerConfig::with_multipart_limits).
+///
+/// A part's size includes its own headers. The total size applies on top of
+/// [`HandlerConfig::max_request_body_size`](super::HandlerConfig::max_request_body_size):
+/// the smaller of the two bounds a multipart body.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, MultipartLimits};
+///
+/// let config = HandlerConfig::default()
+///     .with_max_request_body_size(64 * 1024 * 1024)
+///     .with_multipart_limits(
+///         MultipartLimits::new()
+///             .with_max_parts(8)
+///             .with_max_part_size(16 * 1024 * 1024)
+///             .with_max_total_size(32 * 1024 * 1024),
+///     );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MultipartLimits {
+    parts: usize,
+    part_size: usize,
+    total_size: usize,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartLimits {
+    /// Returns limits of 64 parts, each and all together at most 16 MiB.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            parts: DEFAULT_MAX_MULTIPART_PARTS,
+            part_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            total_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+        }
+    }
+
+    /// Returns a copy that accepts at most `parts` parts.
+    #[must_use]
+    pub const fn with_max_parts(mut self, parts: usize) -> Self {
+        self.parts = parts;
+        self
+    }
+
+    /// Returns a copy that accepts parts of at most `bytes` each.
+    #[must_use]
+    pub const fn with_max_part_size(mut self, bytes: usize) -> Self {
+        self.part_size = bytes;
+        self
+    }
+
+    /// Returns a copy that accepts bodies of at most `bytes` in total.
+    #[must_use]
+    pub const fn with_max_total_size(mut self, bytes: usize) -> Self {
+        self.total_size = bytes;
+        self
+    }
+
+    /// Returns the maximum number of parts.
+    #[must_use]
+    pub const fn max_parts(&self) -> usize {
+        self.parts
+    }
+
+    /// Returns the maximum size of one part in bytes.
+    #[must_use]
+    pub const fn max_part_size(&self) -> usize {
+        self.part_size
+    }
+
+    /// Returns the maximum size of the whole body in bytes.
+    #[must_use]
+    pub const fn max_total_size(&self) -> usize {
+        self.total_size
+    }
+}
+
+/// Returns the boundary of a `multipart/form-data` body, judged by its
+/// `Content-Type`.
+pub(super) fn multipart_boundary(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    let mut params = content_type.split(';');
+    let essence = params.next()?.trim();
+    if !essence.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("boundary") {
+            return None;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        // RFC 2046 bounds a boundary at 70 characters.
+        (!value.is_empty() && value.len() <= 70).then(|| value.to_owned())
+    })
+}
+
+/// What the scanner expects after a complete delimiter: a `--` closes the
+/// body, anything else opens the next part.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AfterDelimiter {
+    None,
+    Start,
+    Dash,
+}
+
+/// Counts the parts of a multipart body, and the bytes of each, as chunks
+/// arrive.
+pub(super) struct PartScanner {
+    delimiter: Vec<u8>,
+    failure: Vec<usize>,
+    matched: usize,
+    after: AfterDelimiter,
+    closed: bool,
+    parts: usize,
+    part_bytes: usize,
+    limits: MultipartLimits,
+}
+
+impl PartScanner {
+    pub(super) fn new(boundary: &str, limits: MultipartLimits) -> Self {
+        // RFC 2046 only recognizes a delimiter at the start of a line, so
+        // the CRLF before it is part of the pattern. The body starts
+        // mid-match, as though after a CRLF, so a delimiter opening the body
+        // with no preamble is found too.
+        let delimiter = [b"\r\n--".as_slice(), boundary.as_bytes()].concat();
+        let failure = failure_table(&delimiter);
+        Self {
+            delimiter,
+            failure,
+            matched: 2,
+            after: AfterDelimiter::None,
+            closed: false,
+            parts: 0,
+            part_bytes: 0,
+            limits,
+        }
+    }
+
+    /// Scans `chunk`, returning the first limit it breaches.
+    pub(super) fn feed(
+        &mut self,
+        chunk: &[u8],
+    ) -> Result<(), RequestPolicyError> {
+        for &byte in chunk {
+            if self.closed {
+                // The epilogue after the closing delimiter is ignored.
+                return Ok(());
+            }
+            match self.after {
+                AfterDelimiter::Start if byte == b'-' => {
+                    self.after = AfterDelimiter::Dash;
+                    continue;
+                }
+                AfterDelimiter::Dash if byte == b'-' => {
+                    self.closed = true;
+                    continue;
+                }
+                AfterDelimiter::Start | AfterDelimiter::Dash => {
+                    self.start_part()?;
+                }
+                AfterDelimiter::None => {}
+            }
+            if self.parts > 0 {
+                self.part_bytes = self.part_bytes.saturating_add(1);
+                if self.part_bytes > self.limits.part_size {
+                    return Err(RequestPolicyError::MultipartPartTooLarge {
+                        limit: self.limits.part_size,
+                    });
+                }
+            }
+            self.advance(byte);
+        }
+        Ok(())
+    }
+
+    fn start_part(&mut self) -> Result<(), RequestPolicyError> {
+        self.after = AfterDelimiter::None;
+        self.parts = self.parts.saturating_add(1);
+        self.part_bytes = 0;
+        if self.parts > self.limits.parts {
+            return Err(RequestPolicyError::TooManyMultipartParts {
+                limit: self.limits.parts,
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves the delimiter match on by one byte.
+    fn advance(&mut self, byte: u8) {
+        while self.matched > 0 && self.delimiter[self.matched] != byte {
+            self.matched = self.failure[self.matched - 1];
+        }
+        if self.delimiter[self.matched] == byte {
+            self.matched += 1;
+        }
+        if self.matched == self.delimiter.len() {
+            self.matched = self.failure[self.matched - 1];
+            self.after = AfterDelimiter::Start;
+        }
+    }
+}
+
+/// The Knuth-Morris-Pratt failure table of `pattern`, so a delimiter that
+/// starts partway through a near match, as dash-heavy boundaries do, is
+/// still found.
+fn failure_table(pattern: &[u8]) -> Vec<usize> {
+    let mut failure = vec![0; pattern.len()];
+    let mut matched = 0;
+    for index in 1..pattern.len() {
+        while matched > 0 && pattern[index] != pattern[matched] {
+            matched = failure[matched - 1];
+        }
+        if pattern[index] == pattern[matched] {
+            matched += 1;
+        }
+        failure[index] = matched;
+    }
+    failure
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n\
+        ------x\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+        hello\r\n\
+        ------x\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\n\
+        0123456789\r\n\
+        ------x--\r\n\
+        epilogue ------x\r\n";
+
+    fn scan(
+        limits: MultipartLimits,
+        chunk_size: usize,
+    ) -> Result<(), RequestPolicyError> {
+        scan_body(BODY, limits, chunk_size)
+    }
+
+    fn scan_body(
+        body: &[u8],
+        limits: MultipartLimits,
+        chunk_size: usize,
+    ) -> Result<(), RequestPolicyError> {
+        let mut scanner = PartScanner::new("----x", limits);
+        body.chunks(chunk_size)
+            .try_for_each(|chunk| scanner.feed(chunk))
+    }
+
+    #[test]
+    fn the_boundary_is_read_from_the_content_type() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(multipart_boundary(&headers), None);
+
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("Multipart/Form-Data; boundary=\"a b\""),
+        );
+        assert_eq!(multipart_boundary(&headers).as_deref(), Some("a b"));
+
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("multipart/mixed; boundary=x"),
+        );
+        assert_eq!(multipart_boundary(&headers), None);
+
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("multipart/form-data"),
+        );
+        assert_eq!(multipart_boundary(&headers), None);
+    }
+
+    #[test]
+    fn a_body_within_its_limits_passes_in_any_chunking() {
+        let limits = MultipartLimits::new()
+            .with_max_parts(2)
+            .with_max_part_size(86);
+
+        for chunk_size in 1..=BODY.len() {
+            assert_eq!(
+                scan(limits, chunk_size),
+                Ok(()),
+                "chunks of {chunk_size}"
+            );
+        }
+    }
+
+    #[test]
+    fn parts_past_the_limit_are_rejected() {
+        let limits = MultipartLimits::new().with_max_parts(1);
+
+        for chunk_size in [1, 7, BODY.len()] {
+            assert_eq!(
+                scan(limits, chunk_size),
+                Err(RequestPolicyError::TooManyMultipartParts { limit: 1 })
+            );
+        }
+    }
+
+    #[test]
+    fn an_oversized_part_is_rejected() {
+        let limits = MultipartLimits::new().with_max_part_size(85);
+
+        for chunk_size in [1, 7, BODY.len()] {
+            assert_eq!(
+                scan(limits, chunk_size),
+                Err(RequestPolicyError::MultipartPartTooLarge { limit: 85 })
+            );
+        }
+    }
+
+    #[test]
+    fn a_boundary_mid_line_is_part_of_the_body() {
+        const BODY: &[u8] = b"------x\r\n\
+            Content-Disposition: form-data; name=\"note\"\r\n\r\n\
+            see ------x and ------x--\r\n\
+            ------x--\r\n";
+        let limits = MultipartLimits::new().with_max_parts(1);
+
+        for chunk_size in 1..=BODY.len() {
+            assert_eq!(
+                scan_body(BODY, limits, chunk_size),
+                Ok(()),
+                "chunks of {chunk_size}"
+            );
+        }
+        assert_eq!(
+            scan_body(BODY, limits.with_max_parts(0), BODY.len()),
+            Err(RequestPolicyError::TooManyMultipartParts { limit: 0 })
+        );
+    }
+}
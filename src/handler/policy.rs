@@ -3,12 +3,33 @@
 
 use bytes::Bytes;
 use http::{
-    HeaderMap, HeaderValue, StatusCode,
+    HeaderMap, HeaderName, HeaderValue, StatusCode,
     header::{CONTENT_LENGTH, CONTENT_TYPE},
 };
+use server_fn::ServerFn;
 use thiserror::Error;
 
-use crate::response::{Body, Response};
+#[cfg(feature = "compression")]
+use super::compression::ResponseCompression;
+#[cfg(feature = "compression")]
+use super::decompression::RequestDecompression;
+use super::{
+    body_limits::{BodyLimit, PathPattern, limit_for},
+    client_info::TrustedProxies,
+    csp::ContentSecurityPolicy,
+    error_pages::{ErrorCause, ErrorPages, error_response},
+    header_limits::HeaderLimits,
+    multipart::MultipartLimits,
+    redirects::RedirectPolicy,
+    request_id::{RequestIdPolicy, X_REQUEST_ID},
+    security_headers::SecurityHeaders,
+};
+#[cfg(feature = "cookies")]
+use crate::utils::cookies::CookieKey;
+use crate::{
+    integration::SsrChunking,
+    response::{Body, Response},
+};
 
 /// Default maximum request body size: 16 MiB.
 pub const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 16 * 1024 * 1024;
@@ -16,10 +37,29 @@ pub const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 16 * 1024 * 1024;
 pub(super) const X_CONTENT_TYPE_OPTIONS: &str = "x-content-type-options";
 
 /// Request policy applied while converting incoming WASI HTTP requests.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct HandlerConfig {
     max_request_body_size: usize,
     request_body_timeout_ns: Option<u64>,
+    path_body_limits: Vec<(PathPattern, BodyLimit)>,
+    request_body_streaming: bool,
+    header_limits: Option<HeaderLimits>,
+    multipart_limits: Option<MultipartLimits>,
+    ssr_chunking: SsrChunking,
+    #[cfg(feature = "compression")]
+    response_compression: Option<ResponseCompression>,
+    #[cfg(feature = "compression")]
+    request_decompression: Option<RequestDecompression>,
+    security_headers: Option<SecurityHeaders>,
+    content_security_policy: Option<ContentSecurityPolicy>,
+    error_pages: Option<ErrorPages>,
+    problem_details: bool,
+    #[cfg(feature = "cookies")]
+    cookie_key: Option<CookieKey>,
+    redirect_policy: RedirectPolicy,
+    head_rendering: bool,
+    request_ids: Option<RequestIdPolicy>,
+    trusted_proxies: Option<TrustedProxies>,
 }
 
 impl HandlerConfig {
@@ -63,6 +103,419 @@ impl HandlerConfig {
     pub const fn request_body_timeout_ns(&self) -> Option<u64> {
         self.request_body_timeout_ns
     }
+
+    /// Returns a copy that applies `limit` to requests for exactly `path`,
+    /// in place of the handler-wide size limit and read budget.
+    ///
+    /// The limit is picked from the request path before the body is read,
+    /// so a body over a tighter limit is refused without being buffered.
+    /// Setting the same path again replaces its limit; see [`BodyLimit`].
+    #[must_use]
+    pub fn with_path_body_limit(self, path: &str, limit: BodyLimit) -> Self {
+        self.with_body_limit(PathPattern::Exact(path.to_owned()), limit)
+    }
+
+    /// Returns a copy that applies `limit` to requests for `prefix` and the
+    /// paths below it.
+    ///
+    /// The prefix matches whole segments: `/api/files` covers
+    /// `/api/files/avatar` but not `/api/filesystem`. An exact path limit
+    /// beats any prefix, and a longer prefix beats a shorter one.
+    #[must_use]
+    pub fn with_prefix_body_limit(
+        self,
+        prefix: &str,
+        limit: BodyLimit,
+    ) -> Self {
+        self.with_body_limit(PathPattern::Prefix(prefix.to_owned()), limit)
+    }
+
+    /// Returns a copy that applies `limit` to requests for the server
+    /// function `T`.
+    ///
+    /// This is [`HandlerConfig::with_path_body_limit`] with `T`'s path, which
+    /// includes any prefix it was registered under.
+    #[must_use]
+    pub fn with_server_fn_body_limit<T: ServerFn>(
+        self,
+        limit: BodyLimit,
+    ) -> Self {
+        self.with_path_body_limit(T::PATH, limit)
+    }
+
+    fn with_body_limit(
+        mut self,
+        pattern: PathPattern,
+        limit: BodyLimit,
+    ) -> Self {
+        match self
+            .path_body_limits
+            .iter_mut()
+            .find(|(existing, _)| *existing == pattern)
+        {
+            Some((_, existing)) => *existing = limit,
+            None => self.path_body_limits.push((pattern, limit)),
+        }
+        self
+    }
+
+    /// Returns the size limit and read budget that apply to a request for
+    /// `path`: the most specific path limit, or the handler-wide ones.
+    #[must_use]
+    pub fn body_limit_for(&self, path: &str) -> BodyLimit {
+        let limit = limit_for(&self.path_body_limits, path)
+            .unwrap_or(BodyLimit::new(self.max_request_body_size));
+        match limit.timeout_ns().or(self.request_body_timeout_ns) {
+            Some(nanoseconds) => limit.with_timeout_ns(nanoseconds),
+            None => limit,
+        }
+    }
+
+    /// Returns this config with the handler-wide size limit and read budget
+    /// replaced by those for `path`, for `build_with_config` to apply before
+    /// the body is read.
+    pub(super) fn for_path(mut self, path: &str) -> Self {
+        let limit = self.body_limit_for(path);
+        self.max_request_body_size = limit.max_size();
+        self.request_body_timeout_ns = limit.timeout_ns();
+        self
+    }
+
+    /// Returns a copy that hands request bodies to server functions using a
+    /// streaming input encoding as they arrive.
+    ///
+    /// A request whose `Content-Type` is `application/octet-stream`, which
+    /// `server_fn`'s `Streaming` and `StreamingText` encodings send, is not
+    /// buffered by `build_with_config`: the server function reads it from the
+    /// host input stream instead. The size limit and read budget still apply,
+    /// counted as chunks arrive, and a breach ends the stream and is answered
+    /// with the same `413` or `408` a buffered body receives. Every other
+    /// request is buffered as before. Off by default.
+    #[must_use]
+    pub const fn with_request_body_streaming(mut self) -> Self {
+        self.request_body_streaming = true;
+        self
+    }
+
+    /// Returns whether streaming server function bodies are read as they
+    /// arrive.
+    #[must_use]
+    pub const fn request_body_streaming(&self) -> bool {
+        self.request_body_streaming
+    }
+
+    /// Returns a copy that bounds the header section and the path with
+    /// query under `limits`.
+    ///
+    /// The limits are checked before the request is converted into
+    /// `http` types, and a breach is answered with `431` or `414` without
+    /// the body being read. Off by default, in which case only the host's
+    /// own limits apply.
+    #[must_use]
+    pub const fn with_header_limits(mut self, limits: HeaderLimits) -> Self {
+        self.header_limits = Some(limits);
+        self
+    }
+
+    /// Returns the header limits, if enabled.
+    #[must_use]
+    pub const fn header_limits(&self) -> Option<HeaderLimits> {
+        self.header_limits
+    }
+
+    /// Returns a copy that streams `multipart/form-data` bodies to server
+    /// functions under `limits`.
+    ///
+    /// Like [`HandlerConfig::with_request_body_streaming`], the body is read
+    /// from the host as the server function consumes it, so a file field can
+    /// be written out chunk by chunk instead of being held in memory. The
+    /// number of parts, the size of each part, and the total size are counted
+    /// as chunks arrive; a breach ends the stream and is answered with `413`.
+    /// Off by default, in which case multipart bodies are buffered like any
+    /// other.
+    #[must_use]
+    pub const fn with_multipart_limits(
+        mut self,
+        limits: MultipartLimits,
+    ) -> Self {
+        self.multipart_limits = Some(limits);
+        self
+    }
+
+    /// Returns the multipart limits, if enabled.
+    #[must_use]
+    pub const fn multipart_limits(&self) -> Option<&MultipartLimits> {
+        self.multipart_limits.as_ref()
+    }
+
+    /// Returns a copy that groups SSR HTML into body writes by `chunking`.
+    ///
+    /// The config is chosen per request, so an application can give a
+    /// latency-sensitive route a different policy from a bulk one by picking
+    /// the config from the request path before building the handler.
+    #[must_use]
+    pub const fn with_ssr_chunking(mut self, chunking: SsrChunking) -> Self {
+        self.ssr_chunking = chunking;
+        self
+    }
+
+    /// Returns the SSR write-coalescing policy.
+    #[must_use]
+    pub const fn ssr_chunking(&self) -> SsrChunking {
+        self.ssr_chunking
+    }
+
+    /// Returns a copy that compresses eligible responses on the fly.
+    ///
+    /// Off by default: compression costs guest CPU on every response, and an
+    /// ingress or CDN that already compresses would only see encoded bytes it
+    /// then passes through. See [`ResponseCompression`] for which responses
+    /// are eligible.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub const fn with_response_compression(
+        mut self,
+        compression: ResponseCompression,
+    ) -> Self {
+        self.response_compression = Some(compression);
+        self
+    }
+
+    /// Returns the configured response compression, if enabled.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub const fn response_compression(&self) -> Option<ResponseCompression> {
+        self.response_compression
+    }
+
+    /// Returns a copy that decodes `Content-Encoding` request bodies.
+    ///
+    /// Off by default, in which case an encoded body reaches the application
+    /// as received. [`HandlerConfig::max_request_body_size`] bounds the
+    /// encoded bytes; see [`RequestDecompression`] for the caps on what they
+    /// decode to. An encoded body is always buffered, even when it would
+    /// otherwise be streamed to a server function.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub const fn with_request_decompression(
+        mut self,
+        decompression: RequestDecompression,
+    ) -> Self {
+        self.request_decompression = Some(decompression);
+        self
+    }
+
+    /// Returns the configured request decompression, if enabled.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub const fn request_decompression(&self) -> Option<RequestDecompression> {
+        self.request_decompression
+    }
+
+    /// Returns the largest buffered body a server function may receive: the
+    /// body size limit, or the decoded size cap when that is larger.
+    pub(super) fn buffered_body_limit(&self) -> usize {
+        #[cfg(feature = "compression")]
+        if let Some(decompression) = self.request_decompression {
+            return self
+                .max_request_body_size
+                .max(decompression.max_decoded_size());
+        }
+        self.max_request_body_size
+    }
+
+    /// Returns a copy that adds `headers` to every response.
+    ///
+    /// Off by default, so an ingress that already sets these headers keeps
+    /// sole control of them. Each header is only added when the response does
+    /// not already carry it; see [`SecurityHeaders`].
+    #[must_use]
+    pub const fn with_security_headers(
+        mut self,
+        headers: SecurityHeaders,
+    ) -> Self {
+        self.security_headers = Some(headers);
+        self
+    }
+
+    /// Returns the configured security header policy, if enabled.
+    #[must_use]
+    pub const fn security_headers(&self) -> Option<SecurityHeaders> {
+        self.security_headers
+    }
+
+    /// Returns a copy that sends `policy` with every SSR response.
+    ///
+    /// The header carries that request's nonce and is in place before the
+    /// first body byte, whatever the application renders. A value the
+    /// application writes through
+    /// [`ResponseOptions`](crate::response::ResponseOptions) replaces it.
+    /// Server-function, static, and error responses are not documents and do
+    /// not receive it.
+    #[must_use]
+    pub fn with_content_security_policy(
+        mut self,
+        policy: ContentSecurityPolicy,
+    ) -> Self {
+        self.content_security_policy = Some(policy);
+        self
+    }
+
+    /// Returns the configured Content Security Policy, if enabled.
+    #[must_use]
+    pub const fn content_security_policy(
+        &self,
+    ) -> Option<&ContentSecurityPolicy> {
+        self.content_security_policy.as_ref()
+    }
+
+    /// Returns a copy that answers browsers with `pages` whenever the handler
+    /// itself produces an error: the not-found fallback, a static `405`, and
+    /// request policy rejections.
+    ///
+    /// Requests that do not accept HTML keep the plain-text body, so API
+    /// clients see no change.
+    #[must_use]
+    pub fn with_error_pages(mut self, pages: ErrorPages) -> Self {
+        self.error_pages = Some(pages);
+        self
+    }
+
+    /// Returns the configured error page renderer, if any.
+    #[must_use]
+    pub const fn error_pages(&self) -> Option<&ErrorPages> {
+        self.error_pages.as_ref()
+    }
+
+    /// Returns a copy that answers clients asking for JSON with an RFC 9457
+    /// `application/problem+json` body whenever the handler itself produces
+    /// an error.
+    ///
+    /// A client qualifies when its `Accept` header names
+    /// `application/problem+json` or `application/json`; `*/*` alone does
+    /// not. The document carries a stable `type` URI per cause, such as
+    /// `urn:leptos-wasi:problem:body-too-large`, the configured limit as an
+    /// extension member where one applies, and the request's `x-request-id`
+    /// as `requestId`. Off by default, so existing clients keep the
+    /// plain-text body.
+    #[must_use]
+    pub const fn with_problem_details(mut self) -> Self {
+        self.problem_details = true;
+        self
+    }
+
+    /// Returns whether problem details are enabled.
+    #[must_use]
+    pub const fn problem_details(&self) -> bool {
+        self.problem_details
+    }
+
+    /// Returns a copy that signs and encrypts cookies with `key`.
+    ///
+    /// The key is provided as context to SSR routes and server functions, where
+    /// [`crate::utils::cookies`] finds it. Without one, signed and private
+    /// cookies cannot be written and never verify when read.
+    #[cfg(feature = "cookies")]
+    #[must_use]
+    pub fn with_cookie_key(mut self, key: CookieKey) -> Self {
+        self.cookie_key = Some(key);
+        self
+    }
+
+    /// Returns the configured cookie key, if any.
+    #[cfg(feature = "cookies")]
+    #[must_use]
+    pub const fn cookie_key(&self) -> Option<&CookieKey> {
+        self.cookie_key.as_ref()
+    }
+
+    /// Returns a copy that checks every redirect target against `policy`.
+    ///
+    /// The default allows same-origin paths only. The policy applies to
+    /// [`redirect`](crate::utils::redirect), to a `Location` written through
+    /// [`ResponseOptions`](crate::response::ResponseOptions), and to one a
+    /// server function returns; see [`RedirectPolicy`].
+    #[must_use]
+    pub fn with_redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = policy;
+        self
+    }
+
+    /// Returns the redirect policy.
+    #[must_use]
+    pub const fn redirect_policy(&self) -> &RedirectPolicy {
+        &self.redirect_policy
+    }
+
+    /// Returns a copy that renders the application to answer `HEAD` on an
+    /// SSR route.
+    ///
+    /// By default a `HEAD` on an SSR route is answered without running the
+    /// application: the request contexts and `additional_context` run, so
+    /// headers written there are sent, but components and resources do not.
+    /// Enable this when components set the status or headers, for example
+    /// through [`ResponseOptions`](crate::response::ResponseOptions) or
+    /// `leptos_meta`, and a `HEAD` must report them at the cost of a full
+    /// render. The body is discarded either way.
+    #[must_use]
+    pub const fn with_head_rendering(mut self) -> Self {
+        self.head_rendering = true;
+        self
+    }
+
+    /// Returns whether `HEAD` requests render the application.
+    #[must_use]
+    pub const fn head_rendering(&self) -> bool {
+        self.head_rendering
+    }
+
+    /// Returns a copy that gives every request an ID under `policy`.
+    ///
+    /// A usable incoming ID is kept and anything else is replaced with a
+    /// generated one, before tracing or the application sees the request.
+    /// The ID is provided as a [`RequestId`](super::RequestId) context and
+    /// echoed on the response, with or without the `tracing` feature, so a
+    /// client's bug report can be matched to the host's logs. Off by default.
+    #[must_use]
+    pub fn with_request_ids(mut self, policy: RequestIdPolicy) -> Self {
+        self.request_ids = Some(policy);
+        self
+    }
+
+    /// Returns the request ID policy, if enabled.
+    #[must_use]
+    pub const fn request_ids(&self) -> Option<&RequestIdPolicy> {
+        self.request_ids.as_ref()
+    }
+
+    /// Returns the header a request ID is read from: the policy's, or
+    /// `x-request-id` when request IDs are not enabled.
+    pub(super) fn request_id_header(&self) -> HeaderName {
+        self.request_ids
+            .as_ref()
+            .map_or(X_REQUEST_ID, |policy| policy.header().clone())
+    }
+
+    /// Returns a copy that reads the client from forwarding headers added by
+    /// `proxies`.
+    ///
+    /// The client is provided as a [`ClientInfo`](super::ClientInfo) context,
+    /// and `RequestUrl` becomes the absolute URL on the client's origin, so
+    /// links built from it carry the scheme and host the client used rather
+    /// than the ingress's. An absolute redirect to that origin passes the
+    /// [`RedirectPolicy`] like a same-origin path. Off by default, because
+    /// forwarding headers are only as trustworthy as whoever wrote them.
+    #[must_use]
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = Some(proxies);
+        self
+    }
+
+    /// Returns the trusted proxies, if configured.
+    #[must_use]
+    pub const fn trusted_proxies(&self) -> Option<&TrustedProxies> {
+        self.trusted_proxies.as_ref()
+    }
 }
 
 impl Default for HandlerConfig {
@@ -70,6 +523,25 @@ impl Default for HandlerConfig {
         Self {
             max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
             request_body_timeout_ns: None,
+            path_body_limits: Vec::new(),
+            request_body_streaming: false,
+            header_limits: None,
+            multipart_limits: None,
+            ssr_chunking: SsrChunking::default(),
+            #[cfg(feature = "compression")]
+            response_compression: None,
+            #[cfg(feature = "compression")]
+            request_decompression: None,
+            security_headers: None,
+            content_security_policy: None,
+            error_pages: None,
+            problem_details: false,
+            #[cfg(feature = "cookies")]
+            cookie_key: None,
+            redirect_policy: RedirectPolicy::same_origin(),
+            head_rendering: false,
+            request_ids: None,
+            trusted_proxies: None,
         }
     }
 }
@@ -105,7 +577,7 @@ pub enum RegistrationError {
 }
 
 /// Errors produced while validating request size headers.
-#[derive(Debug, Error)]
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
 #[non_exhaustive]
 pub enum RequestPolicyError {
     /// A Content-Length value was not a valid unsigned byte count.
@@ -126,16 +598,87 @@ pub enum RequestPolicyError {
         /// Configured whole-body read budget in nanoseconds.
         nanoseconds: u64,
     },
+    /// The body's `Content-Encoding` is not one request decompression
+    /// accepts.
+    #[error("unsupported request Content-Encoding")]
+    UnsupportedContentEncoding,
+    /// The body could not be decoded with its `Content-Encoding`.
+    #[error("request body does not match its Content-Encoding")]
+    InvalidEncodedBody,
+    /// The decoded body exceeded the configured limit.
+    #[error("decoded request body exceeds limit of {limit} bytes")]
+    DecodedBodyTooLarge {
+        /// Configured limit for the decoded body in bytes.
+        limit: usize,
+    },
+    /// The decoded body was larger than the configured multiple of the
+    /// received body.
+    #[error("decoded request body exceeds {limit} times its encoded size")]
+    DecompressionRatioExceeded {
+        /// Configured maximum ratio of decoded to received bytes.
+        limit: u32,
+    },
+    /// A multipart body had more parts than the configured limit.
+    #[error("multipart body exceeds limit of {limit} parts")]
+    TooManyMultipartParts {
+        /// Configured maximum number of parts.
+        limit: usize,
+    },
+    /// One part of a multipart body exceeded the configured limit.
+    #[error("multipart part exceeds limit of {limit} bytes")]
+    MultipartPartTooLarge {
+        /// Configured limit for one part in bytes.
+        limit: usize,
+    },
+    /// The request had more header fields than the configured limit.
+    #[error("request exceeds limit of {limit} header fields")]
+    TooManyHeaders {
+        /// Configured maximum number of header fields.
+        limit: usize,
+    },
+    /// The header section exceeded the configured limit.
+    #[error("request headers exceed limit of {limit} bytes")]
+    HeadersTooLarge {
+        /// Configured limit for the header section in bytes.
+        limit: usize,
+    },
+    /// One header value exceeded the configured limit.
+    #[error("request header value exceeds limit of {limit} bytes")]
+    HeaderValueTooLarge {
+        /// Configured limit for one header value in bytes.
+        limit: usize,
+    },
+    /// The path with query exceeded the configured limit.
+    #[error("request URI exceeds limit of {limit} bytes")]
+    UriTooLong {
+        /// Configured limit for the path with query in bytes.
+        limit: usize,
+    },
 }
 
 impl RequestPolicyError {
     pub(super) const fn status(&self) -> StatusCode {
         match self {
-            Self::BodyTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
-            Self::BodyReadTimeout { .. } => StatusCode::REQUEST_TIMEOUT,
-            Self::InvalidContentLength | Self::ConflictingContentLength => {
-                StatusCode::BAD_REQUEST
+            Self::BodyTooLarge { .. }
+            | Self::TooManyMultipartParts { .. }
+            | Self::MultipartPartTooLarge { .. }
+            | Self::DecodedBodyTooLarge { .. }
+            | Self::DecompressionRatioExceeded { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::UnsupportedContentEncoding => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
             }
+            Self::TooManyHeaders { .. }
+            | Self::HeadersTooLarge { .. }
+            | Self::HeaderValueTooLarge { .. } => {
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+            }
+            Self::UriTooLong { .. } => StatusCode::URI_TOO_LONG,
+            Self::BodyReadTimeout { .. } => StatusCode::REQUEST_TIMEOUT,
+            Self::InvalidContentLength
+            | Self::ConflictingContentLength
+            | Self::InvalidEncodedBody => StatusCode::BAD_REQUEST,
         }
     }
 }
@@ -172,7 +715,11 @@ pub(crate) fn validate_content_length(
 }
 
 pub(super) fn policy_response(error: &RequestPolicyError) -> Response {
-    plain_response(error.status(), error.to_string())
+    error_response(
+        error.status(),
+        error.to_string(),
+        ErrorCause::Policy(error.clone()),
+    )
 }
 
 /// Builds one of the crate's own error responses.
@@ -246,6 +793,32 @@ mod tests {
         assert!(error.to_string().contains("30000000"));
     }
 
+    #[test]
+    fn path_limits_replace_the_handler_wide_ones_before_reading() {
+        let config = HandlerConfig::default()
+            .with_max_request_body_size(16 * 1024)
+            .with_request_body_timeout_ns(5_000)
+            .with_path_body_limit("/api/avatar", BodyLimit::new(1))
+            .with_path_body_limit(
+                "/api/avatar",
+                BodyLimit::new(8 * 1024 * 1024).with_timeout_ns(9_000),
+            )
+            .with_prefix_body_limit("/api/admin", BodyLimit::new(512));
+
+        let avatar = config.clone().for_path("/api/avatar");
+        assert_eq!(avatar.max_request_body_size(), 8 * 1024 * 1024);
+        assert_eq!(avatar.request_body_timeout_ns(), Some(9_000));
+
+        // A limit without a budget of its own keeps the handler-wide one.
+        let admin = config.clone().for_path("/api/admin/rename");
+        assert_eq!(admin.max_request_body_size(), 512);
+        assert_eq!(admin.request_body_timeout_ns(), Some(5_000));
+
+        let other = config.for_path("/api/echo");
+        assert_eq!(other.max_request_body_size(), 16 * 1024);
+        assert_eq!(other.request_body_timeout_ns(), Some(5_000));
+    }
+
     #[test]
     fn default_request_limit_is_sixteen_mib() {
         assert_eq!(
@@ -0,0 +1,254 @@
+//! RFC 9457 `application/problem+json` bodies for the handler's own errors.
+//!
+//! A response classified with an [`ErrorCause`] is rewritten here when
+//! [`HandlerConfig::with_problem_details`](super::HandlerConfig::with_problem_details)
+//! is on and the client asked for JSON. Each cause has a fixed `type` URI, so
+//! a client can branch on it instead of matching the English sentence.
+
+use bytes::Bytes;
+use http::{
+    HeaderValue,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+};
+use serde_json::{Map, Value, json};
+
+use super::{error_pages::ErrorCause, policy::RequestPolicyError};
+use crate::response::{Body, Response};
+
+const TYPE_PREFIX: &str = "urn:leptos-wasi:problem:";
+
+impl ErrorCause {
+    /// The stable slug appended to [`TYPE_PREFIX`] to form the `type` URI.
+    const fn problem_slug(&self) -> &'static str {
+        match self {
+            Self::NotFound => "not-found",
+            Self::MethodNotAllowed => "method-not-allowed",
+            Self::Policy(RequestPolicyError::InvalidContentLength) => {
+                "invalid-content-length"
+            }
+            Self::Policy(RequestPolicyError::ConflictingContentLength) => {
+                "conflicting-content-length"
+            }
+            Self::Policy(RequestPolicyError::BodyTooLarge { .. }) => {
+                "body-too-large"
+            }
+            Self::Policy(RequestPolicyError::BodyReadTimeout { .. }) => {
+                "body-read-timeout"
+            }
+            Self::Policy(RequestPolicyError::UnsupportedContentEncoding) => {
+                "unsupported-content-encoding"
+            }
+            Self::Policy(RequestPolicyError::InvalidEncodedBody) => {
+                "invalid-encoded-body"
+            }
+            Self::Policy(RequestPolicyError::DecodedBodyTooLarge {
+                ..
+            }) => "decoded-body-too-large",
+            Self::Policy(RequestPolicyError::DecompressionRatioExceeded {
+                ..
+            }) => "decompression-ratio-exceeded",
+            Self::Policy(RequestPolicyError::TooManyMultipartParts {
+                ..
+            }) => "too-many-multipart-parts",
+            Self::Policy(RequestPolicyError::MultipartPartTooLarge {
+                ..
+            }) => "multipart-part-too-large",
+            Self::Policy(RequestPolicyError::TooManyHeaders { .. }) => {
+                "too-many-headers"
+            }
+            Self::Policy(RequestPolicyError::HeadersTooLarge { .. }) => {
+                "headers-too-large"
+            }
+            Self::Policy(RequestPolicyError::HeaderValueTooLarge {
+                ..
+            }) => "header-value-too-large",
+            Self::Policy(RequestPolicyError::UriTooLong { .. }) => {
+                "uri-too-long"
+            }
+            Self::BodyUnreadable => "body-unreadable",
+            Self::Registration(_) => "registration-failed",
+        }
+    }
+
+    /// Adds the limit behind the error, if any, as extension members.
+    fn problem_extensions(&self, members: &mut Map<String, Value>) {
+        match self {
+            Self::Policy(
+                RequestPolicyError::BodyTooLarge { limit }
+                | RequestPolicyError::TooManyMultipartParts { limit }
+                | RequestPolicyError::MultipartPartTooLarge { limit }
+                | RequestPolicyError::DecodedBodyTooLarge { limit }
+                | RequestPolicyError::TooManyHeaders { limit }
+                | RequestPolicyError::HeadersTooLarge { limit }
+                | RequestPolicyError::HeaderValueTooLarge { limit }
+                | RequestPolicyError::UriTooLong { limit },
+            ) => {
+                members.insert("limit".into(), json!(limit));
+            }
+            Self::Policy(RequestPolicyError::DecompressionRatioExceeded {
+                limit,
+            }) => {
+                members.insert("limit".into(), json!(limit));
+            }
+            Self::Policy(RequestPolicyError::BodyReadTimeout {
+                nanoseconds,
+            }) => {
+                members.insert("nanoseconds".into(), json!(nanoseconds));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rewrites `response` as a problem document, if the handler produced it as
+/// an error.
+pub(super) fn apply_problem_details(
+    response: &mut Response,
+    request_id: Option<&str>,
+) {
+    let Some(cause) = response.0.extensions().get::<ErrorCause>() else {
+        return;
+    };
+    let Body::Sync(message) = response.0.body() else {
+        return;
+    };
+    let status = response.0.status();
+    let mut members = Map::new();
+    members.insert(
+        "type".into(),
+        json!(format!("{TYPE_PREFIX}{}", cause.problem_slug())),
+    );
+    members.insert(
+        "title".into(),
+        json!(status.canonical_reason().unwrap_or("Error")),
+    );
+    members.insert("status".into(), json!(status.as_u16()));
+    members.insert("detail".into(), json!(String::from_utf8_lossy(message)));
+    cause.problem_extensions(&mut members);
+    if let Some(request_id) = request_id {
+        members.insert("requestId".into(), json!(request_id));
+    }
+    let body = Value::Object(members).to_string();
+    *response.0.body_mut() = Body::Sync(Bytes::from(body));
+    let headers = response.0.headers_mut();
+    headers.remove(CONTENT_LENGTH);
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/problem+json"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::handler::{
+        error_pages::error_response, policy::policy_response,
+    };
+
+    fn problem_of(response: &Response) -> Option<Value> {
+        match response.0.body() {
+            Body::Sync(bytes) => serde_json::from_slice(bytes).ok(),
+            Body::Async(_) => None,
+        }
+    }
+
+    #[test]
+    fn a_body_limit_rejection_names_its_type_and_limit() {
+        let mut response =
+            policy_response(&RequestPolicyError::BodyTooLarge { limit: 1024 });
+
+        apply_problem_details(&mut response, Some("req-1"));
+
+        assert_eq!(
+            response.0.headers().get(CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/problem+json"))
+        );
+        assert_eq!(
+            problem_of(&response),
+            Some(json!({
+                "type": "urn:leptos-wasi:problem:body-too-large",
+                "title": "Payload Too Large",
+                "status": 413,
+                "detail": "request body exceeds limit of 1024 bytes",
+                "limit": 1024,
+                "requestId": "req-1",
+            }))
+        );
+    }
+
+    #[test]
+    fn every_policy_error_has_its_own_type() {
+        let errors = [
+            RequestPolicyError::InvalidContentLength,
+            RequestPolicyError::ConflictingContentLength,
+            RequestPolicyError::BodyTooLarge { limit: 1 },
+            RequestPolicyError::BodyReadTimeout { nanoseconds: 1 },
+            RequestPolicyError::TooManyMultipartParts { limit: 1 },
+            RequestPolicyError::MultipartPartTooLarge { limit: 1 },
+            RequestPolicyError::UnsupportedContentEncoding,
+            RequestPolicyError::InvalidEncodedBody,
+            RequestPolicyError::DecodedBodyTooLarge { limit: 1 },
+            RequestPolicyError::DecompressionRatioExceeded { limit: 1 },
+            RequestPolicyError::TooManyHeaders { limit: 1 },
+            RequestPolicyError::HeadersTooLarge { limit: 1 },
+            RequestPolicyError::HeaderValueTooLarge { limit: 1 },
+            RequestPolicyError::UriTooLong { limit: 1 },
+        ];
+        let mut types: Vec<_> = errors
+            .iter()
+            .map(|error| ErrorCause::Policy(error.clone()).problem_slug())
+            .collect();
+        types.sort_unstable();
+        types.dedup();
+
+        assert_eq!(types.len(), errors.len());
+    }
+
+    #[test]
+    fn a_timeout_carries_its_budget_and_no_request_id_when_absent() {
+        let mut response =
+            policy_response(&RequestPolicyError::BodyReadTimeout {
+                nanoseconds: 5,
+            });
+
+        apply_problem_details(&mut response, None);
+
+        let problem = problem_of(&response).expect("body should be JSON");
+        assert_eq!(problem["status"], 408);
+        assert_eq!(problem["nanoseconds"], 5);
+        assert!(problem.get("requestId").is_none());
+    }
+
+    #[test]
+    fn unclassified_responses_are_left_alone() {
+        let mut response = crate::handler::policy::plain_response(
+            StatusCode::NOT_FOUND,
+            "app 404",
+        );
+
+        apply_problem_details(&mut response, None);
+
+        assert!(problem_of(&response).is_none());
+    }
+
+    #[test]
+    fn not_found_has_a_type_but_no_extensions() {
+        let mut response = error_response(
+            StatusCode::NOT_FOUND,
+            "404 not found",
+            ErrorCause::NotFound,
+        );
+
+        apply_problem_details(&mut response, None);
+
+        let problem = problem_of(&response).expect("body should be JSON");
+        assert_eq!(problem["type"], "urn:leptos-wasi:problem:not-found");
+        assert_eq!(
+            problem.as_object().map(Map::len),
+            Some(4),
+            "type, title, status, and detail only"
+        );
+    }
+}
@@ -0,0 +1,432 @@
+//! Which `Location` targets a response may send the client to.
+//!
+//! One [`RedirectPolicy`] covers every way a redirect leaves the handler: the
+//! [`redirect`](crate::utils::redirect) helper, a `Location` written through
+//! [`ResponseOptions`](crate::response::ResponseOptions), and one returned by a
+//! server function. [`HandlerCore::render`](super::core::HandlerCore) checks
+//! the final response, so a target cannot reach the client by any other path.
+
+use http::{HeaderValue, Uri, header::LOCATION};
+use server_fn::redirect::REDIRECT_HEADER;
+
+use super::http_util::sanitize_referrer;
+
+/// The redirect targets a response may carry in `Location`, configured
+/// through [`HandlerConfig::with_redirect_policy`](super::HandlerConfig::with_redirect_policy).
+///
+/// The default allows same-origin paths only: a value starting with a single
+/// `/`, without a backslash or an encoded backslash, or, while a request is
+/// answered, a relative reference such as `edit` or `../list` that resolves
+/// against the request path to one. An absolute URL is
+/// allowed when its origin is on the allowlist, such as an OAuth
+/// authorization server or a payment provider, or is the client's own origin
+/// as reported by [`TrustedProxies`](super::TrustedProxies). Any other target
+/// on a `3xx` response, or on one carrying the server-function redirect
+/// header, is reduced to its same-origin path, or `/` when it has none, and
+/// the rejection is traced. The `Location` of any other response, such as a
+/// `201 Created`, names a resource rather than sending the client anywhere,
+/// and is left alone.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, RedirectPolicy};
+///
+/// let config = HandlerConfig::default().with_redirect_policy(
+///     RedirectPolicy::same_origin()
+///         .allow_origin("https://accounts.example.com")
+///         .allow_origin("https://checkout.payments.example"),
+/// );
+///
+/// let policy = config.redirect_policy();
+/// assert!(policy.allows("/account"));
+/// assert!(policy.allows("https://accounts.example.com/authorize?client_id=1"));
+/// assert!(!policy.allows("https://evil.example.com/"));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RedirectPolicy {
+    allowed_origins: Vec<String>,
+    request_origin: Option<String>,
+    request_path: Option<String>,
+}
+
+impl RedirectPolicy {
+    /// Returns the default policy, which allows same-origin paths only.
+    #[must_use]
+    pub const fn same_origin() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            request_origin: None,
+            request_path: None,
+        }
+    }
+
+    /// Returns a copy that also allows absolute URLs on `origin`.
+    ///
+    /// `origin` is an `http` or `https` scheme and authority, such as
+    /// `https://accounts.example.com` or `https://pay.example:8443`; a path,
+    /// if present, is ignored. The scheme and host compare case-insensitively
+    /// and a default port matches its absence. A value that is not such an
+    /// origin, including one carrying user information, is ignored.
+    #[must_use]
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        if let Ok(uri) = origin.parse::<Uri>()
+            && let Some(origin) = origin_of(&uri)
+            && !self.allowed_origins.contains(&origin)
+        {
+            self.allowed_origins.push(origin);
+        }
+        self
+    }
+
+    /// Returns the allowed external origins, normalized to
+    /// `scheme://host[:port]`.
+    #[must_use]
+    pub fn allowed_origins(&self) -> &[String] {
+        &self.allowed_origins
+    }
+
+    /// Returns whether a response may send the client to `location`.
+    ///
+    /// Applications that build a redirect target from request data can use
+    /// this to answer with an error of their own instead of having the
+    /// target reduced.
+    #[must_use]
+    pub fn allows(&self, location: &str) -> bool {
+        if is_same_origin_path(location)
+            || self.request_path.as_deref().is_some_and(|path| {
+                resolve_relative(path, location)
+                    .is_some_and(|resolved| is_same_origin_path(&resolved))
+            })
+        {
+            return true;
+        }
+        location
+            .parse::<Uri>()
+            .ok()
+            .and_then(|uri| origin_of(&uri))
+            .is_some_and(|origin| {
+                self.allowed_origins.contains(&origin)
+                    || self.request_origin.as_ref() == Some(&origin)
+            })
+    }
+
+    /// Returns a copy that also allows absolute URLs on `origin`, the
+    /// public origin of the request being answered.
+    pub(super) fn with_request_origin(
+        mut self,
+        origin: Option<String>,
+    ) -> Self {
+        self.request_origin = origin;
+        self
+    }
+
+    /// Returns a copy that resolves relative references against `path`, the
+    /// path of the request being answered.
+    pub(super) fn with_request_path(mut self, path: &str) -> Self {
+        self.request_path = Some(path.to_owned());
+        self
+    }
+
+    /// Like [`RedirectPolicy::allows`], but traces a rejected target under
+    /// `source`.
+    pub(crate) fn check(&self, location: &str, source: &'static str) -> bool {
+        let allowed = self.allows(location);
+        if !allowed {
+            trace_rejection(location, source);
+        }
+        allowed
+    }
+
+    /// Reduces a disallowed `Location` on a redirect `response` to its
+    /// same-origin path, or `/`.
+    ///
+    /// A response is a redirect when its status is `3xx`, or when it carries
+    /// the server-function redirect header, which the server-function client
+    /// follows whatever the status.
+    pub(super) fn enforce<B>(
+        &self,
+        response: &mut http::Response<B>,
+        source: &'static str,
+    ) {
+        if !response.status().is_redirection()
+            && !response.headers().contains_key(REDIRECT_HEADER)
+        {
+            return;
+        }
+        let headers = response.headers_mut();
+        let Some(location) = headers.get(LOCATION) else {
+            return;
+        };
+        let location_str = location.to_str().unwrap_or_default();
+        if self.check(location_str, source) {
+            return;
+        }
+        let reduced = sanitize_referrer(location)
+            .unwrap_or_else(|| HeaderValue::from_static("/"));
+        headers.insert(LOCATION, reduced);
+    }
+}
+
+/// Whether `location` is a path on the current origin, by the same rules
+/// `sanitize_referrer` reduces a value with.
+fn is_same_origin_path(location: &str) -> bool {
+    location.starts_with('/')
+        && !location.starts_with("//")
+        && !location.contains('\\')
+        && !location.contains("%5c")
+        && !location.contains("%5C")
+        && HeaderValue::from_str(location).is_ok()
+}
+
+/// Resolves `reference` against the request `path` as RFC 3986 section 5.2
+/// does, or returns `None` when it is not a relative-path reference: one
+/// with a scheme, starting with `/`, or carrying a backslash or whitespace,
+/// which browsers read as `/` or strip.
+fn resolve_relative(path: &str, reference: &str) -> Option<String> {
+    if reference.starts_with('/')
+        || reference
+            .bytes()
+            .any(|byte| byte == b'\\' || byte.is_ascii_whitespace())
+    {
+        return None;
+    }
+    let end = reference.find(['?', '#']).unwrap_or(reference.len());
+    let (reference_path, suffix) = reference.split_at(end);
+    if reference_path
+        .split('/')
+        .next()
+        .is_some_and(|first| first.contains(':'))
+    {
+        return None;
+    }
+    if reference_path.is_empty() {
+        return Some(format!("{path}{suffix}"));
+    }
+    let directory = &path[..=path.rfind('/')?];
+    let merged = format!("{directory}{reference_path}");
+    let segments: Vec<&str> = merged[1..].split('/').collect();
+    let mut output = Vec::with_capacity(segments.len());
+    for (index, segment) in segments.iter().enumerate() {
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." {
+                    output.pop();
+                }
+                if index + 1 == segments.len() {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+    Some(format!("/{}{suffix}", output.join("/")))
+}
+
+/// Normalizes the origin of an absolute `http` or `https` URI.
+pub(super) fn origin_of(uri: &Uri) -> Option<String> {
+    let scheme = uri.scheme_str()?.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => 80,
+        "https" => 443,
+        _ => return None,
+    };
+    let authority = uri.authority()?;
+    if authority.as_str().contains('@') {
+        return None;
+    }
+    let host = authority.host().to_ascii_lowercase();
+    if host.is_empty() {
+        return None;
+    }
+    Some(match authority.port_u16() {
+        Some(port) if port != default_port => {
+            format!("{scheme}://{host}:{port}")
+        }
+        _ => format!("{scheme}://{host}"),
+    })
+}
+
+#[cfg(feature = "tracing")]
+fn trace_rejection(location: &str, source: &'static str) {
+    // Only the origin is recorded: the rest of a redirect target, such as an
+    // OAuth `state` or a session token, does not belong in a log.
+    let target_origin = location
+        .parse::<Uri>()
+        .ok()
+        .and_then(|uri| origin_of(&uri))
+        .unwrap_or_default();
+    tracing::warn!(
+        runtime = "wasi",
+        source,
+        target_origin,
+        "redirect policy rejected Location"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_rejection(_: &str, _: &'static str) {}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+
+    fn enforced_with_status(
+        policy: &RedirectPolicy,
+        status: StatusCode,
+        location: &'static str,
+    ) -> String {
+        let mut response = http::Response::new(());
+        *response.status_mut() = status;
+        response
+            .headers_mut()
+            .insert(LOCATION, HeaderValue::from_static(location));
+        policy.enforce(&mut response, "test");
+        response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .expect("Location should remain")
+            .to_owned()
+    }
+
+    fn enforced(policy: &RedirectPolicy, location: &'static str) -> String {
+        enforced_with_status(policy, StatusCode::FOUND, location)
+    }
+
+    #[test]
+    fn the_default_allows_same_origin_paths_only() {
+        let policy = RedirectPolicy::default();
+
+        assert!(policy.allows("/"));
+        assert!(policy.allows("/account?tab=billing"));
+        assert!(!policy.allows("//evil.example.com/"));
+        assert!(!policy.allows("/\\evil.example.com"));
+        assert!(!policy.allows("/%5Cevil.example.com"));
+        assert!(!policy.allows("https://evil.example.com/"));
+        assert!(!policy.allows("relative/page"));
+    }
+
+    #[test]
+    fn allowlisted_origins_match_by_normalized_origin() {
+        let policy = RedirectPolicy::same_origin()
+            .allow_origin("HTTPS://Accounts.Example.com:443/ignored")
+            .allow_origin("https://pay.example:8443");
+
+        assert_eq!(
+            policy.allowed_origins(),
+            ["https://accounts.example.com", "https://pay.example:8443"]
+        );
+        assert!(policy.allows("https://accounts.example.com/authorize"));
+        assert!(policy.allows("https://ACCOUNTS.example.com:443/x"));
+        assert!(policy.allows("https://pay.example:8443/checkout"));
+        assert!(!policy.allows("http://accounts.example.com/authorize"));
+        assert!(!policy.allows("https://pay.example/checkout"));
+        assert!(!policy.allows("https://accounts.example.com.evil.test/"));
+        assert!(
+            !policy.allows("https://accounts.example.com@evil.test/authorize")
+        );
+    }
+
+    #[test]
+    fn origins_that_are_not_http_origins_are_ignored() {
+        let policy = RedirectPolicy::same_origin()
+            .allow_origin("javascript:alert(1)")
+            .allow_origin("https://user@accounts.example.com")
+            .allow_origin("/relative")
+            .allow_origin("https://accounts.example.com")
+            .allow_origin("https://accounts.example.com");
+
+        assert_eq!(policy.allowed_origins(), ["https://accounts.example.com"]);
+    }
+
+    #[test]
+    fn the_request_origin_is_allowed_when_known() {
+        let policy = RedirectPolicy::same_origin()
+            .with_request_origin(Some("https://shop.example".to_owned()));
+
+        assert!(policy.allows("https://SHOP.example:443/done"));
+        assert!(!policy.allows("http://shop.example/done"));
+        assert!(!RedirectPolicy::same_origin().allows("https://shop.example/"));
+    }
+
+    #[test]
+    fn disallowed_locations_are_reduced_to_a_path() {
+        let policy = RedirectPolicy::same_origin()
+            .allow_origin("https://accounts.example.com");
+
+        assert_eq!(
+            enforced(&policy, "https://accounts.example.com/authorize"),
+            "https://accounts.example.com/authorize"
+        );
+        assert_eq!(
+            enforced(&policy, "https://evil.example.com/steal?a=1"),
+            "/steal?a=1"
+        );
+        assert_eq!(enforced(&policy, "//evil.example.com"), "/");
+        assert_eq!(enforced(&policy, "/kept"), "/kept");
+    }
+
+    #[test]
+    fn locations_on_other_statuses_are_left_alone() {
+        let policy = RedirectPolicy::same_origin();
+
+        assert_eq!(
+            enforced_with_status(
+                &policy,
+                StatusCode::CREATED,
+                "https://api.example.com/items/7"
+            ),
+            "https://api.example.com/items/7"
+        );
+        assert_eq!(
+            enforced_with_status(
+                &policy,
+                StatusCode::SEE_OTHER,
+                "https://evil.example.com/x"
+            ),
+            "/x"
+        );
+    }
+
+    #[test]
+    fn relative_references_resolve_against_the_request_path() {
+        let policy =
+            RedirectPolicy::same_origin().with_request_path("/items/7/view");
+
+        assert!(policy.allows("edit"));
+        assert!(policy.allows("../list?page=2"));
+        assert!(policy.allows("../../../../top"));
+        assert!(policy.allows("?tab=billing"));
+        assert!(policy.allows("./"));
+        assert!(!policy.allows("javascript:alert(1)"));
+        assert!(!policy.allows("\\\\evil.example.com"));
+        assert!(!policy.allows(" //evil.example.com"));
+        assert!(!RedirectPolicy::same_origin().allows("edit"));
+
+        assert_eq!(enforced(&policy, "edit"), "edit");
+        assert_eq!(enforced(&policy, "../list"), "../list");
+        assert_eq!(enforced(&policy, "javascript:alert(1)"), "/");
+    }
+
+    #[test]
+    fn relative_references_remove_dot_segments() {
+        for (reference, resolved) in [
+            ("edit", "/items/7/edit"),
+            ("../list?page=2", "/items/list?page=2"),
+            ("../../../../top", "/top"),
+            ("..", "/items/"),
+            ("./", "/items/7/"),
+            ("a/./b/../c#d", "/items/7/a/c#d"),
+            ("?tab=billing", "/items/7/view?tab=billing"),
+        ] {
+            assert_eq!(
+                resolve_relative("/items/7/view", reference).as_deref(),
+                Some(resolved),
+                "{reference}"
+            );
+        }
+    }
+}
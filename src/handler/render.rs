@@ -14,16 +14,26 @@ use http::{
 use leptos::{
     IntoView,
     hydration::IslandsRouterNavigation,
+    nonce::use_nonce,
     prelude::{Owner, ScopedFuture, provide_context},
 };
 use leptos_meta::ServerMetaContext;
-use leptos_router::SsrMode;
+use leptos_router::{SsrMode, location::RequestUrl};
 
+use super::client_info::ClientInfo;
+#[cfg(feature = "compression")]
+use super::compression::compress_response;
 use super::core::HandlerCore;
+use super::csp::ContentSecurityPolicy;
+use super::error_pages::{ErrorCause, error_response};
 use super::http_util::{
-    accepts_html, is_islands_router_navigation, provide_standard_contexts,
+    accepts_html, accepts_problem_json, is_islands_router_navigation,
+    provide_standard_contexts, request_id,
 };
-use super::policy::{plain_response, set_default_nosniff};
+use super::policy::set_default_nosniff;
+use super::problem::apply_problem_details;
+use super::request_id::RequestId;
+use super::security_headers::apply_security_headers;
 use super::server_fns::apply_server_fn_redirect;
 use crate::{
     integration::{ExtendResponse, PinnedStream},
@@ -43,6 +53,48 @@ impl HandlerCore {
         let best_match = self.ssr_router.best_match(&path);
         let islands_navigation = is_islands_router_navigation(&self.req);
         let is_head = self.req.method() == Method::HEAD;
+        let skip_app = is_head && !self.config.head_rendering();
+        let chunking = self.config.ssr_chunking();
+        let security_headers = self.config.security_headers();
+        let csp = self.config.content_security_policy().cloned();
+        let client = self.config.trusted_proxies().map(|proxies| {
+            let info = proxies.client_info(self.req.headers(), self.req.uri());
+            let url = info.request_url(self.req.uri());
+            (info, url)
+        });
+        let redirect_policy = self
+            .config
+            .redirect_policy()
+            .clone()
+            .with_request_origin(
+                client.as_ref().and_then(|(info, _)| info.origin()),
+            )
+            .with_request_path(&path);
+        #[cfg(feature = "cookies")]
+        let cookie_key = self.config.cookie_key().cloned();
+        let problem_details = (self.config.problem_details()
+            && accepts_problem_json(self.req.headers()))
+        .then(|| {
+            request_id(self.req.headers(), &self.config.request_id_header())
+                .map(str::to_owned)
+        });
+        let echoed_request_id = self.config.request_ids().and_then(|policy| {
+            let id = request_id(self.req.headers(), policy.header())?;
+            let value = HeaderValue::from_str(id).ok()?;
+            Some((policy.header().clone(), value, RequestId::new(id)))
+        });
+        let request_id_context =
+            echoed_request_id.as_ref().map(|(_, _, id)| id.clone());
+        let error_pages = self
+            .config
+            .error_pages()
+            .filter(|_| accepts_html(self.req.headers()))
+            .cloned();
+        #[cfg(feature = "compression")]
+        let compression = self
+            .config
+            .response_compression()
+            .map(|config| (config, config.negotiate(self.req.headers())));
         let (parts, body) = self.req.into_parts();
         let context_parts = parts.clone();
         let req = Request::from_parts(parts, body);
@@ -57,6 +109,15 @@ impl HandlerCore {
                     Some(response)
                 } else if let Some(server_fn) = self.server_fn {
                     provide_standard_contexts(context_parts, res_opts.clone());
+                    provide_client_info(client);
+                    provide_context(redirect_policy.clone());
+                    if let Some(id) = request_id_context {
+                        provide_context(id);
+                    }
+                    #[cfg(feature = "cookies")]
+                    if let Some(key) = cookie_key {
+                        provide_context(key);
+                    }
                     additional_context();
 
                     let accepts_html = accepts_html(req.headers());
@@ -65,11 +126,13 @@ impl HandlerCore {
                         .get(REFERER)
                         .or_else(|| req.headers().get("referrer"))
                         .cloned();
-                    let mut response = server_fn(req).await;
+                    let mut response =
+                        server_fn(req, self.streaming_body).await;
                     apply_server_fn_redirect(
                         &mut response,
                         accepts_html,
                         referrer,
+                        &redirect_policy,
                     );
                     Some(response.into())
                 } else if let Some(best_match) = best_match {
@@ -79,9 +142,25 @@ impl HandlerCore {
                     let route_context = {
                         let res_opts = res_opts.clone();
                         let meta_context = meta_context.clone();
+                        let redirect_policy = redirect_policy.clone();
                         move || {
+                            if let Some(id) = request_id_context {
+                                provide_context(id);
+                            }
                             provide_context(meta_context);
-                            provide_standard_contexts(context_parts, res_opts);
+                            provide_standard_contexts(
+                                context_parts,
+                                res_opts.clone(),
+                            );
+                            provide_client_info(client);
+                            provide_context(redirect_policy);
+                            if let Some(csp) = csp {
+                                insert_content_security_policy(&csp, &res_opts);
+                            }
+                            #[cfg(feature = "cookies")]
+                            if let Some(key) = cookie_key {
+                                provide_context(key);
+                            }
                             if islands_navigation {
                                 provide_context(IslandsRouterNavigation);
                             }
@@ -89,23 +168,38 @@ impl HandlerCore {
                         }
                     };
 
-                    Some(
-                        Response::from_app(
-                            app,
-                            meta_output,
-                            route_context,
-                            res_opts.clone(),
-                            render_mode::<IV>(listing.mode()),
-                            !islands_navigation,
+                    if skip_app {
+                        // The route matched, so the status is known without
+                        // rendering. The request contexts still run, which
+                        // keeps headers written by `additional_context` and
+                        // the Content Security Policy.
+                        route_context();
+                        Some(head_response())
+                    } else {
+                        Some(
+                            Response::from_app(
+                                app,
+                                meta_output,
+                                route_context,
+                                res_opts.clone(),
+                                render_mode::<IV>(listing.mode()),
+                                !islands_navigation,
+                                chunking,
+                            )
+                            .await,
                         )
-                        .await,
-                    )
+                    }
                 } else {
                     None
                 };
 
                 response.map(|mut response| {
                     response.extend_response(&res_opts);
+                    // `extend_response` replaces whatever `Location`
+                    // `apply_server_fn_redirect` settled on, so the policy
+                    // is checked again on the merged value.
+                    redirect_policy
+                        .enforce(&mut response.0, "response_options");
                     response
                 })
             })
@@ -113,16 +207,39 @@ impl HandlerCore {
         let response = render.await;
 
         let mut response = response.unwrap_or_else(|| {
-            plain_response(StatusCode::NOT_FOUND, "404 not found")
+            error_response(
+                StatusCode::NOT_FOUND,
+                "404 not found",
+                ErrorCause::NotFound,
+            )
         });
+        if let Some(request_id) = problem_details {
+            apply_problem_details(&mut response, request_id.as_deref());
+        } else if let Some(pages) = error_pages {
+            pages.apply(&mut response);
+        }
         // Single insertion point for every response this crate emits. It sits
         // after the `extend_response` tail, so an application value merged from
         // `ResponseOptions` is already present and wins, and after the 404
         // fallback, which never reaches that tail.
         set_default_nosniff(&mut response);
+        if let Some((name, value, _)) = echoed_request_id {
+            response.0.headers_mut().entry(name).or_insert(value);
+        }
+        if let Some(policy) = security_headers {
+            apply_security_headers(&mut response, policy);
+        }
+        #[cfg(feature = "compression")]
+        if let Some((config, coding)) = compression {
+            compress_response(&mut response, config, coding, is_head);
+        }
         if is_head {
             *response.0.body_mut() = Body::Sync(Bytes::new());
         } else if !response.0.headers().contains_key(CONTENT_LENGTH)
+            && !matches!(
+                response.0.status(),
+                StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+            )
             && let Body::Sync(bytes) = response.0.body()
             && let Ok(value) = HeaderValue::from_str(&bytes.len().to_string())
         {
@@ -139,6 +256,51 @@ type RenderMode<IV> =
         bool,
     ) -> Pin<Box<dyn Future<Output = PinnedStream<String>> + Send>>;
 
+/// Provides the trusted-proxy [`ClientInfo`], replacing the path-only
+/// `RequestUrl` `provide_standard_contexts` installed with the absolute one.
+fn provide_client_info(client: Option<(ClientInfo, Option<RequestUrl>)>) {
+    let Some((info, url)) = client else {
+        return;
+    };
+    if let Some(url) = url {
+        provide_context(url);
+    }
+    provide_context(info);
+}
+
+/// The answer to `HEAD` on an SSR route when the application is not rendered:
+/// the `200` and content type [`Response::from_app`] would have produced,
+/// without a body or a `Content-Length`.
+///
+/// The body stands in for the rendered stream, of unknown length, so response
+/// compression negotiates `Content-Encoding` and `Vary` as it would for the
+/// `GET`; `render` empties it like any other `HEAD` body.
+fn head_response() -> Response {
+    let mut response = Response(http::Response::new(Body::Async(Box::pin(
+        futures::stream::empty(),
+    ))));
+    response.set_default_content_type("text/html; charset=utf-8");
+    response
+}
+
+/// Writes `policy`, with the nonce `provide_standard_contexts` just
+/// installed, into `response` for `extend_response` to merge.
+///
+/// This runs before the application component, so anything the application
+/// writes under the same name afterwards replaces it.
+fn insert_content_security_policy(
+    policy: &ContentSecurityPolicy,
+    response: &ResponseOptions,
+) {
+    let nonce = use_nonce().map(|nonce| nonce.to_string());
+    if let Some(value) = policy.header_value(nonce.as_deref()) {
+        response.insert_header(policy.header_name(), value);
+    }
+    if let Some((name, value)) = policy.reporting_endpoints() {
+        response.insert_header(name, value);
+    }
+}
+
 // Keep this selection in one place so WASIp2 and WASIp3 cannot drift.
 fn render_mode<IV>(mode: &SsrMode) -> RenderMode<IV>
 where
@@ -207,12 +369,14 @@ mod tests {
         request::Parts,
     };
 
+    use super::super::body_stream::StreamingBody;
     use super::super::http_util::ISLANDS_ROUTER_HEADER;
     use super::super::policy::{
         HandlerConfig, RequestPolicyError, X_CONTENT_TYPE_OPTIONS,
-        policy_response,
+        plain_response, policy_response,
     };
     use super::*;
+    use crate::{ErrorPages, RedirectPolicy, RequestIdPolicy};
     use leptos::prelude::{use_context, view};
     use leptos_router::{
         components::{Route, Router, Routes},
@@ -255,7 +419,7 @@ mod tests {
                 .body(Bytes::new())
                 .expect("test request should be valid");
             let mut core = HandlerCore::new(request, HandlerConfig::default());
-            core.server_fn = Some(Box::new(|_| {
+            core.server_fn = Some(Box::new(|_, _| {
                 Box::pin(async {
                     http::Response::new(Body::Sync(Bytes::from_static(b"ok")))
                 })
@@ -293,26 +457,95 @@ mod tests {
         );
     }
 
-    /// `ResponseOptions` is the documented escape hatch for redirects that
-    /// legitimately leave the origin (OAuth authorization endpoints, payment
-    /// providers). `extend_response` runs *after* `apply_server_fn_redirect`
-    /// and `http`'s `Extend` impl replaces rather than appends, so a
-    /// `Location` written through the reactive context wins outright and is
-    /// never reduced to a path. This pins that contract end to end.
+    /// Renders a form POST whose server function writes `location` through
+    /// `ResponseOptions`, which `extend_response` merges *after*
+    /// `apply_server_fn_redirect` has run.
+    async fn response_options_redirect(
+        config: HandlerConfig,
+        location: &'static str,
+    ) -> Response {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/start_oauth")
+            .header(ACCEPT, "text/html")
+            .header(REFERER, "http://127.0.0.1/previous-page")
+            .body(Bytes::new())
+            .expect("test request should be valid");
+        let mut core = HandlerCore::new(request, config);
+        core.server_fn = Some(Box::new(|_, _| {
+            Box::pin(async {
+                http::Response::new(Body::Sync(Bytes::from_static(b"ok")))
+            })
+        }));
+
+        core.render(
+            || view! { "unused" },
+            move || {
+                use_context::<ResponseOptions>()
+                    .expect("response options should be installed")
+                    .insert_header(
+                        LOCATION,
+                        HeaderValue::from_static(location),
+                    );
+            },
+        )
+        .await
+    }
+
+    /// An OAuth authorization endpoint or payment provider on the redirect
+    /// allowlist reaches the client unchanged through `ResponseOptions`.
     #[tokio::test(flavor = "current_thread")]
-    async fn response_options_location_survives_server_fn_sanitizing() {
+    async fn allowlisted_response_options_location_reaches_the_client() {
         const OFF_ORIGIN: &str =
             "https://accounts.example.com/oauth/authorize?client_id=leptos";
+        let config = HandlerConfig::default().with_redirect_policy(
+            RedirectPolicy::same_origin()
+                .allow_origin("https://accounts.example.com"),
+        );
+
+        let response = response_options_redirect(config, OFF_ORIGIN).await;
 
+        // `apply_server_fn_redirect` saw an html form POST with a `Referer`
+        // and still promoted the status, so this is the same code path a real
+        // form redirect takes.
+        assert_eq!(response.0.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.0.headers().get(LOCATION),
+            Some(&HeaderValue::from_static(OFF_ORIGIN)),
+            "an allowlisted Location set through ResponseOptions must reach \
+             the client unchanged, including the scheme and authority"
+        );
+    }
+
+    /// `ResponseOptions` is not an escape hatch from the redirect policy: an
+    /// origin that is not allowlisted is reduced like any other.
+    #[tokio::test(flavor = "current_thread")]
+    async fn response_options_location_is_checked_after_merging() {
+        let response = response_options_redirect(
+            HandlerConfig::default(),
+            "https://accounts.example.com/oauth/authorize?client_id=leptos",
+        )
+        .await;
+
+        assert_eq!(
+            response.0.headers().get(LOCATION),
+            Some(&HeaderValue::from_static(
+                "/oauth/authorize?client_id=leptos"
+            ))
+        );
+    }
+
+    /// The server-function client follows `Location` whenever the redirect
+    /// header is present, so a `200` carrying both is a redirect too.
+    #[tokio::test(flavor = "current_thread")]
+    async fn server_fn_client_redirects_are_checked_whatever_the_status() {
         let request = Request::builder()
             .method(Method::POST)
             .uri("/api/start_oauth")
-            .header(ACCEPT, "text/html")
-            .header(REFERER, "http://127.0.0.1/previous-page")
             .body(Bytes::new())
             .expect("test request should be valid");
         let mut core = HandlerCore::new(request, HandlerConfig::default());
-        core.server_fn = Some(Box::new(|_| {
+        core.server_fn = Some(Box::new(|_, _| {
             Box::pin(async {
                 http::Response::new(Body::Sync(Bytes::from_static(b"ok")))
             })
@@ -322,31 +555,32 @@ mod tests {
             .render(
                 || view! { "unused" },
                 || {
-                    use_context::<ResponseOptions>()
-                        .expect("response options should be installed")
-                        .insert_header(
-                            LOCATION,
-                            HeaderValue::from_static(OFF_ORIGIN),
-                        );
+                    let options = use_context::<ResponseOptions>()
+                        .expect("response options should be installed");
+                    options.insert_header(
+                        LOCATION,
+                        HeaderValue::from_static("https://evil.example/"),
+                    );
+                    options.insert_header(
+                        http::HeaderName::from_static(
+                            server_fn::redirect::REDIRECT_HEADER,
+                        ),
+                        HeaderValue::from_static(""),
+                    );
                 },
             )
             .await;
 
-        // `apply_server_fn_redirect` saw an html form POST with a `Referer`
-        // and still promoted the status, so this is the same code path a real
-        // form redirect takes.
-        assert_eq!(response.0.status(), StatusCode::FOUND);
+        assert_eq!(response.0.status(), StatusCode::OK);
         assert_eq!(
             response.0.headers().get(LOCATION),
-            Some(&HeaderValue::from_static(OFF_ORIGIN)),
-            "a Location set through ResponseOptions must reach the client \
-             unchanged, including the scheme and authority"
+            Some(&HeaderValue::from_static("/"))
         );
     }
 
-    /// The complementary half: a `Location` the server function wrote onto its
-    /// own `http::Response` is *not* an escape hatch. It is sanitized down to
-    /// a path, and it takes precedence over the `Referer` fallback.
+    /// A `Location` the server function wrote onto its own `http::Response`
+    /// is checked by the same policy. Off the allowlist it is sanitized down
+    /// to a path, and it takes precedence over the `Referer` fallback.
     #[tokio::test(flavor = "current_thread")]
     async fn server_fn_response_location_is_reduced_to_a_path() {
         let request = Request::builder()
@@ -357,7 +591,7 @@ mod tests {
             .body(Bytes::new())
             .expect("test request should be valid");
         let mut core = HandlerCore::new(request, HandlerConfig::default());
-        core.server_fn = Some(Box::new(|_| {
+        core.server_fn = Some(Box::new(|_, _| {
             Box::pin(async {
                 let mut response =
                     http::Response::new(Body::Sync(Bytes::from_static(b"ok")));
@@ -459,6 +693,241 @@ mod tests {
         );
     }
 
+    async fn render_ssr_arm(
+        config: HandlerConfig,
+        additional_context: impl Fn() + 'static + Clone + Send,
+    ) -> Response {
+        let _ = any_spawner::Executor::init_futures_executor();
+
+        let request = Request::builder()
+            .uri("/rendered")
+            .body(Bytes::new())
+            .expect("test request should be valid");
+        HandlerCore::new(request, config)
+            .generate_routes_with_exclusions_and_discovery_context(
+                ssr_arm_app,
+                None,
+                || {},
+            )
+            .expect("route registration should succeed")
+            .render(ssr_arm_app, additional_context)
+            .await
+    }
+
+    /// Sends `HEAD /rendered` through an app that records whether it ran and
+    /// writes `x-from-component` while rendering.
+    async fn render_ssr_head(config: HandlerConfig) -> (Response, bool) {
+        render_ssr_rendered(Method::HEAD, &[], config).await
+    }
+
+    /// Sends `method /rendered` with `headers` through the app
+    /// `render_ssr_head` uses.
+    async fn render_ssr_rendered(
+        method: Method,
+        headers: &[(&str, &str)],
+        config: HandlerConfig,
+    ) -> (Response, bool) {
+        let _ = any_spawner::Executor::init_futures_executor();
+        let rendered = Arc::new(Mutex::new(false));
+
+        let mut request = Request::builder().method(method).uri("/rendered");
+        for &(name, value) in headers {
+            request = request.header(name, value);
+        }
+        let request = request
+            .body(Bytes::new())
+            .expect("test request should be valid");
+        let app = {
+            let rendered = Arc::clone(&rendered);
+            move || {
+                *rendered.lock().expect("render flag should not be poisoned") =
+                    true;
+                if let Some(options) = use_context::<ResponseOptions>() {
+                    options.insert_header(
+                        http::header::HeaderName::from_static(
+                            "x-from-component",
+                        ),
+                        HeaderValue::from_static("1"),
+                    );
+                }
+                ssr_arm_app()
+            }
+        };
+        let response = HandlerCore::new(request, config)
+            .generate_routes_with_exclusions_and_discovery_context(
+                ssr_arm_app,
+                None,
+                || {},
+            )
+            .expect("route registration should succeed")
+            .render(app, || {
+                use_context::<ResponseOptions>()
+                    .expect("response options should be installed")
+                    .insert_header(
+                        http::header::HeaderName::from_static("x-from-context"),
+                        HeaderValue::from_static("1"),
+                    );
+            })
+            .await;
+        let rendered =
+            *rendered.lock().expect("render flag should not be poisoned");
+        (response, rendered)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn head_on_an_ssr_route_does_not_run_the_application() {
+        let (response, rendered) = render_ssr_head(
+            HandlerConfig::default()
+                .with_content_security_policy(ContentSecurityPolicy::new()),
+        )
+        .await;
+
+        assert!(!rendered, "HEAD must not render the application");
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(
+            header_of(&response, "content-type"),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(header_of(&response, "x-from-context"), Some("1"));
+        assert_eq!(header_of(&response, "x-from-component"), None);
+        assert!(header_of(&response, "content-security-policy").is_some());
+        assert_eq!(header_of(&response, "content-length"), None);
+        assert!(
+            matches!(response.0.body(), Body::Sync(body) if body.is_empty())
+        );
+    }
+
+    /// A cache or probe sees the same representation metadata for the
+    /// skipped `HEAD` as for the `GET` it stands for.
+    #[cfg(feature = "compression")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn a_skipped_head_negotiates_compression_like_its_get() {
+        let config = HandlerConfig::default()
+            .with_response_compression(crate::ResponseCompression::default());
+        let accept = [("accept-encoding", "gzip")];
+        let (head, rendered) =
+            render_ssr_rendered(Method::HEAD, &accept, config.clone()).await;
+        let (get, _) = render_ssr_rendered(Method::GET, &accept, config).await;
+
+        assert!(!rendered, "HEAD must not render the application");
+        for name in ["content-encoding", "vary", "content-length"] {
+            assert_eq!(
+                header_of(&head, name),
+                header_of(&get, name),
+                "{name} must match the GET"
+            );
+        }
+        assert_eq!(header_of(&head, "content-encoding"), Some("gzip"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn head_rendering_learns_headers_from_components() {
+        let (response, rendered) =
+            render_ssr_head(HandlerConfig::default().with_head_rendering())
+                .await;
+
+        assert!(rendered, "HEAD must render the application when enabled");
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(header_of(&response, "x-from-component"), Some("1"));
+        assert_eq!(header_of(&response, "x-from-context"), Some("1"));
+        assert!(
+            matches!(response.0.body(), Body::Sync(body) if body.is_empty())
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn content_security_policy_carries_the_request_nonce() {
+        let seen = Arc::new(Mutex::new(None));
+        let response = render_ssr_arm(
+            HandlerConfig::default()
+                .with_content_security_policy(ContentSecurityPolicy::new()),
+            {
+                let seen = Arc::clone(&seen);
+                move || {
+                    *seen.lock().expect("nonce slot should not be poisoned") =
+                        use_nonce().map(|nonce| nonce.to_string());
+                }
+            },
+        )
+        .await;
+
+        let nonce = seen
+            .lock()
+            .expect("nonce slot should not be poisoned")
+            .clone()
+            .expect("a nonce should be provided for the SSR arm");
+        let policy = header_of(&response, "content-security-policy")
+            .expect("the policy should be sent");
+        assert!(policy.contains(&format!(
+            "script-src 'self' 'nonce-{nonce}' 'wasm-unsafe-eval'"
+        )));
+        assert!(policy.contains(&format!("style-src 'self' 'nonce-{nonce}'")));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn report_only_policy_uses_its_own_header() {
+        let response = render_ssr_arm(
+            HandlerConfig::default().with_content_security_policy(
+                ContentSecurityPolicy::new()
+                    .report_only()
+                    .with_report_to("csp", "https://example.com/r"),
+            ),
+            || {},
+        )
+        .await;
+
+        assert_eq!(header_of(&response, "content-security-policy"), None);
+        assert!(
+            header_of(&response, "content-security-policy-report-only")
+                .is_some_and(|policy| policy.ends_with("report-to csp"))
+        );
+        assert_eq!(
+            header_of(&response, "reporting-endpoints"),
+            Some("csp=\"https://example.com/r\"")
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn application_content_security_policy_wins() {
+        let response = render_ssr_arm(
+            HandlerConfig::default()
+                .with_content_security_policy(ContentSecurityPolicy::new()),
+            || {
+                use_context::<ResponseOptions>()
+                    .expect("response options should be installed")
+                    .insert_header(
+                        http::header::CONTENT_SECURITY_POLICY,
+                        HeaderValue::from_static("default-src 'none'"),
+                    );
+            },
+        )
+        .await;
+
+        let policies: Vec<_> = response
+            .0
+            .headers()
+            .get_all("content-security-policy")
+            .iter()
+            .collect();
+        assert_eq!(policies, ["default-src 'none'"]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn content_security_policy_skips_non_document_responses() {
+        let core = HandlerCore::new(
+            Request::builder()
+                .uri("/missing")
+                .body(Bytes::new())
+                .expect("test request should be valid"),
+            HandlerConfig::default()
+                .with_content_security_policy(ContentSecurityPolicy::new()),
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(header_of(&response, "content-security-policy"), None);
+    }
+
     #[cfg(feature = "islands-router")]
     mod islands_router_streaming {
         use super::*;
@@ -724,7 +1193,7 @@ mod tests {
                 .expect("test request should be valid"),
             HandlerConfig::default(),
         );
-        core.server_fn = Some(Box::new(|_| {
+        core.server_fn = Some(Box::new(|_, _| {
             Box::pin(async {
                 let mut response =
                     http::Response::new(Body::Sync(Bytes::from_static(b"{}")));
@@ -799,7 +1268,7 @@ mod tests {
                 .expect("test request should be valid"),
             HandlerConfig::default().with_max_request_body_size(limit),
         );
-        core.server_fn = Some(Box::new(move |request| {
+        core.server_fn = Some(Box::new(move |request, _| {
             Box::pin(async move {
                 let (_, bytes) = request.into_parts();
                 assert!(bytes.len() > limit, "fixture must exceed the limit");
@@ -821,6 +1290,44 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn a_streaming_body_reaches_the_server_function_unread() {
+        let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+            Ok(Bytes::from_static(b"first ")),
+            Ok(Bytes::from_static(b"second")),
+        ];
+        let mut core = HandlerCore::new(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/upload")
+                .body(Bytes::new())
+                .expect("test request should be valid"),
+            HandlerConfig::default().with_request_body_streaming(),
+        )
+        .with_streaming_body(StreamingBody::new(
+            Box::pin(futures::stream::iter(chunks)),
+            64,
+            None,
+            "test",
+        ));
+        core.server_fn = Some(Box::new(|request, streaming| {
+            Box::pin(async move {
+                assert!(request.body().is_empty(), "nothing was buffered");
+                let streaming = streaming.expect("the body is handed over");
+                let (bytes, _) = streaming
+                    .into_body::<Bytes>()
+                    .await
+                    .expect("the body fits");
+                http::Response::new(Body::Sync(bytes))
+            })
+        }));
+
+        let response = render_plain(core).await;
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(sync_body_of(&response), b"first second");
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn static_method_not_allowed_carries_nosniff() {
         let response = render_plain(static_asset_core(Method::POST)).await;
@@ -876,7 +1383,7 @@ mod tests {
                 .expect("test request should be valid"),
             HandlerConfig::default(),
         );
-        core.server_fn = Some(Box::new(|_| {
+        core.server_fn = Some(Box::new(|_, _| {
             Box::pin(async {
                 http::Response::new(Body::Sync(Bytes::from_static(b"ok")))
             })
@@ -909,4 +1416,389 @@ mod tests {
             1
         );
     }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn security_headers_are_off_by_default() {
+        let core = HandlerCore::new(
+            Request::builder()
+                .uri("/missing")
+                .body(Bytes::new())
+                .expect("test request should be valid"),
+            HandlerConfig::default(),
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(header_of(&response, "referrer-policy"), None);
+        assert_eq!(header_of(&response, "x-frame-options"), None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn security_headers_reach_responses_that_skip_extend_response() {
+        // The 404 fallback never reaches the `extend_response` tail, so this
+        // proves the policy sits at the single insertion point.
+        let core = HandlerCore::new(
+            Request::builder()
+                .uri("/missing")
+                .body(Bytes::new())
+                .expect("test request should be valid"),
+            HandlerConfig::default().with_security_headers(
+                crate::SecurityHeaders::new().with_hsts(crate::Hsts::default()),
+            ),
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(response.0.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            header_of(&response, "strict-transport-security"),
+            Some("max-age=31536000")
+        );
+        assert_eq!(header_of(&response, "x-frame-options"), Some("DENY"));
+        assert_eq!(nosniff_of(&response), Some("nosniff"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn application_security_header_overrides_win() {
+        let mut core = HandlerCore::new(
+            Request::builder()
+                .uri("/api/echo")
+                .body(Bytes::new())
+                .expect("test request should be valid"),
+            HandlerConfig::default()
+                .with_security_headers(crate::SecurityHeaders::new()),
+        );
+        core.server_fn = Some(Box::new(|_, _| {
+            Box::pin(async {
+                http::Response::new(Body::Sync(Bytes::from_static(b"ok")))
+            })
+        }));
+
+        let response = core
+            .render(
+                || view! { "unused" },
+                || {
+                    use_context::<ResponseOptions>()
+                        .expect("response options should be installed")
+                        .insert_header(
+                            http::header::X_FRAME_OPTIONS,
+                            HeaderValue::from_static("SAMEORIGIN"),
+                        );
+                },
+            )
+            .await;
+
+        assert_eq!(header_of(&response, "x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(
+            header_of(&response, "referrer-policy"),
+            Some("strict-origin-when-cross-origin")
+        );
+    }
+
+    fn error_page_request(
+        uri: &str,
+        method: Method,
+        accept: &str,
+    ) -> Request<Bytes> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(ACCEPT, accept)
+            .body(Bytes::new())
+            .expect("test request should be valid")
+    }
+
+    fn error_page_config() -> HandlerConfig {
+        HandlerConfig::default().with_error_pages(ErrorPages::from_fn(|page| {
+            format!("<h1>{}</h1>", page.status().as_u16())
+        }))
+    }
+
+    fn sync_body_of(response: &Response) -> &[u8] {
+        match response.0.body() {
+            Body::Sync(bytes) => bytes,
+            Body::Async(_) => panic!("error pages are synchronous"),
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn browsers_get_the_error_page_for_the_not_found_fallback() {
+        let core = HandlerCore::new(
+            error_page_request("/missing", Method::GET, "text/html"),
+            error_page_config(),
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(response.0.status(), StatusCode::NOT_FOUND);
+        assert_eq!(sync_body_of(&response), b"<h1>404</h1>");
+        assert_eq!(
+            header_of(&response, "content-type"),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(header_of(&response, "content-length"), Some("12"));
+        assert_eq!(nosniff_of(&response), Some("nosniff"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn api_clients_keep_the_plain_error_body() {
+        let core = HandlerCore::new(
+            error_page_request("/missing", Method::GET, "application/json"),
+            error_page_config(),
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(sync_body_of(&response), b"404 not found");
+        assert_eq!(
+            header_of(&response, "content-type"),
+            Some("text/plain; charset=utf-8")
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn policy_rejections_and_static_405_get_the_error_page() {
+        let rejected = HandlerCore::new(
+            error_page_request("/upload", Method::POST, "text/html"),
+            error_page_config(),
+        )
+        .with_preset(
+            policy_response(&RequestPolicyError::BodyReadTimeout {
+                nanoseconds: 1,
+            }),
+            "request_policy",
+        );
+        let response = render_plain(rejected).await;
+        assert_eq!(response.0.status(), StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(sync_body_of(&response), b"<h1>408</h1>");
+
+        let static_post = HandlerCore::new(
+            error_page_request("/pkg/app.js", Method::POST, "text/html"),
+            error_page_config(),
+        )
+        .static_files_handler("/pkg", |_| None)
+        .expect("static prefix should register");
+        let response = render_plain(static_post).await;
+        assert_eq!(response.0.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(sync_body_of(&response), b"<h1>405</h1>");
+        assert_eq!(header_of(&response, "allow"), Some("GET, HEAD"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn application_error_responses_are_not_replaced() {
+        let core = HandlerCore::new(
+            error_page_request("/any", Method::GET, "text/html"),
+            error_page_config(),
+        )
+        .with_preset(
+            plain_response(StatusCode::NOT_FOUND, "application 404"),
+            "test",
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(sync_body_of(&response), b"application 404");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn json_clients_get_problem_details_with_the_request_id() {
+        let mut request =
+            error_page_request("/missing", Method::GET, "application/json");
+        request
+            .headers_mut()
+            .insert("x-request-id", HeaderValue::from_static("req-7"));
+        let core = HandlerCore::new(
+            request,
+            error_page_config().with_problem_details(),
+        );
+
+        let response = render_plain(core).await;
+
+        assert_eq!(response.0.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            header_of(&response, "content-type"),
+            Some("application/problem+json")
+        );
+        let problem: serde_json::Value =
+            serde_json::from_slice(sync_body_of(&response))
+                .expect("body should be JSON");
+        assert_eq!(problem["type"], "urn:leptos-wasi:problem:not-found");
+        assert_eq!(problem["requestId"], "req-7");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn generated_request_ids_reach_problem_details_and_the_response() {
+        let core = HandlerCore::new(
+            error_page_request("/missing", Method::GET, "application/json"),
+            error_page_config()
+                .with_problem_details()
+                .with_request_ids(RequestIdPolicy::new()),
+        );
+
+        let response = render_plain(core).await;
+
+        let echoed = header_of(&response, "x-request-id")
+            .expect("the generated ID should be echoed");
+        let problem: serde_json::Value =
+            serde_json::from_slice(sync_body_of(&response))
+                .expect("body should be JSON");
+        assert_eq!(problem["requestId"], echoed);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn server_functions_see_the_request_id_that_is_echoed() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/whoami")
+            .header("x-correlation-id", "client-supplied")
+            .body(Bytes::new())
+            .expect("test request should be valid");
+        let mut core = HandlerCore::new(
+            request,
+            HandlerConfig::default().with_request_ids(
+                RequestIdPolicy::new().with_header(
+                    http::header::HeaderName::from_static("x-correlation-id"),
+                ),
+            ),
+        );
+        core.server_fn = Some(Box::new(|_, _| {
+            Box::pin(async {
+                http::Response::new(Body::Sync(Bytes::from(
+                    use_context::<RequestId>()
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                )))
+            })
+        }));
+
+        let response = render_plain(core).await;
+
+        assert_eq!(sync_body_of(&response), b"client-supplied");
+        assert_eq!(
+            header_of(&response, "x-correlation-id"),
+            Some("client-supplied")
+        );
+        assert_eq!(header_of(&response, "x-request-id"), None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn trusted_proxies_describe_the_client_and_its_origin() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("http://ingress.internal/api/checkout?step=2")
+            .header(
+                "forwarded",
+                "for=6.6.6.6, for=203.0.113.7;proto=https;host=shop.example",
+            )
+            .body(Bytes::new())
+            .expect("test request should be valid");
+        let mut core = HandlerCore::new(
+            request,
+            HandlerConfig::default().with_trusted_proxies(
+                crate::TrustedProxies::hops(1)
+                    .with_headers(crate::ForwardingHeaders::Forwarded),
+            ),
+        );
+        core.server_fn = Some(Box::new(|_, _| {
+            Box::pin(async {
+                let client = use_context::<crate::ClientInfo>()
+                    .expect("the client should be provided");
+                let url = use_context::<RequestUrl>()
+                    .expect("the request URL should be provided");
+                let mut response =
+                    http::Response::new(Body::Sync(Bytes::from(format!(
+                        "{} {}",
+                        client
+                            .ip()
+                            .map(|ip| ip.to_string())
+                            .unwrap_or_default(),
+                        url.as_ref()
+                    ))));
+                response.headers_mut().insert(
+                    LOCATION,
+                    HeaderValue::from_static("https://shop.example/done"),
+                );
+                response
+            })
+        }));
+
+        let response = render_plain(core).await;
+
+        assert_eq!(
+            sync_body_of(&response),
+            b"203.0.113.7 https://shop.example/api/checkout?step=2"
+        );
+        assert_eq!(
+            header_of(&response, "location"),
+            Some("https://shop.example/done"),
+            "an absolute redirect to the client's own origin is allowed"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn request_ids_are_not_echoed_unless_enabled() {
+        let mut request =
+            error_page_request("/missing", Method::GET, "text/plain");
+        request
+            .headers_mut()
+            .insert("x-request-id", HeaderValue::from_static("req-7"));
+
+        let response =
+            render_plain(HandlerCore::new(request, HandlerConfig::default()))
+                .await;
+
+        assert_eq!(header_of(&response, "x-request-id"), None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn problem_details_are_opt_in_and_negotiated() {
+        let off = HandlerCore::new(
+            error_page_request("/missing", Method::GET, "application/json"),
+            HandlerConfig::default(),
+        );
+        let response = render_plain(off).await;
+        assert_eq!(sync_body_of(&response), b"404 not found");
+
+        // A browser's `*/*` must not turn its error page into JSON.
+        let browser = HandlerCore::new(
+            error_page_request(
+                "/missing",
+                Method::GET,
+                "text/html,application/xhtml+xml,*/*;q=0.8",
+            ),
+            error_page_config().with_problem_details(),
+        );
+        let response = render_plain(browser).await;
+        assert_eq!(sync_body_of(&response), b"<h1>404</h1>");
+    }
+
+    #[cfg(feature = "cookies")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn server_functions_see_the_configured_cookie_key() {
+        use crate::utils::cookies::{self, CookieKey, SetCookie};
+
+        let key = CookieKey::derive_from(&[1; 32])
+            .expect("32 bytes of key material should be accepted");
+        let mut core = HandlerCore::new(
+            Request::builder()
+                .uri("/api/login")
+                .body(Bytes::new())
+                .expect("test request should be valid"),
+            HandlerConfig::default().with_cookie_key(key),
+        );
+        core.server_fn = Some(Box::new(|_, _| {
+            Box::pin(async {
+                cookies::set_private(SetCookie::new("session", "s3cret"))
+                    .expect("the key should be in context");
+                http::Response::new(Body::Sync(Bytes::from_static(b"ok")))
+            })
+        }));
+
+        let response = render_plain(core).await;
+
+        let set_cookie = header_of(&response, "set-cookie")
+            .expect("the private cookie should be written");
+        assert!(set_cookie.starts_with("session="));
+        assert!(!set_cookie.contains("s3cret"));
+    }
 }
@@ -0,0 +1,232 @@
+//! Request IDs: accepted from the client when well formed, generated
+//! otherwise, provided as context, and echoed on the response.
+//!
+//! The ID is settled in [`HandlerCore::new`](super::core::HandlerCore), by
+//! writing a generated one into the request's own headers. Everything that
+//! reads the header afterwards, such as the tracing span, problem details,
+//! and the application, therefore sees the same value.
+
+use std::fmt;
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+use uuid::Uuid;
+
+use super::http_util::request_id;
+
+/// The header a request ID is read from when no [`RequestIdPolicy`] names
+/// another.
+pub(super) const X_REQUEST_ID: HeaderName =
+    HeaderName::from_static("x-request-id");
+
+/// Crockford's base32 alphabet, which ULIDs are written in.
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// How a generated request ID is written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RequestIdFormat {
+    /// A hyphenated, lowercase version 7 UUID, such as
+    /// `01920e4c-3f6b-7c3a-9a4e-2b1d5f0c8e77`.
+    #[default]
+    UuidV7,
+    /// A 26-character ULID, such as `01J83MRFVB7GX9MKHB3NFGS3VQ`.
+    ///
+    /// It encodes the same 48-bit millisecond timestamp and random bits as
+    /// [`RequestIdFormat::UuidV7`], so IDs of both formats sort by creation
+    /// time.
+    Ulid,
+}
+
+/// Request ID handling, enabled through
+/// [`HandlerConfig::with_request_ids`](super::HandlerConfig::with_request_ids).
+///
+/// A request carrying the header with up to 128 printable ASCII characters
+/// keeps its ID. Any other request, including one whose value is unusable,
+/// gets a freshly generated ID. Either way the ID is provided to SSR routes
+/// and server functions as a [`RequestId`] context, and sent back under the
+/// same header on every response the application has not already set it on.
+///
+/// # Example
+///
+/// ```rust
+/// use http::HeaderName;
+/// use leptos_wasi::{HandlerConfig, RequestIdFormat, RequestIdPolicy};
+///
+/// let config = HandlerConfig::default().with_request_ids(
+///     RequestIdPolicy::new()
+///         .with_header(HeaderName::from_static("x-correlation-id"))
+///         .with_format(RequestIdFormat::Ulid),
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestIdPolicy {
+    header: HeaderName,
+    format: RequestIdFormat,
+}
+
+impl Default for RequestIdPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestIdPolicy {
+    /// Returns a policy using `x-request-id` and version 7 UUIDs.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            header: X_REQUEST_ID,
+            format: RequestIdFormat::UuidV7,
+        }
+    }
+
+    /// Returns a copy that reads and echoes the ID under `header`.
+    #[must_use]
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Returns a copy that generates IDs in `format`.
+    #[must_use]
+    pub const fn with_format(mut self, format: RequestIdFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Returns the header the ID is read from and echoed under.
+    #[must_use]
+    pub const fn header(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// Returns the format generated IDs are written in.
+    #[must_use]
+    pub const fn format(&self) -> RequestIdFormat {
+        self.format
+    }
+
+    /// Keeps a usable ID in `headers`, or replaces whatever is there with a
+    /// generated one.
+    pub(super) fn resolve(&self, headers: &mut HeaderMap) {
+        if request_id(headers, &self.header).is_some() {
+            return;
+        }
+        let generated = generate(self.format);
+        if let Ok(value) = HeaderValue::from_str(&generated) {
+            headers.insert(self.header.clone(), value);
+        }
+    }
+}
+
+/// The current request's ID, provided as context to SSR routes and server
+/// functions when [`RequestIdPolicy`] is enabled.
+///
+/// ```rust
+/// use leptos::prelude::use_context;
+/// use leptos_wasi::RequestId;
+///
+/// fn log_failure(message: &str) {
+///     if let Some(id) = use_context::<RequestId>() {
+///         eprintln!("[{id}] {message}");
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub(super) fn new(id: &str) -> Self {
+        Self(id.to_owned())
+    }
+
+    /// Returns the ID as sent in the header.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn generate(format: RequestIdFormat) -> String {
+    let id = Uuid::now_v7();
+    match format {
+        RequestIdFormat::UuidV7 => id.hyphenated().to_string(),
+        RequestIdFormat::Ulid => ulid(id.as_u128()),
+    }
+}
+
+/// Writes 128 bits as a ULID: 26 Crockford base32 digits, most significant
+/// first, the leading one carrying only the top three bits.
+fn ulid(value: u128) -> String {
+    (0..26)
+        .rev()
+        .map(|digit| {
+            let index = (value >> (digit * 5)) & 0x1f;
+            usize::try_from(index)
+                .map_or('0', |index| char::from(CROCKFORD[index]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(policy: &RequestIdPolicy, incoming: Option<&str>) -> String {
+        let mut headers = HeaderMap::new();
+        if let Some(incoming) = incoming {
+            headers.insert(
+                policy.header().clone(),
+                HeaderValue::from_str(incoming).expect("test ID is valid"),
+            );
+        }
+        policy.resolve(&mut headers);
+        headers
+            .get(policy.header())
+            .and_then(|value| value.to_str().ok())
+            .expect("an ID should be present")
+            .to_owned()
+    }
+
+    #[test]
+    fn a_usable_incoming_id_is_kept() {
+        assert_eq!(resolved(&RequestIdPolicy::new(), Some("req-42")), "req-42");
+    }
+
+    #[test]
+    fn missing_or_unusable_ids_are_replaced_with_a_uuid_v7() {
+        for incoming in [None, Some(&*"x".repeat(129))] {
+            let id = resolved(&RequestIdPolicy::new(), incoming);
+            let parsed = Uuid::parse_str(&id).expect("a UUID is generated");
+
+            assert_eq!(parsed.get_version_num(), 7);
+            assert_eq!(id, parsed.hyphenated().to_string());
+        }
+    }
+
+    #[test]
+    fn ulids_are_26_crockford_digits_under_the_configured_header() {
+        let policy = RequestIdPolicy::new()
+            .with_header(HeaderName::from_static("x-correlation-id"))
+            .with_format(RequestIdFormat::Ulid);
+
+        let id = resolved(&policy, None);
+
+        assert_eq!(id.len(), 26);
+        assert!(id.bytes().all(|byte| CROCKFORD.contains(&byte)));
+        assert!(id.as_bytes()[0] <= b'7', "a ULID fits in 128 bits");
+    }
+
+    #[test]
+    fn ulid_encoding_matches_the_reference_layout() {
+        assert_eq!(ulid(0), "00000000000000000000000000");
+        assert_eq!(ulid(u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+        assert_eq!(ulid(32), "00000000000000000000000010");
+    }
+}
@@ -0,0 +1,748 @@
+//! Opt-in security response headers.
+//!
+//! [`HandlerCore::render`](super::core::HandlerCore) applies the configured
+//! [`SecurityHeaders`] at the same point as `x-content-type-options`, with
+//! the same rule: a header the application already set is left alone.
+
+use http::{
+    HeaderMap, HeaderName, HeaderValue,
+    header::{
+        CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY,
+        X_FRAME_OPTIONS,
+    },
+};
+
+use crate::response::Response;
+
+const PERMISSIONS_POLICY: &str = "permissions-policy";
+const CROSS_ORIGIN_OPENER_POLICY: &str = "cross-origin-opener-policy";
+const CROSS_ORIGIN_EMBEDDER_POLICY: &str = "cross-origin-embedder-policy";
+const CROSS_ORIGIN_RESOURCE_POLICY: &str = "cross-origin-resource-policy";
+
+/// One year, the `max-age` HSTS preload lists require.
+const DEFAULT_HSTS_MAX_AGE: u64 = 365 * 24 * 60 * 60;
+
+/// Security response headers, enabled through
+/// [`HandlerConfig::with_security_headers`](super::HandlerConfig::with_security_headers).
+///
+/// [`SecurityHeaders::new`] is a baseline that is safe for a typical Leptos
+/// application: `Referrer-Policy: strict-origin-when-cross-origin`, framing
+/// denied, `Cross-Origin-Opener-Policy: same-origin`, and
+/// `Cross-Origin-Resource-Policy: same-origin`. HSTS,
+/// `Cross-Origin-Embedder-Policy`, and `Permissions-Policy` commit a
+/// deployment to something only it can judge, so they are added explicitly.
+/// [`SecurityHeaders::none`] starts from nothing.
+///
+/// Every header is insert-if-absent, like `x-content-type-options`: a value
+/// the application set through
+/// [`ResponseOptions`](crate::response::ResponseOptions) or a static asset's
+/// own headers wins. The one exception is `frame-ancestors`, which is sent as
+/// its own `Content-Security-Policy` header alongside any the application
+/// set. Browsers enforce every CSP header they receive, so the extra header
+/// only forbids framing and cannot relax the application's policy; it is
+/// skipped when an existing policy already has a `frame-ancestors` directive,
+/// or when the application set its own `X-Frame-Options`, which
+/// `frame-ancestors` would override.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{FrameOptions, HandlerConfig, Hsts, SecurityHeaders};
+///
+/// let config = HandlerConfig::default().with_security_headers(
+///     SecurityHeaders::new()
+///         .with_hsts(Hsts::default().include_subdomains())
+///         .with_frame_options(FrameOptions::SameOrigin)
+///         .with_permissions_policy("camera=(), microphone=()"),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SecurityHeaders {
+    hsts: Option<Hsts>,
+    referrer_policy: Option<ReferrerPolicy>,
+    frame_options: Option<FrameOptions>,
+    permissions_policy: Option<&'static str>,
+    opener_policy: Option<CrossOriginOpenerPolicy>,
+    embedder_policy: Option<CrossOriginEmbedderPolicy>,
+    resource_policy: Option<CrossOriginResourcePolicy>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecurityHeaders {
+    /// Returns the baseline policy described on [`SecurityHeaders`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            hsts: None,
+            referrer_policy: Some(ReferrerPolicy::StrictOriginWhenCrossOrigin),
+            frame_options: Some(FrameOptions::Deny),
+            permissions_policy: None,
+            opener_policy: Some(CrossOriginOpenerPolicy::SameOrigin),
+            embedder_policy: None,
+            resource_policy: Some(CrossOriginResourcePolicy::SameOrigin),
+        }
+    }
+
+    /// Returns a policy that sends no headers, to build up from.
+    #[must_use]
+    pub const fn none() -> Self {
+        Self {
+            hsts: None,
+            referrer_policy: None,
+            frame_options: None,
+            permissions_policy: None,
+            opener_policy: None,
+            embedder_policy: None,
+            resource_policy: None,
+        }
+    }
+
+    /// Returns a copy that sends `Strict-Transport-Security`.
+    ///
+    /// Browsers ignore the header over plain HTTP, but honour it for
+    /// `max-age` once seen over HTTPS, so only enable it on a deployment that
+    /// is always reached through TLS.
+    #[must_use]
+    pub const fn with_hsts(mut self, hsts: Hsts) -> Self {
+        self.hsts = Some(hsts);
+        self
+    }
+
+    /// Returns a copy that sends no `Strict-Transport-Security`.
+    #[must_use]
+    pub const fn without_hsts(mut self) -> Self {
+        self.hsts = None;
+        self
+    }
+
+    /// Returns a copy that sends `Referrer-Policy: policy`.
+    #[must_use]
+    pub const fn with_referrer_policy(
+        mut self,
+        policy: ReferrerPolicy,
+    ) -> Self {
+        self.referrer_policy = Some(policy);
+        self
+    }
+
+    /// Returns a copy that sends no `Referrer-Policy`.
+    #[must_use]
+    pub const fn without_referrer_policy(mut self) -> Self {
+        self.referrer_policy = None;
+        self
+    }
+
+    /// Returns a copy that restricts framing with both `X-Frame-Options` and
+    /// the CSP `frame-ancestors` directive.
+    #[must_use]
+    pub const fn with_frame_options(mut self, options: FrameOptions) -> Self {
+        self.frame_options = Some(options);
+        self
+    }
+
+    /// Returns a copy that leaves framing unrestricted.
+    #[must_use]
+    pub const fn without_frame_options(mut self) -> Self {
+        self.frame_options = None;
+        self
+    }
+
+    /// Returns a copy that sends `Permissions-Policy: policy`.
+    ///
+    /// The value is passed through verbatim, for example
+    /// `"camera=(), geolocation=(self)"`. A value that is not a valid header
+    /// value is not sent.
+    #[must_use]
+    pub const fn with_permissions_policy(
+        mut self,
+        policy: &'static str,
+    ) -> Self {
+        self.permissions_policy = Some(policy);
+        self
+    }
+
+    /// Returns a copy that sends no `Permissions-Policy`.
+    #[must_use]
+    pub const fn without_permissions_policy(mut self) -> Self {
+        self.permissions_policy = None;
+        self
+    }
+
+    /// Returns a copy that sends `Cross-Origin-Opener-Policy: policy`.
+    #[must_use]
+    pub const fn with_opener_policy(
+        mut self,
+        policy: CrossOriginOpenerPolicy,
+    ) -> Self {
+        self.opener_policy = Some(policy);
+        self
+    }
+
+    /// Returns a copy that sends no `Cross-Origin-Opener-Policy`.
+    #[must_use]
+    pub const fn without_opener_policy(mut self) -> Self {
+        self.opener_policy = None;
+        self
+    }
+
+    /// Returns a copy that sends `Cross-Origin-Embedder-Policy: policy`.
+    ///
+    /// With `COOP: same-origin` this makes the page cross-origin isolated,
+    /// which `SharedArrayBuffer` requires, but every cross-origin subresource
+    /// must then opt in through CORS or CORP.
+    #[must_use]
+    pub const fn with_embedder_policy(
+        mut self,
+        policy: CrossOriginEmbedderPolicy,
+    ) -> Self {
+        self.embedder_policy = Some(policy);
+        self
+    }
+
+    /// Returns a copy that sends no `Cross-Origin-Embedder-Policy`.
+    #[must_use]
+    pub const fn without_embedder_policy(mut self) -> Self {
+        self.embedder_policy = None;
+        self
+    }
+
+    /// Returns a copy that sends `Cross-Origin-Resource-Policy: policy`.
+    #[must_use]
+    pub const fn with_resource_policy(
+        mut self,
+        policy: CrossOriginResourcePolicy,
+    ) -> Self {
+        self.resource_policy = Some(policy);
+        self
+    }
+
+    /// Returns a copy that sends no `Cross-Origin-Resource-Policy`.
+    #[must_use]
+    pub const fn without_resource_policy(mut self) -> Self {
+        self.resource_policy = None;
+        self
+    }
+
+    /// Returns the configured HSTS policy, if any.
+    #[must_use]
+    pub const fn hsts(&self) -> Option<Hsts> {
+        self.hsts
+    }
+
+    /// Returns the configured referrer policy, if any.
+    #[must_use]
+    pub const fn referrer_policy(&self) -> Option<ReferrerPolicy> {
+        self.referrer_policy
+    }
+
+    /// Returns the configured framing restriction, if any.
+    #[must_use]
+    pub const fn frame_options(&self) -> Option<FrameOptions> {
+        self.frame_options
+    }
+
+    /// Returns the configured permissions policy, if any.
+    #[must_use]
+    pub const fn permissions_policy(&self) -> Option<&'static str> {
+        self.permissions_policy
+    }
+
+    /// Returns the configured opener policy, if any.
+    #[must_use]
+    pub const fn opener_policy(&self) -> Option<CrossOriginOpenerPolicy> {
+        self.opener_policy
+    }
+
+    /// Returns the configured embedder policy, if any.
+    #[must_use]
+    pub const fn embedder_policy(&self) -> Option<CrossOriginEmbedderPolicy> {
+        self.embedder_policy
+    }
+
+    /// Returns the configured resource policy, if any.
+    #[must_use]
+    pub const fn resource_policy(&self) -> Option<CrossOriginResourcePolicy> {
+        self.resource_policy
+    }
+}
+
+/// A `Strict-Transport-Security` policy.
+///
+/// The default is a one-year `max-age` with neither `includeSubDomains` nor
+/// `preload`, since both commit hosts other than this one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Hsts {
+    max_age: u64,
+    include_subdomains: bool,
+    preload: bool,
+}
+
+impl Default for Hsts {
+    fn default() -> Self {
+        Self::new(DEFAULT_HSTS_MAX_AGE)
+    }
+}
+
+impl Hsts {
+    /// Returns a policy with `max-age` set to `seconds`.
+    #[must_use]
+    pub const fn new(seconds: u64) -> Self {
+        Self {
+            max_age: seconds,
+            include_subdomains: false,
+            preload: false,
+        }
+    }
+
+    /// Returns a copy that also covers every subdomain.
+    #[must_use]
+    pub const fn include_subdomains(mut self) -> Self {
+        self.include_subdomains = true;
+        self
+    }
+
+    /// Returns a copy that asks to be added to browser preload lists.
+    ///
+    /// The lists also require `includeSubDomains` and a `max-age` of at
+    /// least one year; this does not add them for you.
+    #[must_use]
+    pub const fn preload(mut self) -> Self {
+        self.preload = true;
+        self
+    }
+
+    fn header_value(self) -> HeaderValue {
+        let mut value = format!("max-age={}", self.max_age);
+        if self.include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if self.preload {
+            value.push_str("; preload");
+        }
+        HeaderValue::from_str(&value)
+            .unwrap_or_else(|_| HeaderValue::from_static("max-age=0"))
+    }
+}
+
+/// A `Referrer-Policy` value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ReferrerPolicy {
+    /// `no-referrer`.
+    NoReferrer,
+    /// `no-referrer-when-downgrade`.
+    NoReferrerWhenDowngrade,
+    /// `origin`.
+    Origin,
+    /// `origin-when-cross-origin`.
+    OriginWhenCrossOrigin,
+    /// `same-origin`.
+    SameOrigin,
+    /// `strict-origin`.
+    StrictOrigin,
+    /// `strict-origin-when-cross-origin`, the browser default.
+    StrictOriginWhenCrossOrigin,
+    /// `unsafe-url`.
+    UnsafeUrl,
+}
+
+impl ReferrerPolicy {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::NoReferrer => "no-referrer",
+            Self::NoReferrerWhenDowngrade => "no-referrer-when-downgrade",
+            Self::Origin => "origin",
+            Self::OriginWhenCrossOrigin => "origin-when-cross-origin",
+            Self::SameOrigin => "same-origin",
+            Self::StrictOrigin => "strict-origin",
+            Self::StrictOriginWhenCrossOrigin => {
+                "strict-origin-when-cross-origin"
+            }
+            Self::UnsafeUrl => "unsafe-url",
+        }
+    }
+}
+
+/// Which pages may frame this one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FrameOptions {
+    /// No page: `X-Frame-Options: DENY` and `frame-ancestors 'none'`.
+    Deny,
+    /// Same-origin pages only: `X-Frame-Options: SAMEORIGIN` and
+    /// `frame-ancestors 'self'`.
+    SameOrigin,
+}
+
+impl FrameOptions {
+    const fn x_frame_options(self) -> &'static str {
+        match self {
+            Self::Deny => "DENY",
+            Self::SameOrigin => "SAMEORIGIN",
+        }
+    }
+
+    const fn frame_ancestors(self) -> &'static str {
+        match self {
+            Self::Deny => "frame-ancestors 'none'",
+            Self::SameOrigin => "frame-ancestors 'self'",
+        }
+    }
+}
+
+/// A `Cross-Origin-Opener-Policy` value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CrossOriginOpenerPolicy {
+    /// `unsafe-none`.
+    UnsafeNone,
+    /// `same-origin-allow-popups`.
+    SameOriginAllowPopups,
+    /// `same-origin`.
+    SameOrigin,
+}
+
+impl CrossOriginOpenerPolicy {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::UnsafeNone => "unsafe-none",
+            Self::SameOriginAllowPopups => "same-origin-allow-popups",
+            Self::SameOrigin => "same-origin",
+        }
+    }
+}
+
+/// A `Cross-Origin-Embedder-Policy` value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CrossOriginEmbedderPolicy {
+    /// `unsafe-none`.
+    UnsafeNone,
+    /// `require-corp`.
+    RequireCorp,
+    /// `credentialless`.
+    Credentialless,
+}
+
+impl CrossOriginEmbedderPolicy {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::UnsafeNone => "unsafe-none",
+            Self::RequireCorp => "require-corp",
+            Self::Credentialless => "credentialless",
+        }
+    }
+}
+
+/// A `Cross-Origin-Resource-Policy` value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CrossOriginResourcePolicy {
+    /// `same-site`.
+    SameSite,
+    /// `same-origin`.
+    SameOrigin,
+    /// `cross-origin`.
+    CrossOrigin,
+}
+
+impl CrossOriginResourcePolicy {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::SameSite => "same-site",
+            Self::SameOrigin => "same-origin",
+            Self::CrossOrigin => "cross-origin",
+        }
+    }
+}
+
+/// Applies `policy` to `response`, leaving every header the response already
+/// carries in place.
+pub(super) fn apply_security_headers(
+    response: &mut Response,
+    policy: SecurityHeaders,
+) {
+    let headers = response.0.headers_mut();
+    if let Some(hsts) = policy.hsts {
+        insert_if_absent(
+            headers,
+            STRICT_TRANSPORT_SECURITY,
+            hsts.header_value(),
+        );
+    }
+    if let Some(referrer) = policy.referrer_policy {
+        insert_if_absent(
+            headers,
+            REFERRER_POLICY,
+            HeaderValue::from_static(referrer.as_str()),
+        );
+    }
+    // An application that chose its own `X-Frame-Options` keeps that choice:
+    // browsers let `frame-ancestors` override it, so none is added either.
+    if let Some(frame) = policy.frame_options
+        && !headers.contains_key(X_FRAME_OPTIONS)
+    {
+        headers.insert(
+            X_FRAME_OPTIONS,
+            HeaderValue::from_static(frame.x_frame_options()),
+        );
+        if !has_frame_ancestors(headers) {
+            headers.append(
+                CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static(frame.frame_ancestors()),
+            );
+        }
+    }
+    if let Some(permissions) = policy.permissions_policy
+        && let Ok(value) = HeaderValue::from_str(permissions)
+    {
+        insert_if_absent(
+            headers,
+            HeaderName::from_static(PERMISSIONS_POLICY),
+            value,
+        );
+    }
+    if let Some(opener) = policy.opener_policy {
+        insert_if_absent(
+            headers,
+            HeaderName::from_static(CROSS_ORIGIN_OPENER_POLICY),
+            HeaderValue::from_static(opener.as_str()),
+        );
+    }
+    if let Some(embedder) = policy.embedder_policy {
+        insert_if_absent(
+            headers,
+            HeaderName::from_static(CROSS_ORIGIN_EMBEDDER_POLICY),
+            HeaderValue::from_static(embedder.as_str()),
+        );
+    }
+    if let Some(resource) = policy.resource_policy {
+        insert_if_absent(
+            headers,
+            HeaderName::from_static(CROSS_ORIGIN_RESOURCE_POLICY),
+            HeaderValue::from_static(resource.as_str()),
+        );
+    }
+}
+
+fn insert_if_absent(
+    headers: &mut HeaderMap,
+    name: HeaderName,
+    value: HeaderValue,
+) {
+    if !headers.contains_key(&name) {
+        headers.insert(name, value);
+    }
+}
+
+/// Whether any `Content-Security-Policy` header already has a
+/// `frame-ancestors` directive. Directive names are case-insensitive.
+fn has_frame_ancestors(headers: &HeaderMap) -> bool {
+    const DIRECTIVE: &str = "frame-ancestors";
+    headers
+        .get_all(CONTENT_SECURITY_POLICY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|policy| policy.split(';'))
+        .any(|directive| {
+            directive
+                .trim_start()
+                .get(..DIRECTIVE.len())
+                .is_some_and(|name| name.eq_ignore_ascii_case(DIRECTIVE))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http::{HeaderValue, header::CONTENT_SECURITY_POLICY};
+
+    use super::*;
+    use crate::response::Body;
+
+    fn applied(
+        policy: SecurityHeaders,
+        existing: &[(&'static str, &'static str)],
+    ) -> Response {
+        let mut response = http::Response::new(Body::Sync(Bytes::new()));
+        for (name, value) in existing {
+            response.headers_mut().append(
+                http::header::HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+        }
+        let mut response = Response::from(response);
+        apply_security_headers(&mut response, policy);
+        response
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+        response
+            .0
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    #[test]
+    fn the_baseline_sends_its_documented_headers() {
+        let response = applied(SecurityHeaders::new(), &[]);
+
+        assert_eq!(
+            header(&response, "referrer-policy"),
+            Some("strict-origin-when-cross-origin")
+        );
+        assert_eq!(header(&response, "x-frame-options"), Some("DENY"));
+        assert_eq!(
+            header(&response, "content-security-policy"),
+            Some("frame-ancestors 'none'")
+        );
+        assert_eq!(
+            header(&response, "cross-origin-opener-policy"),
+            Some("same-origin")
+        );
+        assert_eq!(
+            header(&response, "cross-origin-resource-policy"),
+            Some("same-origin")
+        );
+        // These commit a deployment to more than a library can judge.
+        assert_eq!(header(&response, "strict-transport-security"), None);
+        assert_eq!(header(&response, "cross-origin-embedder-policy"), None);
+        assert_eq!(header(&response, "permissions-policy"), None);
+    }
+
+    #[test]
+    fn none_sends_nothing() {
+        let response = applied(SecurityHeaders::none(), &[]);
+
+        assert!(response.0.headers().is_empty());
+    }
+
+    #[test]
+    fn every_header_can_be_configured() {
+        let policy = SecurityHeaders::none()
+            .with_hsts(Hsts::new(600).include_subdomains().preload())
+            .with_referrer_policy(ReferrerPolicy::NoReferrer)
+            .with_frame_options(FrameOptions::SameOrigin)
+            .with_permissions_policy("camera=()")
+            .with_opener_policy(CrossOriginOpenerPolicy::SameOriginAllowPopups)
+            .with_embedder_policy(CrossOriginEmbedderPolicy::RequireCorp)
+            .with_resource_policy(CrossOriginResourcePolicy::CrossOrigin);
+        let response = applied(policy, &[]);
+
+        assert_eq!(
+            header(&response, "strict-transport-security"),
+            Some("max-age=600; includeSubDomains; preload")
+        );
+        assert_eq!(header(&response, "referrer-policy"), Some("no-referrer"));
+        assert_eq!(header(&response, "x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(
+            header(&response, "content-security-policy"),
+            Some("frame-ancestors 'self'")
+        );
+        assert_eq!(header(&response, "permissions-policy"), Some("camera=()"));
+        assert_eq!(
+            header(&response, "cross-origin-opener-policy"),
+            Some("same-origin-allow-popups")
+        );
+        assert_eq!(
+            header(&response, "cross-origin-embedder-policy"),
+            Some("require-corp")
+        );
+        assert_eq!(
+            header(&response, "cross-origin-resource-policy"),
+            Some("cross-origin")
+        );
+    }
+
+    #[test]
+    fn application_values_win() {
+        let policy = SecurityHeaders::new().with_hsts(Hsts::default());
+        let response = applied(
+            policy,
+            &[
+                ("referrer-policy", "no-referrer"),
+                ("x-frame-options", "SAMEORIGIN"),
+                ("strict-transport-security", "max-age=0"),
+                ("cross-origin-resource-policy", "cross-origin"),
+            ],
+        );
+
+        assert_eq!(header(&response, "referrer-policy"), Some("no-referrer"));
+        assert_eq!(header(&response, "x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(
+            header(&response, "strict-transport-security"),
+            Some("max-age=0")
+        );
+        assert_eq!(
+            header(&response, "cross-origin-resource-policy"),
+            Some("cross-origin")
+        );
+    }
+
+    #[test]
+    fn frame_ancestors_joins_an_application_csp() {
+        let response = applied(
+            SecurityHeaders::new(),
+            &[("content-security-policy", "default-src 'self'")],
+        );
+
+        // A second CSP header can only tighten the first, so it is added
+        // rather than skipped.
+        let policies: Vec<_> = response
+            .0
+            .headers()
+            .get_all(CONTENT_SECURITY_POLICY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        assert_eq!(policies, ["default-src 'self'", "frame-ancestors 'none'"]);
+    }
+
+    #[test]
+    fn an_application_frame_ancestors_directive_wins() {
+        let response = applied(
+            SecurityHeaders::new(),
+            &[(
+                "content-security-policy",
+                "default-src 'self'; Frame-Ancestors https://example.com",
+            )],
+        );
+
+        assert_eq!(
+            response
+                .0
+                .headers()
+                .get_all(CONTENT_SECURITY_POLICY)
+                .iter()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn an_application_x_frame_options_is_not_overridden() {
+        let response = applied(
+            SecurityHeaders::new(),
+            &[("x-frame-options", "SAMEORIGIN")],
+        );
+
+        assert_eq!(header(&response, "x-frame-options"), Some("SAMEORIGIN"));
+        assert_eq!(header(&response, "content-security-policy"), None);
+    }
+
+    #[test]
+    fn an_invalid_permissions_policy_is_not_sent() {
+        let response = applied(
+            SecurityHeaders::none().with_permissions_policy("camera=()\n"),
+            &[],
+        );
+
+        assert_eq!(header(&response, "permissions-policy"), None);
+    }
+}
@@ -8,12 +8,16 @@ use bytes::Bytes;
 use http::{HeaderValue, Request, StatusCode, header::LOCATION};
 use server_fn::{ServerFn, error::ServerFnErrorErr, middleware::Service};
 
-use super::http_util::sanitize_referrer;
+use super::{
+    body_stream::StreamingBody, http_util::sanitize_referrer,
+    redirects::RedirectPolicy,
+};
 use crate::{__private::ServerWithBody, response::Body};
 
 pub(super) type ServerFnHandler = Box<
     dyn Fn(
             Request<Bytes>,
+            Option<StreamingBody>,
         )
             -> Pin<Box<dyn Future<Output = http::Response<Body>> + Send>>
         + Send,
@@ -63,6 +67,7 @@ pub(super) fn apply_server_fn_redirect(
     response: &mut http::Response<Body>,
     accepts_html: bool,
     referrer: Option<HeaderValue>,
+    policy: &RedirectPolicy,
 ) {
     let mut redirect_target = None;
     if accepts_html && let Some(referrer) = referrer {
@@ -78,14 +83,10 @@ pub(super) fn apply_server_fn_redirect(
                 .or_else(|| Some(HeaderValue::from_static("/")));
         }
     }
-    if redirect_target.is_none()
-        && let Some(location) = response.headers().get(LOCATION).cloned()
-    {
-        redirect_target = sanitize_referrer(&location)
-            .or_else(|| Some(HeaderValue::from_static("/")));
-    }
     if let Some(target) = redirect_target {
         response.headers_mut().insert(LOCATION, target);
+    } else {
+        policy.enforce(response, "server_fn");
     }
 }
 
@@ -111,6 +112,8 @@ mod tests {
             &mut response,
             accepts_html,
             referrer.map(HeaderValue::from_static),
+            &RedirectPolicy::same_origin()
+                .allow_origin("https://accounts.example.com"),
         );
         let location = response
             .headers()
@@ -158,6 +161,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn allowlisted_origins_are_kept_for_form_posts_and_api_clients() {
+        const AUTHORIZE: &str = "https://accounts.example.com/authorize";
+
+        assert_eq!(
+            redirected(
+                StatusCode::OK,
+                Some(AUTHORIZE),
+                true,
+                Some("http://127.0.0.1/previous-page")
+            ),
+            (StatusCode::OK, Some(AUTHORIZE.to_owned()))
+        );
+        assert_eq!(
+            redirected(StatusCode::FOUND, Some(AUTHORIZE), false, None),
+            (StatusCode::FOUND, Some(AUTHORIZE.to_owned()))
+        );
+    }
+
     #[test]
     fn api_clients_keep_an_explicit_same_origin_location() {
         assert_eq!(
@@ -166,6 +188,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn locations_on_non_redirect_statuses_are_left_alone() {
+        assert_eq!(
+            redirected(
+                StatusCode::CREATED,
+                Some("https://api.example.com/items/7"),
+                false,
+                None
+            ),
+            (
+                StatusCode::CREATED,
+                Some("https://api.example.com/items/7".to_owned())
+            )
+        );
+    }
+
     #[test]
     fn responses_without_a_location_are_left_alone() {
         assert_eq!(
@@ -0,0 +1,1459 @@
+//! The static-asset response: directory indexes and fallbacks, validators,
+//! conditional requests, and ranges.
+//!
+//! [`HandlerCore::static_assets_handler`](super::core::HandlerCore) resolves
+//! a request path to a [`StaticAsset`]; this module turns that asset into the
+//! response a conditional or ranged `GET`, or a `HEAD`, for it should receive.
+
+use std::{ops::Range, sync::Arc, time::SystemTime};
+
+use bytes::{Bytes, BytesMut};
+use futures::{StreamExt, stream};
+use http::{
+    HeaderMap, HeaderValue, Method, Request, StatusCode,
+    header::{
+        ACCEPT_RANGES, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH,
+        CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED, LOCATION, VARY,
+    },
+};
+use mime_guess::MimeGuess;
+
+use super::byte_ranges::{RangeOutcome, evaluate_range};
+use super::content_coding::coding_quality;
+use super::http_date::{format_http_date, parse_http_date, unix_seconds};
+use super::http_util::accepts_html;
+use crate::{
+    response::{Body, Response},
+    static_files::{
+        AssetSource, Precompressed, SeekableSource, StaticAsset,
+        StaticRouteOptions, content_tag,
+    },
+};
+
+/// Resolves a request below a static prefix to its response, applying the
+/// prefix's directory index, trailing-slash redirect, and fallback document,
+/// or returns `None` for a `404`.
+///
+/// `directory` is whether the request named a directory, by ending in `/` or
+/// being the prefix root; `path` is the normalized relative path, which has
+/// lost any trailing slash.
+pub(super) fn route_response(
+    request: &Request<Bytes>,
+    path: &str,
+    directory: bool,
+    options: &StaticRouteOptions,
+    handler: impl Fn(String) -> Option<StaticAsset>,
+) -> Option<Response> {
+    let index_path = |index: &str| {
+        if path.is_empty() {
+            index.to_owned()
+        } else {
+            format!("{path}/{index}")
+        }
+    };
+    if directory
+        && let Some(index) = options.directory_index()
+        && let Some(response) =
+            static_response(request, &index_path(index), options, &handler)
+    {
+        return Some(response);
+    }
+    if let Some(response) = static_response(request, path, options, &handler) {
+        return Some(response);
+    }
+    if !directory
+        && options.directory_redirect()
+        && let Some(index) = options.directory_index()
+        && handler(index_path(index)).is_some()
+        && let Some(response) = directory_redirect(request)
+    {
+        return Some(response);
+    }
+    let fallback = options
+        .fallback()
+        .filter(|_| accepts_html(request.headers()))?;
+    static_response(request, fallback, options, &handler)
+}
+
+/// `308 Permanent Redirect` from the request path to the same path with a
+/// trailing slash, keeping the query.
+///
+/// A path beginning with `//` would turn into a scheme-relative `Location`
+/// pointing at another host, so it is never redirected.
+pub(super) fn directory_redirect(request: &Request<Bytes>) -> Option<Response> {
+    let path = request.uri().path();
+    if path.starts_with("//") {
+        return None;
+    }
+    let location = match request.uri().query() {
+        Some(query) => format!("{path}/?{query}"),
+        None => format!("{path}/"),
+    };
+    let location = HeaderValue::from_str(&location).ok()?;
+    let mut response = http::Response::new(Body::Sync(Bytes::new()));
+    *response.status_mut() = StatusCode::PERMANENT_REDIRECT;
+    response.headers_mut().insert(LOCATION, location);
+    Some(response.into())
+}
+
+/// Asks `handler` for the asset at `path` and builds its response, or returns
+/// `None` when the callback has nothing there.
+///
+/// With precompressed siblings configured, the siblings the request accepts
+/// are tried first, best quality first; the first one the callback returns is
+/// served with `Content-Encoding` in place of the original.
+pub(super) fn static_response(
+    request: &Request<Bytes>,
+    path: &str,
+    options: &StaticRouteOptions,
+    handler: impl Fn(String) -> Option<StaticAsset>,
+) -> Option<Response> {
+    negotiated_codings(request.headers(), path, options)
+        .into_iter()
+        .find_map(|coding| {
+            handler(format!("{path}.{}", coding.extension())).map(|asset| {
+                asset_response(request, path, asset, Some(coding), options)
+            })
+        })
+        .or_else(|| {
+            handler(path.to_owned()).map(|asset| {
+                asset_response(request, path, asset, None, options)
+            })
+        })
+}
+
+/// The configured codings this request accepts, best quality first and in
+/// server preference order among equals.
+///
+/// A request without `Accept-Encoding` gets the original, which is the one
+/// representation every client can decode. So does the prefix itself, whose
+/// empty path has no file name to add an extension to.
+fn negotiated_codings(
+    headers: &HeaderMap,
+    path: &str,
+    options: &StaticRouteOptions,
+) -> Vec<Precompressed> {
+    if path.is_empty() || path.ends_with('/') {
+        return Vec::new();
+    }
+    let mut accepted = options
+        .precompressed()
+        .iter()
+        .filter_map(|&coding| {
+            coding_quality(headers, coding.content_coding(), coding.aliases())
+                .filter(|&quality| quality > 0)
+                .map(|quality| (coding, quality))
+        })
+        .collect::<Vec<_>>();
+    // Stable, so equal qualities keep the configured order.
+    accepted.sort_by_key(|&(_, quality)| std::cmp::Reverse(quality));
+    accepted.into_iter().map(|(coding, _)| coding).collect()
+}
+
+/// Builds the response for a static asset the callback found at `path`,
+/// stored in `encoding` when it is a precompressed sibling.
+///
+/// A matching `If-None-Match` or `If-Modified-Since` produces `304 Not
+/// Modified` without the body. Otherwise a `GET` with a satisfiable `Range`
+/// produces `206 Partial Content` for an asset whose length is known, and
+/// anything else produces the full asset.
+pub(super) fn asset_response(
+    request: &Request<Bytes>,
+    path: &str,
+    asset: StaticAsset,
+    encoding: Option<Precompressed>,
+    options: &StaticRouteOptions,
+) -> Response {
+    let StaticAsset {
+        source,
+        etag,
+        last_modified,
+        content_type,
+        length: declared_length,
+        headers: extra_headers,
+    } = asset;
+    let etag = etag.or_else(|| match &source {
+        AssetSource::Body(Body::Sync(bytes)) => Some(content_tag(bytes)),
+        AssetSource::Body(Body::Async(_)) | AssetSource::Seekable(_) => None,
+    });
+    // Each encoding is its own representation and needs its own strong tag,
+    // even when the callback derived both from one build hash.
+    let etag = match encoding {
+        Some(coding) => etag.map(|tag| format!("{tag}-{}", coding.extension())),
+        None => etag,
+    };
+    let content_encoding = encoding
+        .map(|coding| HeaderValue::from_static(coding.content_coding()));
+    let mut validators = HeaderMap::new();
+    if let Some(value) = etag
+        .as_deref()
+        .and_then(|tag| HeaderValue::from_str(&format!("\"{tag}\"")).ok())
+    {
+        validators.insert(ETAG, value);
+    }
+    if let Some(value) = last_modified
+        .and_then(|time| HeaderValue::from_str(&format_http_date(time)).ok())
+    {
+        validators.insert(LAST_MODIFIED, value);
+    }
+    // RFC 9110 section 15.4.5: a `304` repeats the `Cache-Control` the full
+    // response would have carried, so it travels with the validators.
+    if let Some(value) = options.cache_control(path) {
+        validators.insert(CACHE_CONTROL, value.clone());
+    }
+    if !options.precompressed().is_empty() {
+        validators.insert(VARY, HeaderValue::from_static("accept-encoding"));
+    }
+
+    if is_not_modified(
+        request.method(),
+        request.headers(),
+        etag.as_deref(),
+        last_modified,
+    ) {
+        let mut response = http::Response::new(Body::Sync(Bytes::new()));
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        *response.headers_mut() = validators;
+        apply_extra_headers(response.headers_mut(), extra_headers);
+        return response.into();
+    }
+
+    let is_head = request.method() == Method::HEAD;
+    let mime = content_type.unwrap_or_else(|| {
+        MimeGuess::from_path(path)
+            .first_or_octet_stream()
+            .to_string()
+    });
+    let source = match source {
+        AssetSource::Body(Body::Sync(bytes)) => Ranged::Bytes(bytes),
+        AssetSource::Seekable(source) => Ranged::Seekable(source),
+        AssetSource::Body(body @ Body::Async(_)) => {
+            // No offset of a plain stream is known, so it is always sent
+            // whole and without `Accept-Ranges`; its length only when the
+            // callback declared one.
+            let body = if is_head {
+                Body::Sync(Bytes::new())
+            } else {
+                body
+            };
+            let mut response = http::Response::new(body);
+            *response.headers_mut() = validators;
+            insert_header(response.headers_mut(), CONTENT_TYPE, &mime);
+            if let Some(value) = content_encoding {
+                response.headers_mut().insert(CONTENT_ENCODING, value);
+            }
+            if let Some(length) = declared_length {
+                insert_header(
+                    response.headers_mut(),
+                    CONTENT_LENGTH,
+                    &length.to_string(),
+                );
+            }
+            apply_extra_headers(response.headers_mut(), extra_headers);
+            return response.into();
+        }
+    };
+
+    let length = source.len();
+    // `GET` is the only method RFC 9110 defines range handling for; a `HEAD`
+    // describes the full representation.
+    let outcome = if request.method() == Method::GET {
+        evaluate_range(
+            request.headers(),
+            length,
+            etag.as_deref(),
+            last_modified,
+        )
+    } else {
+        RangeOutcome::Full
+    };
+    // A top-level `Content-Encoding` on a multipart body would claim the
+    // framing itself is compressed. Rather than repeat it in every part,
+    // send the encoded asset whole, which RFC 9110 always permits.
+    let outcome = match outcome {
+        RangeOutcome::Partial(ranges)
+            if encoding.is_some() && ranges.len() > 1 =>
+        {
+            RangeOutcome::Full
+        }
+        outcome => outcome,
+    };
+    let mut status = StatusCode::OK;
+    let mut headers = validators;
+    let mut body = Body::Sync(Bytes::new());
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    match outcome {
+        RangeOutcome::Full => {
+            insert_header(&mut headers, CONTENT_TYPE, &mime);
+            if let Some(value) = content_encoding {
+                headers.insert(CONTENT_ENCODING, value);
+            }
+            insert_header(&mut headers, CONTENT_LENGTH, &length.to_string());
+            if !is_head {
+                body = source.read(0..length);
+            }
+        }
+        RangeOutcome::Unsatisfiable => {
+            status = StatusCode::RANGE_NOT_SATISFIABLE;
+            // A long-lived policy meant for the asset must not make a cache
+            // keep this error for a year.
+            headers.remove(CACHE_CONTROL);
+            insert_header(
+                &mut headers,
+                CONTENT_RANGE,
+                &format!("bytes */{length}"),
+            );
+        }
+        RangeOutcome::Partial(ranges) => {
+            status = StatusCode::PARTIAL_CONTENT;
+            if let [range] = ranges.as_slice() {
+                insert_header(&mut headers, CONTENT_TYPE, &mime);
+                if let Some(value) = content_encoding {
+                    headers.insert(CONTENT_ENCODING, value);
+                }
+                insert_header(
+                    &mut headers,
+                    CONTENT_RANGE,
+                    &content_range(range, length),
+                );
+                insert_header(
+                    &mut headers,
+                    CONTENT_LENGTH,
+                    &(range.end - range.start).to_string(),
+                );
+                body = source.read(range.clone());
+            } else {
+                let boundary = boundary(etag.as_deref(), length, &ranges);
+                let (multipart, multipart_length) =
+                    multipart_body(&source, &ranges, length, &mime, &boundary);
+                insert_header(
+                    &mut headers,
+                    CONTENT_TYPE,
+                    &format!("multipart/byteranges; boundary={boundary}"),
+                );
+                insert_header(
+                    &mut headers,
+                    CONTENT_LENGTH,
+                    &multipart_length.to_string(),
+                );
+                body = multipart;
+            }
+        }
+    }
+    if status != StatusCode::RANGE_NOT_SATISFIABLE {
+        apply_extra_headers(&mut headers, extra_headers);
+    }
+    let mut response = http::Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    // `nosniff` is applied centrally in `HandlerCore::render`, which every
+    // static response also funnels through.
+    response.into()
+}
+
+/// Merges a callback's extra headers over the ones the handler chose, adding
+/// to `Vary` rather than replacing it.
+fn apply_extra_headers(headers: &mut HeaderMap, extra: HeaderMap) {
+    let mut current = None;
+    for (name, value) in extra {
+        // `HeaderMap::into_iter` names only the first value of each header.
+        if let Some(name) = name {
+            if name != VARY {
+                headers.remove(&name);
+            }
+            current = Some(name);
+        }
+        if let Some(name) = &current {
+            headers.append(name.clone(), value);
+        }
+    }
+}
+
+fn insert_header(headers: &mut HeaderMap, name: http::HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    } else if name == CONTENT_TYPE {
+        headers
+            .insert(name, HeaderValue::from_static("application/octet-stream"));
+    }
+}
+
+/// An asset body whose length is known and which can be read from any
+/// offset.
+enum Ranged {
+    Bytes(Bytes),
+    Seekable(Arc<dyn SeekableSource>),
+}
+
+impl Ranged {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::Seekable(source) => source.len(),
+        }
+    }
+
+    /// Reads `range`, which lies within `0..self.len()`. An empty range is
+    /// never passed to a [`SeekableSource`].
+    fn read(&self, range: Range<u64>) -> Body {
+        match self {
+            Self::Bytes(bytes) => {
+                let start = usize::try_from(range.start).unwrap_or(usize::MAX);
+                let end = usize::try_from(range.end).unwrap_or(usize::MAX);
+                Body::Sync(
+                    bytes.slice(start.min(bytes.len())..end.min(bytes.len())),
+                )
+            }
+            Self::Seekable(_) if range.is_empty() => Body::Sync(Bytes::new()),
+            Self::Seekable(source) => source.read_range(range),
+        }
+    }
+}
+
+fn content_range(range: &Range<u64>, length: u64) -> String {
+    format!("bytes {}-{}/{length}", range.start, range.end - 1)
+}
+
+/// A boundary derived from what the response covers, so the same request
+/// always produces the same bytes.
+///
+/// A 64-bit hash is not proof against the boundary appearing inside a part,
+/// but the parts are the application's own assets, not attacker input.
+fn boundary(etag: Option<&str>, length: u64, ranges: &[Range<u64>]) -> String {
+    let key = format!("{etag:?}/{length}/{ranges:?}");
+    format!("leptos-wasi-{}", content_tag(key.as_bytes()))
+}
+
+/// Frames `ranges` of `source` as a `multipart/byteranges` body and returns
+/// it with its exact length.
+///
+/// An in-memory asset is assembled into one buffer. A seekable source is
+/// streamed part by part, so only one range is being read at a time.
+fn multipart_body(
+    source: &Ranged,
+    ranges: &[Range<u64>],
+    length: u64,
+    mime: &str,
+    boundary: &str,
+) -> (Body, u64) {
+    let mut pieces = Vec::with_capacity(ranges.len() * 2 + 1);
+    let mut total = 0_u64;
+    for range in ranges {
+        let header = Bytes::from(format!(
+            "\r\n--{boundary}\r\nContent-Type: {mime}\r\nContent-Range: {}\r\n\r\n",
+            content_range(range, length),
+        ));
+        total += header.len() as u64 + (range.end - range.start);
+        pieces.push(Body::Sync(header));
+        pieces.push(source.read(range.clone()));
+    }
+    let closing = Bytes::from(format!("\r\n--{boundary}--\r\n"));
+    total += closing.len() as u64;
+    pieces.push(Body::Sync(closing));
+
+    if pieces.iter().all(|piece| matches!(piece, Body::Sync(_))) {
+        let mut buffer =
+            BytesMut::with_capacity(usize::try_from(total).unwrap_or(0));
+        for piece in pieces {
+            if let Body::Sync(bytes) = piece {
+                buffer.extend_from_slice(&bytes);
+            }
+        }
+        return (Body::Sync(buffer.freeze()), total);
+    }
+    let stream = stream::iter(pieces).flat_map(|piece| match piece {
+        Body::Sync(bytes) => stream::once(async move { Ok(bytes) }).boxed(),
+        Body::Async(stream) => stream,
+    });
+    (Body::Async(Box::pin(stream)), total)
+}
+
+/// Evaluates `If-None-Match` and `If-Modified-Since` as RFC 9110 section
+/// 13.2.2 orders them.
+///
+/// `If-None-Match` wins whenever it is present, even when it does not match,
+/// because an entity tag is the more precise validator. `If-Modified-Since`
+/// is only consulted without it, only for `GET` and `HEAD`, and an
+/// unparseable date is treated as an absent header.
+fn is_not_modified(
+    method: &Method,
+    headers: &HeaderMap,
+    etag: Option<&str>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    if !matches!(*method, Method::GET | Method::HEAD) {
+        return false;
+    }
+    if headers.contains_key(IF_NONE_MATCH) {
+        return headers
+            .get_all(IF_NONE_MATCH)
+            .iter()
+            .any(|value| if_none_match_hits(value, etag));
+    }
+    let (Some(last_modified), Some(since)) = (
+        last_modified,
+        headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_http_date),
+    ) else {
+        return false;
+    };
+    unix_seconds(last_modified) <= unix_seconds(since)
+}
+
+/// Whether one `If-None-Match` field value matches the current entity tag.
+///
+/// `If-None-Match` uses the weak comparison, so a `W/` prefix on the
+/// client's tag is ignored. `*` matches any current representation, which a
+/// found asset always has.
+fn if_none_match_hits(value: &HeaderValue, etag: Option<&str>) -> bool {
+    let Ok(value) = value.to_str() else {
+        return false;
+    };
+    if value.trim() == "*" {
+        return true;
+    }
+    etag.is_some_and(|current| {
+        entity_tags(value).any(|(_, opaque)| opaque == current)
+    })
+}
+
+/// Iterates over a comma-separated list of entity tags as
+/// `(is_weak, opaque_tag)` pairs.
+///
+/// The opaque part may itself contain commas, so the list is scanned quote by
+/// quote rather than split. Parsing stops at the first malformed member,
+/// which can only make a precondition fail to match.
+fn entity_tags(list: &str) -> impl Iterator<Item = (bool, &str)> {
+    let mut rest = list;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches([',', ' ', '\t']);
+        if rest.is_empty() {
+            return None;
+        }
+        let (weak, quoted) = match rest.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, rest),
+        };
+        let opaque = quoted.strip_prefix('"')?;
+        let end = opaque.find('"')?;
+        let tag = &opaque[..end];
+        rest = &opaque[end + 1..];
+        Some((weak, tag))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use http::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+    use leptos::prelude::view;
+
+    use super::super::core::HandlerCore;
+    use super::super::policy::HandlerConfig;
+    use super::*;
+
+    const ASSET: &[u8] = b"console.log(1)";
+
+    fn request(method: Method, headers: &[(&str, &str)]) -> Request<Bytes> {
+        let mut builder =
+            Request::builder().method(method).uri("/static/app.js");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder
+            .body(Bytes::new())
+            .expect("test request should be valid")
+    }
+
+    fn sync_asset() -> StaticAsset {
+        StaticAsset::new(Body::Sync(Bytes::from_static(ASSET)))
+    }
+
+    fn dated_asset() -> StaticAsset {
+        sync_asset()
+            .with_etag("v1")
+            .with_last_modified(UNIX_EPOCH + Duration::from_secs(784_111_777))
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+        response
+            .0
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    async fn body_bytes(body: Body) -> Vec<u8> {
+        match body {
+            Body::Sync(bytes) => bytes.to_vec(),
+            Body::Async(mut stream) => {
+                let mut collected = Vec::new();
+                while let Some(chunk) = stream.next().await {
+                    collected.extend_from_slice(
+                        &chunk.expect("stream chunk should not fail"),
+                    );
+                }
+                collected
+            }
+        }
+    }
+
+    /// A seekable source that streams its ranges in three-byte chunks and
+    /// counts how often it is read.
+    struct CountingSource {
+        bytes: Bytes,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl SeekableSource for CountingSource {
+        fn len(&self) -> u64 {
+            self.bytes.len() as u64
+        }
+
+        fn read_range(&self, range: Range<u64>) -> Body {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            let start = usize::try_from(range.start).expect("range in bounds");
+            let end = usize::try_from(range.end).expect("range in bounds");
+            let chunks = self
+                .bytes
+                .slice(start..end)
+                .chunks(3)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>();
+            Body::Async(Box::pin(stream::iter(chunks)))
+        }
+    }
+
+    fn seekable_asset() -> (StaticAsset, Arc<AtomicUsize>) {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let asset = StaticAsset::seekable(CountingSource {
+            bytes: Bytes::from_static(ASSET),
+            reads: Arc::clone(&reads),
+        })
+        .with_etag("v1");
+        (asset, reads)
+    }
+
+    fn quoted_content_tag() -> String {
+        format!("\"{}\"", content_tag(ASSET))
+    }
+
+    #[test]
+    fn synchronous_assets_carry_a_computed_strong_etag() {
+        let response = asset_response(
+            &request(Method::GET, &[]),
+            "app.js",
+            sync_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(
+            header(&response, "etag"),
+            Some(quoted_content_tag().as_str())
+        );
+        assert_eq!(header(&response, "last-modified"), None);
+    }
+
+    #[test]
+    fn explicit_validators_replace_the_computed_tag() {
+        let response = asset_response(
+            &request(Method::GET, &[]),
+            "app.js",
+            dated_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(header(&response, "etag"), Some("\"v1\""));
+        assert_eq!(
+            header(&response, "last-modified"),
+            Some("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+    }
+
+    #[test]
+    fn an_explicit_content_type_replaces_the_guess() {
+        let serve = |asset: StaticAsset| {
+            asset_response(
+                &request(Method::GET, &[]),
+                "app.js",
+                asset,
+                None,
+                &StaticRouteOptions::default(),
+            )
+        };
+
+        let response = serve(sync_asset().with_content_type("text/plain"));
+        assert_eq!(header(&response, "content-type"), Some("text/plain"));
+        let response = serve(sync_asset().with_content_type("bad\nvalue"));
+        assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+    }
+
+    #[test]
+    fn declared_lengths_describe_async_bodies_without_polling_them() {
+        let polled = Arc::new(AtomicUsize::new(0));
+        let asset = || {
+            let polled = Arc::clone(&polled);
+            StaticAsset::new(Body::Async(Box::pin(stream::once(async move {
+                polled.fetch_add(1, Ordering::Relaxed);
+                Ok(Bytes::from_static(ASSET))
+            }))))
+            .with_etag("v1")
+            .with_length(ASSET.len() as u64)
+        };
+        let serve = |method, headers: &[(&str, &str)]| {
+            asset_response(
+                &request(method, headers),
+                "app.js",
+                asset(),
+                None,
+                &StaticRouteOptions::default(),
+            )
+        };
+
+        let length = ASSET.len().to_string();
+        let response = serve(Method::GET, &[]);
+        assert_eq!(header(&response, "content-length"), Some(length.as_str()));
+        let response = serve(Method::HEAD, &[]);
+        assert_eq!(header(&response, "content-length"), Some(length.as_str()));
+        assert!(
+            matches!(response.0.body(), Body::Sync(bytes) if bytes.is_empty())
+        );
+        let response = serve(Method::GET, &[("if-none-match", "\"v1\"")]);
+        assert_eq!(response.0.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(polled.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn extra_headers_override_the_route_policy() {
+        let asset = || {
+            dated_asset()
+                .with_header(
+                    CACHE_CONTROL,
+                    HeaderValue::from_static("private, max-age=60"),
+                )
+                .with_header(VARY, HeaderValue::from_static("origin"))
+                .with_header(
+                    http::header::CONTENT_DISPOSITION,
+                    HeaderValue::from_static("attachment"),
+                )
+        };
+        let options = StaticRouteOptions::default()
+            .with_precompressed([Precompressed::Gzip]);
+        let serve = |headers: &[(&str, &str)]| {
+            asset_response(
+                &request(Method::GET, headers),
+                "app.js",
+                asset(),
+                None,
+                &options,
+            )
+        };
+
+        for response in [
+            serve(&[]),
+            serve(&[("range", "bytes=0-0")]),
+            serve(&[("if-none-match", "\"v1\"")]),
+        ] {
+            assert_eq!(
+                header(&response, "cache-control"),
+                Some("private, max-age=60")
+            );
+            assert_eq!(
+                header(&response, "content-disposition"),
+                Some("attachment")
+            );
+            let vary = response
+                .0
+                .headers()
+                .get_all(VARY)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect::<Vec<_>>();
+            assert_eq!(vary, ["accept-encoding", "origin"]);
+        }
+
+        let response = serve(&[("range", "bytes=500-")]);
+        assert_eq!(response.0.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(header(&response, "content-disposition"), None);
+    }
+
+    #[test]
+    fn asynchronous_assets_without_a_tag_are_served_in_full() {
+        let asset = StaticAsset::new(Body::Async(Box::pin(
+            futures::stream::once(async { Ok(Bytes::from_static(ASSET)) }),
+        )));
+        let response = asset_response(
+            &request(Method::GET, &[("if-none-match", "\"anything\"")]),
+            "app.js",
+            asset,
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(header(&response, "etag"), None);
+    }
+
+    #[test]
+    fn a_matching_if_none_match_is_not_modified() {
+        let tag = quoted_content_tag();
+        for value in [
+            tag.clone(),
+            format!("W/{tag}"),
+            format!("\"other\", {tag}"),
+            "*".to_owned(),
+        ] {
+            let response = asset_response(
+                &request(Method::GET, &[("if-none-match", &value)]),
+                "app.js",
+                sync_asset(),
+                None,
+                &StaticRouteOptions::default(),
+            );
+            assert_eq!(
+                response.0.status(),
+                StatusCode::NOT_MODIFIED,
+                "{value}"
+            );
+            assert_eq!(header(&response, "etag"), Some(tag.as_str()));
+            assert_eq!(header(&response, "content-type"), None);
+            assert_eq!(header(&response, "content-length"), None);
+            assert!(
+                matches!(response.0.body(), Body::Sync(bytes) if bytes.is_empty())
+            );
+        }
+    }
+
+    #[test]
+    fn a_stale_if_none_match_is_served_in_full() {
+        let response = asset_response(
+            &request(
+                Method::GET,
+                &[("if-none-match", "\"stale\", W/\"older\"")],
+            ),
+            "app.js",
+            sync_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(header(&response, "content-length"), Some("14"));
+    }
+
+    #[test]
+    fn opaque_tags_may_contain_commas() {
+        let response = asset_response(
+            &request(Method::GET, &[("if-none-match", "\"a,b\", \"v,1\"")]),
+            "app.js",
+            sync_asset().with_etag("v,1"),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn if_modified_since_compares_at_whole_second_resolution() {
+        let cases = [
+            ("Sun, 06 Nov 1994 08:49:37 GMT", StatusCode::NOT_MODIFIED),
+            ("Sun, 06 Nov 1994 08:49:38 GMT", StatusCode::NOT_MODIFIED),
+            ("Sun, 06 Nov 1994 08:49:36 GMT", StatusCode::OK),
+            ("not a date", StatusCode::OK),
+        ];
+        for (since, expected) in cases {
+            let asset = sync_asset().with_last_modified(
+                UNIX_EPOCH + Duration::from_millis(784_111_777_500),
+            );
+            let response = asset_response(
+                &request(Method::GET, &[("if-modified-since", since)]),
+                "app.js",
+                asset,
+                None,
+                &StaticRouteOptions::default(),
+            );
+            assert_eq!(response.0.status(), expected, "{since}");
+        }
+    }
+
+    #[test]
+    fn if_none_match_takes_precedence_over_if_modified_since() {
+        let response = asset_response(
+            &request(
+                Method::GET,
+                &[
+                    ("if-none-match", "\"stale\""),
+                    ("if-modified-since", "Sun, 06 Nov 1994 08:49:37 GMT"),
+                ],
+            ),
+            "app.js",
+            dated_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_modified_since_needs_a_known_modification_time() {
+        let response = asset_response(
+            &request(
+                Method::GET,
+                &[("if-modified-since", "Sun, 06 Nov 2094 08:49:37 GMT")],
+            ),
+            "app.js",
+            sync_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn not_modified_responses_reach_the_wire_without_a_length() {
+        for method in [Method::GET, Method::HEAD] {
+            let core = HandlerCore::new(
+                request(method.clone(), &[(IF_NONE_MATCH.as_str(), "\"v1\"")]),
+                HandlerConfig::default(),
+            )
+            .static_assets_handler("/static", |_| Some(dated_asset()))
+            .expect("static registration should succeed");
+
+            let response = core.render(|| view! { "unused" }, || {}).await;
+
+            assert_eq!(
+                response.0.status(),
+                StatusCode::NOT_MODIFIED,
+                "{method}"
+            );
+            assert_eq!(header(&response, "content-length"), None, "{method}");
+            assert_eq!(header(&response, "etag"), Some("\"v1\""), "{method}");
+            assert_eq!(
+                header(&response, "x-content-type-options"),
+                Some("nosniff")
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn plain_body_callbacks_answer_conditional_requests_too() {
+        let core = HandlerCore::new(
+            request(
+                Method::GET,
+                &[(IF_NONE_MATCH.as_str(), quoted_content_tag().as_str())],
+            ),
+            HandlerConfig::default(),
+        )
+        .static_files_handler("/static", |_| {
+            Some(Body::Sync(Bytes::from_static(ASSET)))
+        })
+        .expect("static registration should succeed");
+
+        let response = core.render(|| view! { "unused" }, || {}).await;
+
+        assert_eq!(response.0.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn conditional_headers_are_ignored_for_other_methods() {
+        let headers = {
+            let mut headers = HeaderMap::new();
+            headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+            headers.insert(
+                IF_MODIFIED_SINCE,
+                HeaderValue::from_static("Sun, 06 Nov 2094 08:49:37 GMT"),
+            );
+            headers
+        };
+
+        assert!(!is_not_modified(
+            &Method::POST,
+            &headers,
+            Some("v1"),
+            Some(UNIX_EPOCH)
+        ));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn a_single_range_is_partial_content() {
+        let response = asset_response(
+            &request(Method::GET, &[("range", "bytes=8-")]),
+            "app.js",
+            sync_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(header(&response, "content-range"), Some("bytes 8-13/14"));
+        assert_eq!(header(&response, "content-length"), Some("6"));
+        assert_eq!(header(&response, "accept-ranges"), Some("bytes"));
+        assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+        assert_eq!(body_bytes(response.0.into_body()).await, b"log(1)");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn several_ranges_are_framed_as_multipart_byteranges() {
+        let response = asset_response(
+            &request(Method::GET, &[("range", "bytes=0-6, -3")]),
+            "app.js",
+            sync_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::PARTIAL_CONTENT);
+        let content_type = header(&response, "content-type")
+            .expect("multipart response should have a content type")
+            .to_owned();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .expect("content type should name the boundary")
+            .to_owned();
+        let length = header(&response, "content-length")
+            .and_then(|length| length.parse::<usize>().ok())
+            .expect("multipart response should have a length");
+        let body = body_bytes(response.0.into_body()).await;
+
+        assert_eq!(body.len(), length);
+        assert_eq!(
+            String::from_utf8(body).expect("body should be UTF-8"),
+            format!(
+                "\r\n--{boundary}\r\nContent-Type: text/javascript\r\n\
+                 Content-Range: bytes 0-6/14\r\n\r\nconsole\
+                 \r\n--{boundary}\r\nContent-Type: text/javascript\r\n\
+                 Content-Range: bytes 11-13/14\r\n\r\n(1)\
+                 \r\n--{boundary}--\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn an_unsatisfiable_range_is_416_with_the_length() {
+        let response = asset_response(
+            &request(Method::GET, &[("range", "bytes=14-")]),
+            "app.js",
+            sync_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(header(&response, "content-range"), Some("bytes */14"));
+        assert!(
+            matches!(response.0.body(), Body::Sync(bytes) if bytes.is_empty())
+        );
+    }
+
+    #[test]
+    fn ranges_are_ignored_for_head_and_stale_if_range() {
+        let cases = [
+            request(Method::HEAD, &[("range", "bytes=0-0")]),
+            request(
+                Method::GET,
+                &[("range", "bytes=0-0"), ("if-range", "\"stale\"")],
+            ),
+        ];
+        for request in cases {
+            let response = asset_response(
+                &request,
+                "app.js",
+                dated_asset(),
+                None,
+                &StaticRouteOptions::default(),
+            );
+            assert_eq!(response.0.status(), StatusCode::OK);
+            assert_eq!(header(&response, "content-length"), Some("14"));
+            assert_eq!(header(&response, "accept-ranges"), Some("bytes"));
+        }
+    }
+
+    #[test]
+    fn not_modified_takes_precedence_over_a_range() {
+        let response = asset_response(
+            &request(
+                Method::GET,
+                &[("range", "bytes=0-0"), ("if-none-match", "\"v1\"")],
+            ),
+            "app.js",
+            dated_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn plain_streams_do_not_advertise_ranges() {
+        let asset =
+            StaticAsset::new(Body::Async(Box::pin(stream::once(async {
+                Ok(Bytes::from_static(ASSET))
+            }))));
+        let response = asset_response(
+            &request(Method::GET, &[("range", "bytes=0-0")]),
+            "app.js",
+            asset,
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(header(&response, "accept-ranges"), None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn seekable_sources_read_only_the_requested_ranges() {
+        let (asset, reads) = seekable_asset();
+        let response = asset_response(
+            &request(Method::GET, &[("range", "bytes=1-3,8-10")]),
+            "app.js",
+            asset,
+            None,
+            &StaticRouteOptions::default(),
+        );
+
+        assert_eq!(response.0.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(reads.load(Ordering::Relaxed), 2);
+        let length = header(&response, "content-length")
+            .and_then(|length| length.parse::<usize>().ok())
+            .expect("multipart response should have a length");
+        let body = body_bytes(response.0.into_body()).await;
+        assert_eq!(body.len(), length);
+        let body = String::from_utf8(body).expect("body should be UTF-8");
+        assert!(body.contains("bytes 1-3/14\r\n\r\nons\r\n"), "{body}");
+        assert!(body.contains("bytes 8-10/14\r\n\r\nlog\r\n"), "{body}");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn seekable_sources_serve_whole_and_head_requests() {
+        let (asset, reads) = seekable_asset();
+        let response = asset_response(
+            &request(Method::GET, &[]),
+            "app.js",
+            asset,
+            None,
+            &StaticRouteOptions::default(),
+        );
+        assert_eq!(response.0.status(), StatusCode::OK);
+        assert_eq!(header(&response, "content-length"), Some("14"));
+        assert_eq!(header(&response, "etag"), Some("\"v1\""));
+        assert_eq!(body_bytes(response.0.into_body()).await, ASSET);
+        assert_eq!(reads.load(Ordering::Relaxed), 1);
+
+        let (asset, reads) = seekable_asset();
+        let response = asset_response(
+            &request(Method::HEAD, &[]),
+            "app.js",
+            asset,
+            None,
+            &StaticRouteOptions::default(),
+        );
+        assert_eq!(header(&response, "content-length"), Some("14"));
+        assert_eq!(reads.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn partial_responses_keep_their_length_through_render() {
+        let core = HandlerCore::new(
+            request(Method::GET, &[("range", "bytes=0-6")]),
+            HandlerConfig::default(),
+        )
+        .static_assets_handler("/static", |_| Some(seekable_asset().0))
+        .expect("static registration should succeed");
+
+        let response = core.render(|| view! { "unused" }, || {}).await;
+
+        assert_eq!(response.0.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(header(&response, "content-length"), Some("7"));
+        assert_eq!(body_bytes(response.0.into_body()).await, b"console");
+    }
+
+    #[test]
+    fn cache_control_follows_the_route_options() {
+        let hashed = "app.4f2a9c1b7d.js";
+        let cases = [
+            (request(Method::GET, &[]), Some("no-cache")),
+            (
+                request(Method::GET, &[("if-none-match", "\"v1\"")]),
+                Some("no-cache"),
+            ),
+            (
+                request(Method::GET, &[("range", "bytes=0-0")]),
+                Some("no-cache"),
+            ),
+            (request(Method::GET, &[("range", "bytes=99-")]), None),
+        ];
+        for (request, expected) in cases {
+            let response = asset_response(
+                &request,
+                "app.js",
+                dated_asset(),
+                None,
+                &StaticRouteOptions::default(),
+            );
+            assert_eq!(
+                header(&response, "cache-control"),
+                expected,
+                "{}",
+                response.0.status()
+            );
+        }
+
+        let response = asset_response(
+            &request(Method::GET, &[]),
+            hashed,
+            dated_asset(),
+            None,
+            &StaticRouteOptions::default(),
+        );
+        assert_eq!(
+            header(&response, "cache-control"),
+            Some("public, max-age=31536000, immutable")
+        );
+        let response = asset_response(
+            &request(Method::GET, &[]),
+            hashed,
+            dated_asset(),
+            None,
+            &StaticRouteOptions::default().without_cache_control(),
+        );
+        assert_eq!(header(&response, "cache-control"), None);
+    }
+
+    fn compressed_options() -> StaticRouteOptions {
+        StaticRouteOptions::default()
+            .with_precompressed([Precompressed::Brotli, Precompressed::Gzip])
+    }
+
+    /// Serves `app.js` and the siblings named in `available`, recording
+    /// every path the callback is asked for.
+    fn negotiate(
+        accept: Option<&str>,
+        available: &[&str],
+        options: &StaticRouteOptions,
+    ) -> (Option<Response>, Vec<String>) {
+        let headers = accept
+            .map(|value| vec![("accept-encoding", value)])
+            .unwrap_or_default();
+        let asked = std::cell::RefCell::new(Vec::new());
+        let response = static_response(
+            &request(Method::GET, &headers),
+            "app.js",
+            options,
+            |path| {
+                asked.borrow_mut().push(path.clone());
+                (path == "app.js" || available.contains(&path.as_str())).then(
+                    || {
+                        StaticAsset::new(Body::Sync(Bytes::from(path)))
+                            .with_etag("v1")
+                    },
+                )
+            },
+        );
+        (response, asked.into_inner())
+    }
+
+    #[test]
+    fn accepted_siblings_are_served_with_their_encoding() {
+        let (response, asked) =
+            negotiate(Some("br, gzip"), &["app.js.gz"], &compressed_options());
+        let response = response.expect("an asset should be found");
+
+        assert_eq!(asked, ["app.js.br", "app.js.gz"]);
+        assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+        assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+        assert_eq!(header(&response, "vary"), Some("accept-encoding"));
+        assert_eq!(header(&response, "etag"), Some("\"v1-gz\""));
+        assert!(
+            matches!(response.0.body(), Body::Sync(bytes) if bytes == "app.js.gz")
+        );
+    }
+
+    #[test]
+    fn client_quality_outranks_configured_order() {
+        let (response, asked) = negotiate(
+            Some("gzip, br;q=0.5, zstd"),
+            &["app.js.br", "app.js.gz"],
+            &compressed_options(),
+        );
+
+        assert_eq!(asked, ["app.js.gz"]);
+        assert_eq!(
+            response
+                .as_ref()
+                .and_then(|r| header(r, "content-encoding")),
+            Some("gzip")
+        );
+    }
+
+    #[test]
+    fn unencoded_responses_still_vary_on_accept_encoding() {
+        for accept in [None, Some("identity"), Some("br;q=0, gzip;q=0")] {
+            let (response, asked) =
+                negotiate(accept, &["app.js.br"], &compressed_options());
+            let response = response.expect("an asset should be found");
+
+            assert_eq!(asked, ["app.js"], "{accept:?}");
+            assert_eq!(header(&response, "content-encoding"), None);
+            assert_eq!(header(&response, "vary"), Some("accept-encoding"));
+            assert_eq!(header(&response, "etag"), Some("\"v1\""));
+        }
+    }
+
+    #[test]
+    fn siblings_are_not_looked_up_unless_configured() {
+        let (response, asked) = negotiate(
+            Some("br, gzip"),
+            &["app.js.br"],
+            &StaticRouteOptions::default(),
+        );
+        let response = response.expect("an asset should be found");
+
+        assert_eq!(asked, ["app.js"]);
+        assert_eq!(header(&response, "vary"), None);
+    }
+
+    #[test]
+    fn encoded_assets_answer_one_range_but_not_several() {
+        let asset = || StaticAsset::new(Body::Sync(Bytes::from_static(ASSET)));
+        let single = asset_response(
+            &request(Method::GET, &[("range", "bytes=0-1")]),
+            "app.js",
+            asset(),
+            Some(Precompressed::Brotli),
+            &compressed_options(),
+        );
+        assert_eq!(single.0.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(header(&single, "content-encoding"), Some("br"));
+
+        let several = asset_response(
+            &request(Method::GET, &[("range", "bytes=0-1,4-5")]),
+            "app.js",
+            asset(),
+            Some(Precompressed::Brotli),
+            &compressed_options(),
+        );
+        assert_eq!(several.0.status(), StatusCode::OK);
+        assert_eq!(header(&several, "content-encoding"), Some("br"));
+    }
+
+    fn docs_site(path: &str) -> Option<StaticAsset> {
+        let body: &'static [u8] = match path {
+            "index.html" => b"root index",
+            "guide/index.html" => b"guide index",
+            "app.js" => b"app",
+            _ => return None,
+        };
+        Some(StaticAsset::new(Body::Sync(Bytes::from_static(body))))
+    }
+
+    async fn serve_docs(
+        uri: &str,
+        accept: &str,
+        options: &StaticRouteOptions,
+    ) -> Response {
+        let request = Request::builder()
+            .uri(uri)
+            .header(http::header::ACCEPT, accept)
+            .body(Bytes::new())
+            .expect("test request should be valid");
+        HandlerCore::new(request, HandlerConfig::default())
+            .static_assets_handler_with_options("/docs", options, |path| {
+                docs_site(&path)
+            })
+            .expect("static registration should succeed")
+            .render(|| view! { "unused" }, || {})
+            .await
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn directory_paths_serve_the_index_when_configured() {
+        let options =
+            StaticRouteOptions::default().with_directory_index("index.html");
+
+        for (uri, expected) in [
+            ("/docs/", &b"root index"[..]),
+            ("/docs/guide/", b"guide index"),
+            ("/docs/app.js", b"app"),
+        ] {
+            let response = serve_docs(uri, "*/*", &options).await;
+            assert_eq!(response.0.status(), StatusCode::OK, "{uri}");
+            assert_eq!(
+                header(&response, "content-type"),
+                Some(if expected == b"app" {
+                    "text/javascript"
+                } else {
+                    "text/html"
+                }),
+                "{uri}"
+            );
+            assert_eq!(body_bytes(response.0.into_body()).await, expected);
+        }
+
+        let unconfigured =
+            serve_docs("/docs/guide/", "*/*", &StaticRouteOptions::default())
+                .await;
+        assert_eq!(unconfigured.0.status(), StatusCode::NOT_FOUND);
+        // Without the redirect, `guide` is not a directory request.
+        let bare = serve_docs("/docs/guide", "*/*", &options).await;
+        assert_eq!(bare.0.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn directories_without_a_slash_redirect_when_configured() {
+        let options = StaticRouteOptions::default()
+            .with_directory_index("index.html")
+            .with_directory_redirect();
+
+        for (uri, location) in [
+            ("/docs", "/docs/"),
+            ("/docs?page=2", "/docs/?page=2"),
+            ("/docs/guide", "/docs/guide/"),
+        ] {
+            let response = serve_docs(uri, "*/*", &options).await;
+            assert_eq!(
+                response.0.status(),
+                StatusCode::PERMANENT_REDIRECT,
+                "{uri}"
+            );
+            assert_eq!(header(&response, "location"), Some(location), "{uri}");
+        }
+
+        let missing = serve_docs("/docs/missing", "*/*", &options).await;
+        assert_eq!(missing.0.status(), StatusCode::NOT_FOUND);
+        let file = serve_docs("/docs/app.js", "*/*", &options).await;
+        assert_eq!(file.0.status(), StatusCode::OK);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn unknown_paths_fall_back_to_the_document_for_html_requests() {
+        let options = StaticRouteOptions::default().with_fallback("index.html");
+
+        let page =
+            serve_docs("/docs/reference/install", "text/html", &options).await;
+        assert_eq!(page.0.status(), StatusCode::OK);
+        assert_eq!(header(&page, "cache-control"), Some("no-cache"));
+        assert_eq!(body_bytes(page.0.into_body()).await, b"root index");
+
+        let script = serve_docs("/docs/missing.js", "*/*", &options).await;
+        assert_eq!(script.0.status(), StatusCode::NOT_FOUND);
+
+        let traversal =
+            serve_docs("/docs/../secret", "text/html", &options).await;
+        assert_eq!(traversal.0.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -22,6 +22,8 @@ use leptos_router::SsrMode;
 #[cfg(feature = "tracing")]
 use super::core::HandlerCore;
 #[cfg(feature = "tracing")]
+use super::http_util::request_id;
+#[cfg(feature = "tracing")]
 use super::policy::RequestPolicyError;
 
 #[cfg(feature = "tracing")]
@@ -64,18 +66,9 @@ impl RequestTrace {
         } else {
             "none"
         };
-        let request_id = core
-            .req
-            .headers()
-            .get("x-request-id")
-            .and_then(|value| value.to_str().ok())
-            .filter(|value| {
-                value.len() <= 128
-                    && value
-                        .bytes()
-                        .all(|byte| byte.is_ascii_graphic() || byte == b' ')
-            })
-            .unwrap_or_default();
+        let request_id =
+            request_id(core.req.headers(), &core.config.request_id_header())
+                .unwrap_or_default();
         let span = tracing::info_span!(
             "leptos_wasi.request",
             runtime = "wasi",
@@ -211,13 +204,35 @@ pub(super) fn trace_policy_rejection(
         RequestPolicyError::ConflictingContentLength => {
             "conflicting_content_length"
         }
+        RequestPolicyError::UnsupportedContentEncoding => {
+            "unsupported_content_encoding"
+        }
+        RequestPolicyError::InvalidEncodedBody => "invalid_encoded_body",
+        RequestPolicyError::DecodedBodyTooLarge { .. } => {
+            "decoded_body_too_large"
+        }
+        RequestPolicyError::DecompressionRatioExceeded { .. } => {
+            "decompression_ratio_exceeded"
+        }
+        RequestPolicyError::TooManyMultipartParts { .. } => {
+            "too_many_multipart_parts"
+        }
+        RequestPolicyError::MultipartPartTooLarge { .. } => {
+            "multipart_part_too_large"
+        }
+        RequestPolicyError::TooManyHeaders { .. } => "too_many_headers",
+        RequestPolicyError::HeadersTooLarge { .. } => "headers_too_large",
+        RequestPolicyError::HeaderValueTooLarge { .. } => {
+            "header_value_too_large"
+        }
+        RequestPolicyError::UriTooLong { .. } => "uri_too_long",
     };
     tracing::warn!(
         runtime = "wasi",
         preview,
         status = error.status().as_u16(),
         error_class,
-        "request policy rejected incoming body"
+        "request policy rejected incoming request"
     );
 }
 
@@ -12,24 +12,29 @@ use wasi::{
 use std::time::Instant;
 
 use bytes::Bytes;
-use http::{Request, StatusCode, Uri, header::CONTENT_TYPE};
+use http::{HeaderMap, Request, StatusCode, Uri, header::CONTENT_TYPE};
 use leptos::IntoView;
 use server_fn::ServerFn;
 use thiserror::Error;
 
+use super::body_stream::{Deadline, StreamingBody, is_streamed};
 use super::builder::common_handler_methods;
 use super::core::HandlerCore;
+#[cfg(feature = "compression")]
+use super::decompression::decode_configured;
 use super::policy::{
     HandlerConfig, RegistrationError, RequestPolicyError,
-    X_CONTENT_TYPE_OPTIONS, policy_response,
+    X_CONTENT_TYPE_OPTIONS, policy_response, validate_content_length,
 };
 use super::server_fns::{ReqBody, ResBody};
 #[cfg(feature = "tracing")]
 use super::trace::trace_policy_rejection;
 use super::trace::{TraceHandle, trace_finish, trace_first_byte};
 use crate::{
-    __private::ServerWithBody,
+    __private::{FromChunkStream, ServerWithBody},
+    executor::sleep,
     response::{Body, Response},
+    static_files::{StaticAsset, StaticRouteOptions},
 };
 
 struct ResponseOutGuard(Option<ResponseOutparam>);
@@ -114,16 +119,73 @@ impl Handler {
         let response_out = ResponseOutGuard::new(response_out);
         #[cfg(feature = "tracing")]
         let request_started = Instant::now();
-        let rejected_request = Request::from_parts(
-            crate::request::p2::request_parts(&request)?,
-            Bytes::new(),
-        );
+        let (parts, breach) = crate::request::p2::request_parts(
+            &request,
+            config.header_limits(),
+        )?;
+        let rejected_request = Request::from_parts(parts, Bytes::new());
+        let config = config.for_path(rejected_request.uri().path());
+        if let Some(error) = breach {
+            #[cfg(feature = "tracing")]
+            trace_policy_rejection("p2", &error);
+            let core = HandlerCore::new(rejected_request, config)
+                .with_preset(policy_response(&error), "request_policy");
+            #[cfg(feature = "tracing")]
+            let core = core.with_request_started(request_started);
+            return Ok(Self { core, response_out });
+        }
+        if is_streamed(&config, rejected_request.headers()) {
+            let core = match validate_content_length(
+                rejected_request.headers(),
+                config.max_request_body_size(),
+            ) {
+                Ok(()) => {
+                    let body = streaming_body(
+                        request,
+                        &config,
+                        rejected_request.headers(),
+                    )?;
+                    HandlerCore::new(rejected_request, config)
+                        .with_streaming_body(body)
+                }
+                Err(error) => {
+                    #[cfg(feature = "tracing")]
+                    trace_policy_rejection("p2", &error);
+                    let response = policy_response(&error);
+                    HandlerCore::new(rejected_request, config)
+                        .with_preset(response, "request_policy")
+                }
+            };
+            #[cfg(feature = "tracing")]
+            let core = core.with_request_started(request_started);
+            return Ok(Self { core, response_out });
+        }
         match crate::request::p2::from_wasi_request_with_deadline(
             request,
             config.max_request_body_size(),
             config.request_body_timeout_ns(),
         ) {
             Ok(request) => {
+                #[cfg(feature = "compression")]
+                let request = {
+                    let (mut parts, body) = request.into_parts();
+                    decode_configured(&mut parts, body, &config)
+                        .map(|body| Request::from_parts(parts, body))
+                };
+                #[cfg(feature = "compression")]
+                let request = match request {
+                    Ok(request) => request,
+                    Err(error) => {
+                        #[cfg(feature = "tracing")]
+                        trace_policy_rejection("p2", &error);
+                        let response = policy_response(&error);
+                        let core = HandlerCore::new(rejected_request, config)
+                            .with_preset(response, "request_policy");
+                        #[cfg(feature = "tracing")]
+                        let core = core.with_request_started(request_started);
+                        return Ok(Self { core, response_out });
+                    }
+                };
                 let core = HandlerCore::new(request, config);
                 #[cfg(feature = "tracing")]
                 let core = core.with_request_started(request_started);
@@ -324,6 +386,33 @@ async fn send_response(
     }
 }
 
+/// Opens `request`'s body for a streaming server function, under the size
+/// limit and read budget of `config`.
+fn streaming_body(
+    request: IncomingRequest,
+    config: &HandlerConfig,
+    headers: &HeaderMap,
+) -> Result<StreamingBody, HandlerError> {
+    let chunks = crate::request::p2::body_chunks(request)?;
+    let deadline = config.request_body_timeout_ns().map(|nanoseconds| {
+        let expiry: Deadline = Box::pin(async move {
+            // An executor that cannot register the timer never expires it,
+            // rather than failing every streamed request at once.
+            if sleep(nanoseconds).await.is_err() {
+                futures::future::pending::<()>().await;
+            }
+        });
+        (nanoseconds, expiry)
+    });
+    Ok(StreamingBody::new(
+        Box::pin(chunks),
+        config.max_request_body_size(),
+        deadline,
+        "p2",
+    )
+    .with_multipart_limits(config, headers))
+}
+
 fn send_internal_error(response_out: ResponseOutparam) {
     let headers = wasi::http::types::Headers::new();
     // This response is built outside `HandlerCore::render`, so the
@@ -1,6 +1,9 @@
 //! WASI Preview 3 request handler.
 
-use http_body_util::{BodyExt, Limited};
+use std::io;
+
+use futures::StreamExt;
+use http_body_util::{BodyDataStream, BodyExt, Limited};
 
 #[cfg(feature = "tracing")]
 use std::pin::Pin;
@@ -10,13 +13,16 @@ use std::time::Instant;
 use bytes::Bytes;
 #[cfg(feature = "tracing")]
 use http::StatusCode;
-use http::{Request, Uri};
+use http::{HeaderMap, Request, Uri};
 use leptos::IntoView;
 use server_fn::ServerFn;
 use thiserror::Error;
 
+use super::body_stream::{Deadline, StreamingBody, is_streamed};
 use super::builder::common_handler_methods;
 use super::core::HandlerCore;
+#[cfg(feature = "compression")]
+use super::decompression::decode_configured;
 use super::policy::{
     HandlerConfig, RegistrationError, RequestPolicyError, policy_response,
     validate_content_length,
@@ -26,7 +32,11 @@ use super::server_fns::{ReqBody, ResBody};
 use super::trace::{
     TraceHandle, trace_finish, trace_first_byte, trace_policy_rejection,
 };
-use crate::{__private::ServerWithBody, response::Body};
+use crate::{
+    __private::{FromChunkStream, ServerWithBody},
+    response::Body,
+    static_files::{StaticAsset, StaticRouteOptions},
+};
 
 /// Errors returned by the WASI Preview 3 handler.
 #[derive(Debug, Error)]
@@ -71,10 +81,17 @@ impl Handler {
         #[cfg(feature = "tracing")]
         let request_started = Instant::now();
         let (parts, body) = request.into_parts();
-        if let Err(error) = validate_content_length(
-            &parts.headers,
-            config.max_request_body_size(),
-        ) {
+        let config = config.for_path(parts.uri.path());
+        if let Err(error) = config
+            .header_limits()
+            .map_or(Ok(()), |limits| limits.check_parts(&parts))
+            .and_then(|()| {
+                validate_content_length(
+                    &parts.headers,
+                    config.max_request_body_size(),
+                )
+            })
+        {
             #[cfg(feature = "tracing")]
             trace_policy_rejection("p3", &error);
             let core = HandlerCore::new(
@@ -87,6 +104,18 @@ impl Handler {
             return Ok(Self { core });
         }
 
+        if is_streamed(&config, &parts.headers) {
+            let body = streaming_body(body, &config, &parts.headers);
+            let core = HandlerCore::new(
+                Request::from_parts(parts, Bytes::new()),
+                config,
+            )
+            .with_streaming_body(body);
+            #[cfg(feature = "tracing")]
+            let core = core.with_request_started(request_started);
+            return Ok(Self { core });
+        }
+
         let body = Limited::new(body, config.max_request_body_size());
         // Same total-budget meaning as Preview 2: one timer for the whole
         // body, raced against the collect. `Limited::collect` is a single
@@ -123,10 +152,27 @@ impl Handler {
         };
         match collected {
             Ok(body) => {
-                let core = HandlerCore::new(
-                    Request::from_parts(parts, body.to_bytes()),
-                    config,
-                );
+                #[cfg(feature = "compression")]
+                let mut parts = parts;
+                let body = body.to_bytes();
+                #[cfg(feature = "compression")]
+                let body = match decode_configured(&mut parts, body, &config) {
+                    Ok(body) => body,
+                    Err(error) => {
+                        #[cfg(feature = "tracing")]
+                        trace_policy_rejection("p3", &error);
+                        let core = HandlerCore::new(
+                            Request::from_parts(parts, Bytes::new()),
+                            config,
+                        )
+                        .with_preset(policy_response(&error), "request_policy");
+                        #[cfg(feature = "tracing")]
+                        let core = core.with_request_started(request_started);
+                        return Ok(Self { core });
+                    }
+                };
+                let core =
+                    HandlerCore::new(Request::from_parts(parts, body), config);
                 #[cfg(feature = "tracing")]
                 let core = core.with_request_started(request_started);
                 Ok(Self { core })
@@ -333,3 +379,27 @@ impl<B> Drop for TraceBody<B> {
         );
     }
 }
+
+/// Wraps `body` for a streaming server function, under the size limit and
+/// read budget of `config`.
+fn streaming_body(
+    body: ::wasip3::http_compat::IncomingRequestBody,
+    config: &HandlerConfig,
+    headers: &HeaderMap,
+) -> StreamingBody {
+    let chunks = BodyDataStream::new(body).map(|chunk| {
+        chunk.map_err(|error| io::Error::other(format!("{error:?}")))
+    });
+    let deadline = config.request_body_timeout_ns().map(|nanoseconds| {
+        let expiry: Deadline =
+            Box::pin(::wasip3::clocks::monotonic_clock::wait_for(nanoseconds));
+        (nanoseconds, expiry)
+    });
+    StreamingBody::new(
+        Box::pin(chunks),
+        config.max_request_body_size(),
+        deadline,
+        "p3",
+    )
+    .with_multipart_limits(config, headers)
+}
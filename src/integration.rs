@@ -6,7 +6,7 @@
 
 use std::{future::Future, pin::Pin, sync::Arc};
 
-use futures::{Stream, StreamExt, stream::once};
+use futures::{FutureExt, Stream, StreamExt, stream::once};
 use hydration_context::{SharedContext, SsrSharedContext};
 use leptos::{
     IntoView, PrefetchLazyFn, WasmSplitManifest,
@@ -25,6 +25,119 @@ type StreamBuilder<IV> = fn(
     bool,
 ) -> PinnedFuture<PinnedStream<String>>;
 
+/// How an SSR response's HTML chunks are grouped into body writes.
+///
+/// Leptos renders a page as a stream of HTML fragments, and every item this
+/// crate hands the host becomes at least one body write. Coalescing more
+/// fragments per write saves host calls; flushing sooner gets bytes to the
+/// browser sooner. The default joins up to 32 fragments that are ready at
+/// once and never waits for more, which is what this crate has always done.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::{HandlerConfig, SsrChunking};
+///
+/// // Fewer, larger writes, but never hold back the document shell.
+/// let config = HandlerConfig::default().with_ssr_chunking(
+///     SsrChunking::default()
+///         .with_max_chunks(128)
+///         .with_min_bytes(8 * 1024)
+///         .flush_shell_immediately(),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SsrChunking {
+    max_chunks: usize,
+    min_bytes: usize,
+    flush_shell_immediately: bool,
+}
+
+impl Default for SsrChunking {
+    fn default() -> Self {
+        Self {
+            max_chunks: 32,
+            min_bytes: 0,
+            flush_shell_immediately: false,
+        }
+    }
+}
+
+impl SsrChunking {
+    /// Returns a copy that joins at most `chunks` fragments into one write.
+    ///
+    /// This bounds every write, including one still short of
+    /// [`Self::with_min_bytes`]. A value of `0` is treated as `1`, which
+    /// writes each fragment as Leptos produces it.
+    #[must_use]
+    pub const fn with_max_chunks(mut self, chunks: usize) -> Self {
+        self.max_chunks = if chunks == 0 { 1 } else { chunks };
+        self
+    }
+
+    /// Returns a copy that holds a write back until it has at least `bytes`
+    /// bytes, waiting for fragments that are not ready yet.
+    ///
+    /// The default of `0` writes whatever is ready. A threshold trades
+    /// latency for fewer writes: under `SsrMode::OutOfOrder`, a resolved
+    /// `Suspense` fragment waits for later ones until the threshold is met.
+    /// The end of the stream always flushes what is left.
+    #[must_use]
+    pub const fn with_min_bytes(mut self, bytes: usize) -> Self {
+        self.min_bytes = bytes;
+        self
+    }
+
+    /// Returns a copy that writes the document shell - the first write,
+    /// carrying `<head>` - as soon as it is ready, without waiting for
+    /// [`Self::with_min_bytes`], so the browser can start fetching styles and
+    /// scripts while the rest of the page renders.
+    #[must_use]
+    pub const fn flush_shell_immediately(mut self) -> Self {
+        self.flush_shell_immediately = true;
+        self
+    }
+
+    /// Regroups `stream` into writes following this policy.
+    fn coalesce(
+        self,
+        mut stream: PinnedStream<String>,
+    ) -> PinnedStream<String> {
+        Box::pin(async_stream::stream! {
+            let mut is_shell = true;
+            while let Some(mut write) = stream.next().await {
+                let min_bytes = if is_shell && self.flush_shell_immediately {
+                    0
+                } else {
+                    self.min_bytes
+                };
+                is_shell = false;
+                let mut chunks = 1;
+                let mut ended = false;
+                while chunks < self.max_chunks {
+                    let next = if write.len() < min_bytes {
+                        stream.next().await
+                    } else if let Some(next) = stream.next().now_or_never() {
+                        next
+                    } else {
+                        break;
+                    };
+                    let Some(next) = next else {
+                        ended = true;
+                        break;
+                    };
+                    write.push_str(&next);
+                    chunks += 1;
+                }
+                yield write;
+                if ended {
+                    break;
+                }
+            }
+        })
+    }
+}
+
 pub(crate) trait ExtendResponse: Sized {
     type ResponseOptions: Send;
 
@@ -42,6 +155,7 @@ pub(crate) trait ExtendResponse: Sized {
         response_options: Self::ResponseOptions,
         stream_builder: StreamBuilder<IV>,
         is_islands_router_navigation: bool,
+        chunking: SsrChunking,
     ) -> impl Future<Output = Self> + Send
     where
         IV: IntoView + 'static,
@@ -59,8 +173,7 @@ pub(crate) trait ExtendResponse: Sized {
             let shared_context = owner
                 .shared_context()
                 .expect("SSR owner must have a shared context");
-            let stream =
-                stream.await.ready_chunks(32).map(|chunks| chunks.join(""));
+            let stream = chunking.coalesce(stream.await);
 
             while let Some(pending) = shared_context.await_deferred() {
                 pending.await;
@@ -184,3 +297,91 @@ where
     }));
     (owner, stream)
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc;
+
+    use super::*;
+
+    /// A stream of fragments the test releases one batch at a time.
+    fn fragments() -> (mpsc::UnboundedSender<String>, PinnedStream<String>) {
+        let (sender, receiver) = mpsc::unbounded();
+        (sender, Box::pin(receiver))
+    }
+
+    fn send(sender: &mpsc::UnboundedSender<String>, fragments: &[&str]) {
+        for fragment in fragments {
+            sender
+                .unbounded_send((*fragment).to_owned())
+                .expect("the receiver should be alive");
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn default_policy_joins_only_what_is_ready() {
+        let (sender, stream) = fragments();
+        let mut writes = SsrChunking::default().coalesce(stream);
+
+        send(&sender, &["<head>", "</head>", "<body>"]);
+        assert_eq!(writes.next().await.as_deref(), Some("<head></head><body>"));
+        send(&sender, &["late"]);
+        drop(sender);
+        assert_eq!(writes.next().await.as_deref(), Some("late"));
+        assert_eq!(writes.next().await, None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn max_chunks_bounds_each_write() {
+        let (sender, stream) = fragments();
+        send(&sender, &["a", "b", "c", "d", "e"]);
+        drop(sender);
+
+        let writes = SsrChunking::default()
+            .with_max_chunks(2)
+            .coalesce(stream)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(writes, ["ab", "cd", "e"]);
+
+        let (sender, stream) = fragments();
+        send(&sender, &["a", "b"]);
+        drop(sender);
+        let writes = SsrChunking::default()
+            .with_max_chunks(0)
+            .coalesce(stream)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(writes, ["a", "b"]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn min_bytes_waits_for_fragments_not_yet_ready() {
+        let (sender, stream) = fragments();
+        let mut writes =
+            SsrChunking::default().with_min_bytes(6).coalesce(stream);
+
+        send(&sender, &["abc"]);
+        assert!(writes.next().now_or_never().is_none());
+        send(&sender, &["def", "g"]);
+        assert_eq!(writes.next().await.as_deref(), Some("abcdefg"));
+        send(&sender, &["tail"]);
+        drop(sender);
+        assert_eq!(writes.next().await.as_deref(), Some("tail"));
+        assert_eq!(writes.next().await, None);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn the_shell_can_skip_the_byte_threshold() {
+        let (sender, stream) = fragments();
+        let mut writes = SsrChunking::default()
+            .with_min_bytes(1024)
+            .flush_shell_immediately()
+            .coalesce(stream);
+
+        send(&sender, &["<head>", "</head>"]);
+        assert_eq!(writes.next().await.as_deref(), Some("<head></head>"));
+        send(&sender, &["<p>", "</p>"]);
+        assert!(writes.next().now_or_never().is_none());
+    }
+}
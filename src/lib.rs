@@ -13,19 +13,31 @@ mod handler;
 mod integration;
 mod request;
 pub mod response;
-mod static_files;
+pub mod static_files;
 pub mod utils;
 
 pub use executor::ExecutorError;
 pub use handler::{
-    DEFAULT_MAX_REQUEST_BODY_SIZE, HandlerConfig, RegistrationError,
-    RequestPolicyError, validate_route_table,
+    BodyLimit, ClientInfo, ContentSecurityPolicy, CrossOriginEmbedderPolicy,
+    CrossOriginOpenerPolicy, CrossOriginResourcePolicy,
+    DEFAULT_MAX_MULTIPART_PARTS, DEFAULT_MAX_REQUEST_BODY_SIZE, ErrorCause,
+    ErrorPage, ErrorPages, ForwardingHeaders, FrameOptions, HandlerConfig,
+    HeaderLimits, Hsts, InvalidCidr, MultipartLimits, RedirectPolicy,
+    ReferrerPolicy, RegistrationError, RequestId, RequestIdFormat,
+    RequestIdPolicy, RequestPolicyError, SecurityHeaders, TrustedProxies,
+    validate_route_table,
 };
+#[cfg(feature = "compression")]
+pub use handler::{
+    DEFAULT_MAX_DECOMPRESSION_RATIO, RequestDecompression, ResponseCompression,
+};
+pub use integration::SsrChunking;
 
 /// Implementation details required by generated public bounds.
 ///
 /// This module is not part of the stable API. Its traits are blanket
-/// implemented and cannot be implemented independently by consumers.
+/// implemented or sealed, and cannot be implemented independently by
+/// consumers.
 #[doc(hidden)]
 pub mod __private {
     /// Projects the request and response body types from a server backend.
@@ -59,6 +71,55 @@ pub mod __private {
         type ResBody = ResBody;
     }
 
+    /// The body chunks a streamed server function request arrives in.
+    pub type ChunkStream = std::pin::Pin<
+        Box<
+            dyn futures::Stream<Item = Result<bytes::Bytes, std::io::Error>>
+                + Send,
+        >,
+    >;
+
+    /// Builds a server function's request body from its chunks, for the
+    /// request body types of the backends `server_fn` provides.
+    pub trait FromChunkStream: From<bytes::Bytes> + sealed::Sealed {
+        /// Wraps `chunks` when the body can be read as it arrives, and
+        /// otherwise reads them in full, failing on the first chunk error.
+        fn from_chunk_stream(
+            chunks: ChunkStream,
+        ) -> impl Future<Output = Result<Self, std::io::Error>> + Send;
+    }
+
+    /// The Axum body, which the server function reads as it arrives.
+    impl FromChunkStream for axum_core::body::Body {
+        fn from_chunk_stream(
+            chunks: ChunkStream,
+        ) -> impl Future<Output = Result<Self, std::io::Error>> + Send {
+            std::future::ready(Ok(Self::from_stream(chunks)))
+        }
+    }
+
+    /// The generic backend's body, which holds the whole input.
+    impl FromChunkStream for bytes::Bytes {
+        async fn from_chunk_stream(
+            mut chunks: ChunkStream,
+        ) -> Result<Self, std::io::Error> {
+            use futures::StreamExt;
+
+            let mut bytes = bytes::BytesMut::new();
+            while let Some(chunk) = chunks.next().await {
+                bytes.extend_from_slice(&chunk?);
+            }
+            Ok(bytes.freeze())
+        }
+    }
+
+    mod sealed {
+        pub trait Sealed {}
+
+        impl Sealed for axum_core::body::Body {}
+        impl Sealed for bytes::Bytes {}
+    }
+
     /// Returns the current Preview 2 pollable queue depth for release probes.
     #[cfg(feature = "wasip2")]
     #[doc(hidden)]
@@ -73,7 +134,7 @@ pub mod prelude {
     pub use crate::{
         ExecutorError, HandlerConfig, RegistrationError, RequestPolicyError,
         response::{Body, ResponseOptions, ResponseParts},
-        utils::redirect,
+        utils::{RedirectKind, redirect, redirect_with},
     };
     pub use http::StatusCode;
 }
@@ -3,15 +3,23 @@
 /// WASI Preview 2 request conversion.
 #[cfg(feature = "wasip2")]
 pub mod p2 {
+    use std::io;
+
     use bytes::Bytes;
+    use futures::Stream;
     use http::{Uri, uri::Parts};
     use thiserror::Error;
     use wasi::{
         clocks::monotonic_clock::subscribe_duration,
         http::types::{IncomingBody, IncomingRequest, Method, Scheme},
-        io::{poll::poll, streams::StreamError},
+        io::{
+            poll::poll,
+            streams::{InputStream, StreamError},
+        },
     };
 
+    use crate::{HeaderLimits, RequestPolicyError, executor::WaitPoll};
+
     /// Converts a WASI Preview 2 request into an `http` request while enforcing
     /// the configured body limit.
     ///
@@ -44,7 +52,7 @@ pub mod p2 {
         max_body_size: usize,
         timeout_ns: Option<u64>,
     ) -> Result<http::Request<Bytes>, RequestError> {
-        let parts = request_parts(&request)?;
+        let (parts, _) = request_parts(&request, None)?;
         super::super::handler::validate_content_length(
             &parts.headers,
             max_body_size,
@@ -116,15 +124,108 @@ pub mod p2 {
         Ok(http::Request::from_parts(parts, Bytes::from(body)))
     }
 
+    /// Opens a WASI Preview 2 request body as a stream of chunks, without
+    /// reading any of it.
+    ///
+    /// Each chunk is a non-blocking read; an empty read waits on the
+    /// executor for the stream to become readable, so other tasks keep
+    /// running while the client is slow. The stream ends when the client
+    /// closes the body, and a host read failure ends it with an error.
+    pub(crate) fn body_chunks(
+        request: IncomingRequest,
+    ) -> Result<
+        impl Stream<Item = Result<Bytes, io::Error>> + Send + 'static,
+        RequestError,
+    > {
+        let incoming_body = request
+            .consume()
+            .map_err(|()| RequestError::BodyAlreadyConsumed)?;
+        drop(request);
+        let Ok(body_stream) = incoming_body.stream() else {
+            IncomingBody::finish(incoming_body);
+            return Err(RequestError::BodyStreamUnavailable);
+        };
+        Ok(futures::stream::unfold(
+            Some((body_stream, incoming_body)),
+            |state| async move {
+                let (body_stream, incoming_body) = state?;
+                let read = next_chunk(&body_stream).await;
+                match read {
+                    Ok(Some(chunk)) => {
+                        Some((Ok(chunk), Some((body_stream, incoming_body))))
+                    }
+                    Ok(None) => {
+                        drop(body_stream);
+                        IncomingBody::finish(incoming_body);
+                        None
+                    }
+                    Err(error) => Some((Err(error), None)),
+                }
+            },
+        ))
+    }
+
+    /// Reads the next non-empty chunk of `body_stream`, or `None` once it is
+    /// closed.
+    async fn next_chunk(
+        body_stream: &InputStream,
+    ) -> Result<Option<Bytes>, io::Error> {
+        loop {
+            match body_stream.read(crate::CHUNK_BYTE_SIZE as u64) {
+                Ok(data) if data.is_empty() => {
+                    WaitPoll::new(body_stream.subscribe())
+                        .await
+                        .map_err(io::Error::other)?;
+                }
+                Ok(data) => return Ok(Some(Bytes::from(data))),
+                Err(StreamError::Closed) => return Ok(None),
+                Err(error) => {
+                    return Err(io::Error::other(RequestError::from(error)));
+                }
+            }
+        }
+    }
+
+    /// Converts the request line and headers, checking them against
+    /// `limits` before anything is copied into `http` types.
+    ///
+    /// A breach is returned alongside parts that keep the method, the path
+    /// with query cut to its limit, and the headers admitted before the
+    /// breach, so the rejection is answered against the real request.
     pub(crate) fn request_parts(
         request: &IncomingRequest,
-    ) -> Result<http::request::Parts, RequestError> {
-        let mut builder = http::Request::builder();
+        limits: Option<HeaderLimits>,
+    ) -> Result<(http::request::Parts, Option<RequestPolicyError>), RequestError>
+    {
         let headers = request.headers();
-        for (name, value) in headers.entries() {
+        let mut entries = headers.entries();
+        drop(headers);
+        let mut path_with_query = request.path_with_query();
+        let mut breach = None;
+        if let Some(limits) = limits {
+            if let Err(error) = limits.check_uri(path_with_query.as_deref()) {
+                path_with_query = path_with_query.map(|mut path| {
+                    let mut end = limits.max_uri_length();
+                    while !path.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    path.truncate(end);
+                    path
+                });
+                breach = Some(error);
+            }
+            let (admitted, result) = limits.admit(
+                entries
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_slice())),
+            );
+            entries.truncate(admitted);
+            breach = breach.or(result.err());
+        }
+        let mut builder = http::Request::builder();
+        for (name, value) in entries {
             builder = builder.header(name, value);
         }
-        drop(headers);
 
         let mut uri = Parts::default();
         uri.scheme = request.scheme().map(scheme_wasi_to_http).transpose()?;
@@ -135,19 +236,25 @@ pub mod p2 {
             })
             .transpose()
             .map_err(http::Error::from)?;
-        uri.path_and_query = request
-            .path_with_query()
+        uri.path_and_query = match path_with_query
             .map(|path| {
                 http::uri::PathAndQuery::from_maybe_shared(path.into_bytes())
             })
             .transpose()
-            .map_err(http::Error::from)?;
+        {
+            Ok(path) => path,
+            // A path cut mid-escape is answered from `/` instead.
+            Err(_) if breach.is_some() => {
+                Some(http::uri::PathAndQuery::from_static("/"))
+            }
+            Err(error) => return Err(http::Error::from(error).into()),
+        };
 
         let request = builder
             .method(method_wasi_to_http(request.method())?)
             .uri(Uri::from_parts(uri).map_err(http::Error::from)?)
             .body(())?;
-        Ok(request.into_parts().0)
+        Ok((request.into_parts().0, breach))
     }
 
     /// Errors converting a WASI Preview 2 incoming request.
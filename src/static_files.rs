@@ -1,9 +1,30 @@
-//! Static asset path validation shared by the WASI HTTP transports.
+//! Static assets served through a handler's static-file prefixes.
+//!
+//! [`StaticAsset`] is what a `static_assets_handler` callback returns,
+//! [`SeekableSource`] is how it hands over a body to read ranges from, and
+//! [`StaticRouteOptions`] configures the responses for one prefix.
+//! [`FsStaticProvider`] serves a preopened directory, and [`EmbeddedAssets`]
+//! serves files compiled into the component by [`write_embedded_assets`]. The
+//! rest of this module is the path validation shared by the WASI HTTP
+//! transports, which runs before any callback sees a request path.
 
 use std::path::{Component, Path};
 
 use thiserror::Error;
 
+mod asset;
+mod embedded;
+mod fs;
+mod options;
+mod source;
+
+pub use asset::StaticAsset;
+pub(crate) use asset::{AssetSource, content_tag};
+pub use embedded::{EmbeddedAsset, EmbeddedAssets, write_embedded_assets};
+pub use fs::FsStaticProvider;
+pub use options::{Precompressed, StaticRouteOptions};
+pub use source::SeekableSource;
+
 /// An error produced while validating a static asset path.
 ///
 /// Static asset paths must remain relative after exactly one percent-decoding
@@ -0,0 +1,264 @@
+//! The value a static-asset callback returns.
+
+use std::{sync::Arc, time::SystemTime};
+
+use http::{
+    HeaderMap, HeaderName, HeaderValue,
+    header::{
+        ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE,
+        CONTENT_TYPE, ETAG, LAST_MODIFIED, TRANSFER_ENCODING,
+    },
+};
+
+use super::SeekableSource;
+use crate::response::Body;
+
+/// A static asset, plus the metadata the handler uses to describe it and to
+/// answer conditional requests for it.
+///
+/// A bare [`Body`] is enough for the handler to serve an asset, which is why
+/// `static_files_handler` takes one. Returning a `StaticAsset` from a
+/// `static_assets_handler` callback instead lets the callback supply an
+/// `ETag` and a `Last-Modified` time it already knows, such as a build hash
+/// or a file's modification time, so that `If-None-Match` and
+/// `If-Modified-Since` can be answered with `304 Not Modified`.
+///
+/// Without an explicit tag the handler computes a strong `ETag` from a
+/// [`Body::Sync`] body. An asynchronous body cannot be hashed without
+/// consuming it, so it is served without one unless the callback provides it.
+///
+/// A [`Body::Sync`] asset, and one built with [`StaticAsset::seekable`], also
+/// answers `Range` requests. A plain asynchronous body is always sent whole.
+///
+/// The rest of the response can be described up front as well: a
+/// `Content-Type` in place of the one guessed from the path, the length of an
+/// asynchronous body, and extra headers such as `Content-Disposition`. The
+/// handler answers `HEAD` and `304 Not Modified` from this metadata alone,
+/// without polling the body.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// use bytes::Bytes;
+/// use leptos_wasi::{response::Body, static_files::StaticAsset};
+///
+/// let asset = StaticAsset::new(Body::Sync(Bytes::from_static(b"body {}")))
+///     .with_etag("build-4f2a")
+///     .with_last_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+///
+/// let download = StaticAsset::new(Body::Sync(Bytes::from_static(b"a,b\n")))
+///     .with_content_type("text/csv; charset=utf-8")
+///     .with_header(
+///         http::header::CONTENT_DISPOSITION,
+///         http::HeaderValue::from_static("attachment"),
+///     );
+/// ```
+pub struct StaticAsset {
+    pub(crate) source: AssetSource,
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<SystemTime>,
+    pub(crate) content_type: Option<String>,
+    pub(crate) length: Option<u64>,
+    pub(crate) headers: HeaderMap,
+}
+
+impl StaticAsset {
+    /// Wraps a body with no validators of its own.
+    #[must_use]
+    pub fn new(body: impl Into<Body>) -> Self {
+        Self::from_source(AssetSource::Body(body.into()))
+    }
+
+    /// Wraps a source the handler can read byte ranges from.
+    ///
+    /// The handler never computes an `ETag` for a seekable source, since
+    /// that would mean reading all of it; supply one with
+    /// [`Self::with_etag`] so that `If-None-Match` and `If-Range` can match.
+    #[must_use]
+    pub fn seekable(source: impl SeekableSource) -> Self {
+        Self::from_source(AssetSource::Seekable(Arc::new(source)))
+    }
+
+    fn from_source(source: AssetSource) -> Self {
+        Self {
+            source,
+            etag: None,
+            last_modified: None,
+            content_type: None,
+            length: None,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Returns a copy that is served with a strong `ETag` of `tag`.
+    ///
+    /// `tag` is the opaque part of the entity tag; the handler adds the
+    /// surrounding quotes. A tag containing a `"` or a character outside
+    /// visible ASCII cannot be sent in a header, so it is ignored and the
+    /// handler falls back to computing one.
+    #[must_use]
+    pub fn with_etag(mut self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        self.etag = is_valid_opaque_tag(&tag).then_some(tag);
+        self
+    }
+
+    /// Returns a copy that is served with `Last-Modified: time`.
+    ///
+    /// HTTP dates have whole-second resolution, so any fraction of a second
+    /// is ignored both in the header and when comparing against
+    /// `If-Modified-Since`.
+    #[must_use]
+    pub const fn with_last_modified(mut self, time: SystemTime) -> Self {
+        self.last_modified = Some(time);
+        self
+    }
+
+    /// Returns a copy that is served with `Content-Type: content_type`
+    /// instead of the type guessed from its path.
+    ///
+    /// A value that cannot be sent in a header is ignored, and the handler
+    /// falls back to the guess.
+    #[must_use]
+    pub fn with_content_type(
+        mut self,
+        content_type: impl Into<String>,
+    ) -> Self {
+        let content_type = content_type.into();
+        self.content_type = HeaderValue::from_str(&content_type)
+            .is_ok()
+            .then_some(content_type);
+        self
+    }
+
+    /// Returns a copy that declares its asynchronous body to be `length`
+    /// bytes long, so that the response carries `Content-Length`.
+    ///
+    /// The body must produce exactly that many bytes; the host rejects a
+    /// response whose body disagrees with its length. Synchronous and
+    /// seekable bodies already know their length and ignore this value.
+    #[must_use]
+    pub const fn with_length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Returns a copy that is served with an extra `name: value` header.
+    ///
+    /// Extra headers appear on `200`, `206`, and `304` responses for the
+    /// asset. A name set more than once keeps every value. An extra header
+    /// replaces the prefix's value for the same name, so a per-asset
+    /// `Cache-Control` wins over the route options, except that `Vary` is
+    /// added to rather than replaced. Headers the handler derives itself -
+    /// `Content-Type`, `Content-Length`, `Content-Range`, `Content-Encoding`,
+    /// `Transfer-Encoding`, `Accept-Ranges`, `ETag`, and `Last-Modified` - are
+    /// ignored here; use the dedicated builders instead.
+    #[must_use]
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        if !is_derived_header(&name) {
+            self.headers.append(name, value);
+        }
+        self
+    }
+}
+
+impl From<Body> for StaticAsset {
+    fn from(body: Body) -> Self {
+        Self::new(body)
+    }
+}
+
+/// Where a [`StaticAsset`]'s bytes come from.
+pub(crate) enum AssetSource {
+    /// A body produced up front, which can only be sliced when it is
+    /// [`Body::Sync`].
+    Body(Body),
+    /// A body the handler reads range by range.
+    Seekable(Arc<dyn SeekableSource>),
+}
+
+/// Whether the handler computes `name` from the asset and its range
+/// evaluation, so a callback may not set it directly.
+fn is_derived_header(name: &HeaderName) -> bool {
+    [
+        CONTENT_TYPE,
+        CONTENT_LENGTH,
+        CONTENT_RANGE,
+        CONTENT_ENCODING,
+        TRANSFER_ENCODING,
+        ACCEPT_RANGES,
+        ETAG,
+        LAST_MODIFIED,
+    ]
+    .contains(name)
+}
+
+/// Whether `tag` is a valid `opaque-tag` body: `etagc` is `%x21 / %x23-7E`
+/// plus obsolete text, and this crate does not emit obsolete text.
+fn is_valid_opaque_tag(tag: &str) -> bool {
+    tag.bytes()
+        .all(|byte| byte == 0x21 || (0x23..=0x7e).contains(&byte))
+}
+
+/// Computes the opaque part of a strong entity tag for `bytes`.
+///
+/// FNV-1a is not collision resistant, and does not need to be: an entity tag
+/// only has to change when the content does, and the length is folded in so
+/// that the common edit - an asset growing or shrinking - cannot collide at
+/// all. Hashing costs one pass over a body the handler already holds.
+pub(crate) fn content_tag(bytes: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}-{:x}", bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[test]
+    fn content_tags_change_with_the_content() {
+        assert_eq!(content_tag(b"one"), content_tag(b"one"));
+        assert_ne!(content_tag(b"one"), content_tag(b"two"));
+        assert_ne!(content_tag(b""), content_tag(b"\0"));
+    }
+
+    #[test]
+    fn content_tags_are_valid_opaque_tags() {
+        assert!(is_valid_opaque_tag(&content_tag(b"console.log(1)")));
+    }
+
+    #[test]
+    fn unusable_explicit_tags_are_dropped() {
+        for tag in ["has space", "quote\"d", "caf\u{e9}", "line\nbreak"] {
+            let asset =
+                StaticAsset::new(Body::Sync(Bytes::new())).with_etag(tag);
+            assert_eq!(asset.etag, None, "{tag:?}");
+        }
+        let asset =
+            StaticAsset::new(Body::Sync(Bytes::new())).with_etag("v1.2-beta");
+        assert_eq!(asset.etag.as_deref(), Some("v1.2-beta"));
+    }
+
+    #[test]
+    fn derived_headers_cannot_be_set_directly() {
+        let asset = StaticAsset::new(Body::Sync(Bytes::new()))
+            .with_header(CONTENT_LENGTH, HeaderValue::from_static("9"))
+            .with_header(ETAG, HeaderValue::from_static("\"v2\""))
+            .with_header(
+                http::header::LINK,
+                HeaderValue::from_static("</a.js>; rel=preload"),
+            );
+
+        assert_eq!(asset.headers.len(), 1);
+        assert!(asset.headers.contains_key(http::header::LINK));
+    }
+}
@@ -0,0 +1,414 @@
+//! Static assets compiled into the component.
+
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use mime_guess::MimeGuess;
+
+use super::{Precompressed, StaticAsset, content_tag};
+use crate::response::Body;
+
+/// One file baked into an [`EmbeddedAssets`] table.
+///
+/// Tables are normally generated by [`write_embedded_assets`] rather than
+/// written by hand.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EmbeddedAsset {
+    path: &'static str,
+    bytes: &'static [u8],
+    etag: &'static str,
+    content_type: &'static str,
+}
+
+impl EmbeddedAsset {
+    /// Describes one embedded file.
+    ///
+    /// `path` is relative to the static prefix, with `/` separators and no
+    /// leading slash, exactly as the handler passes it to its callback.
+    /// `etag` is the opaque part of the entity tag, without quotes.
+    #[must_use]
+    pub const fn new(
+        path: &'static str,
+        bytes: &'static [u8],
+        etag: &'static str,
+        content_type: &'static str,
+    ) -> Self {
+        Self {
+            path,
+            bytes,
+            etag,
+            content_type,
+        }
+    }
+
+    /// The path relative to the static prefix.
+    #[must_use]
+    pub const fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// The file's contents.
+    #[must_use]
+    pub const fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
+    /// The precomputed entity tag, without quotes.
+    #[must_use]
+    pub const fn etag(&self) -> &'static str {
+        self.etag
+    }
+
+    /// The precomputed `Content-Type`.
+    #[must_use]
+    pub const fn content_type(&self) -> &'static str {
+        self.content_type
+    }
+
+    /// The asset as the handler serves it, with its precomputed validators
+    /// and media type.
+    #[must_use]
+    pub fn to_static_asset(&self) -> StaticAsset {
+        StaticAsset::new(Body::Sync(Bytes::from_static(self.bytes)))
+            .with_etag(self.etag)
+            .with_content_type(self.content_type)
+    }
+}
+
+/// A table of static assets compiled into the component.
+///
+/// Serving from the table needs no filesystem preopen and no WASI filesystem
+/// import, so a component that only embeds its assets is self-contained for
+/// `wasmtime serve`. The table is sorted by path, so each lookup is a binary
+/// search, and every asset's `ETag` and `Content-Type` were computed when it
+/// was generated, so a request costs no hashing. A table that is not sorted,
+/// such as a hand-written one, still works through a linear scan.
+///
+/// Generate the table from a build script with [`write_embedded_assets`] and
+/// include it where the handler is built:
+///
+/// ```rust,ignore
+/// use leptos_wasi::static_files::EmbeddedAssets;
+///
+/// static PKG: EmbeddedAssets =
+///     include!(concat!(env!("OUT_DIR"), "/pkg_assets.rs"));
+///
+/// let handler = handler.static_assets_handler("/pkg", PKG.handler())?;
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::static_files::{EmbeddedAsset, EmbeddedAssets};
+///
+/// static ASSETS: EmbeddedAssets = EmbeddedAssets::new(&[
+///     EmbeddedAsset::new("app.css", b"body {}", "css-1", "text/css"),
+///     EmbeddedAsset::new("app.js", b"main()", "js-1", "text/javascript"),
+/// ]);
+///
+/// assert!(ASSETS.get("app.js").is_some());
+/// assert!(ASSETS.get("missing.js").is_none());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EmbeddedAssets {
+    assets: &'static [EmbeddedAsset],
+    sorted: bool,
+}
+
+impl EmbeddedAssets {
+    /// Wraps a table of assets, ideally sorted by path.
+    #[must_use]
+    pub const fn new(assets: &'static [EmbeddedAsset]) -> Self {
+        let mut sorted = true;
+        let mut index = 1;
+        while index < assets.len() {
+            if !is_before(assets[index - 1].path, assets[index].path) {
+                sorted = false;
+            }
+            index += 1;
+        }
+        Self { assets, sorted }
+    }
+
+    /// Every asset in the table.
+    #[must_use]
+    pub const fn assets(&self) -> &'static [EmbeddedAsset] {
+        self.assets
+    }
+
+    /// The asset at `path`, if the table has one.
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<&'static EmbeddedAsset> {
+        let assets = self.assets;
+        if self.sorted {
+            assets
+                .binary_search_by(|asset| asset.path.cmp(path))
+                .ok()
+                .and_then(|index| assets.get(index))
+        } else {
+            assets.iter().find(|asset| asset.path == path)
+        }
+    }
+
+    /// The asset at `path` as the handler serves it.
+    #[must_use]
+    pub fn asset(&self, path: &str) -> Option<StaticAsset> {
+        self.get(path).map(EmbeddedAsset::to_static_asset)
+    }
+
+    /// The asset at `path` as a bare body, for `static_files_handler`.
+    #[must_use]
+    pub fn body(&self, path: &str) -> Option<Body> {
+        self.get(path)
+            .map(|asset| Body::Sync(Bytes::from_static(asset.bytes)))
+    }
+
+    /// A callback for `static_assets_handler` that serves [`Self::asset`].
+    pub fn handler(
+        &self,
+    ) -> impl Fn(String) -> Option<StaticAsset> + Clone + Send + 'static {
+        let table = *self;
+        move |path| table.asset(&path)
+    }
+
+    /// A callback for `static_files_handler` that serves [`Self::body`].
+    pub fn body_handler(
+        &self,
+    ) -> impl Fn(String) -> Option<Body> + Clone + Send + 'static {
+        let table = *self;
+        move |path| table.body(&path)
+    }
+}
+
+/// Whether `left` sorts strictly before `right` by bytes, the order
+/// `str::cmp` uses, in a form usable from a `const fn`.
+const fn is_before(left: &str, right: &str) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    let mut index = 0;
+    while index < left.len() && index < right.len() {
+        if left[index] != right[index] {
+            return left[index] < right[index];
+        }
+        index += 1;
+    }
+    left.len() < right.len()
+}
+
+/// Writes an [`EmbeddedAssets`] expression for every file below `dir` to
+/// `out`, for a build script to generate and the crate to `include!`.
+///
+/// Each file is embedded with `include_bytes!` by absolute path, together
+/// with an `ETag` hashed from its contents and a `Content-Type` guessed from
+/// its name. A precompressed sibling such as `app.js.br` takes the media type
+/// of `app.js` when that file is embedded too, since that is what it is
+/// served as. The table is sorted by
+/// path. The generated code names the crate as `::leptos_wasi`, so a
+/// dependency renamed in `Cargo.toml` needs a matching `extern crate` alias.
+///
+/// Symbolic links to files are embedded as the file they point to; symbolic
+/// links to directories are skipped, which rules out cycles. The function
+/// prints `cargo:rerun-if-changed` for the directory and every file, so the
+/// table is regenerated when an asset changes.
+///
+/// ```rust,no_run
+/// // build.rs
+/// fn main() -> std::io::Result<()> {
+///     let out_dir = std::env::var("OUT_DIR").map_err(std::io::Error::other)?;
+///     let out = std::path::Path::new(&out_dir).join("pkg_assets.rs");
+///     leptos_wasi::static_files::write_embedded_assets("target/site/pkg", out)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns an I/O error if `dir` cannot be read, if a file name is not valid
+/// UTF-8, or if `out` cannot be written.
+pub fn write_embedded_assets(
+    dir: impl AsRef<Path>,
+    out: impl AsRef<Path>,
+) -> io::Result<()> {
+    let dir = std::path::absolute(dir)?;
+    let mut files = Vec::new();
+    collect_files(&dir, "", &mut files)?;
+    files.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    let mut source = String::from(
+        "// Generated by leptos_wasi::static_files::write_embedded_assets.\n\
+         ::leptos_wasi::static_files::EmbeddedAssets::new(&[\n",
+    );
+    println!("cargo:rerun-if-changed={}", dir.display());
+    for (path, file) in &files {
+        println!("cargo:rerun-if-changed={}", file.display());
+        let bytes = fs::read(file)?;
+        let absolute = file.to_str().ok_or_else(|| not_utf8(file))?;
+        // `{:?}` on a `str` produces a valid Rust string literal.
+        let _ = writeln!(
+            source,
+            "    ::leptos_wasi::static_files::EmbeddedAsset::new(\n        \
+             {path:?},\n        include_bytes!({absolute:?}),\n        \
+             {:?},\n        {:?},\n    ),",
+            content_tag(&bytes),
+            embedded_content_type(path, |original| {
+                files
+                    .binary_search_by(|(path, _)| path.as_str().cmp(original))
+                    .is_ok()
+            }),
+        );
+    }
+    source.push_str("])\n");
+    fs::write(out, source)
+}
+
+/// Appends every file below `dir` to `files` as `(relative path, file)`.
+fn collect_files(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file = entry.path();
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| not_utf8(&file))?;
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&file, &relative, files)?;
+        } else if file_type.is_file()
+            || (file_type.is_symlink() && fs::metadata(&file)?.is_file())
+        {
+            files.push((relative, file));
+        }
+    }
+    Ok(())
+}
+
+/// The media type an embedded file is served as, looking through a
+/// precompressed sibling's extension to the original name when `exists`
+/// says that original is embedded too.
+fn embedded_content_type(path: &str, exists: impl Fn(&str) -> bool) -> String {
+    let original = [
+        Precompressed::Brotli,
+        Precompressed::Zstd,
+        Precompressed::Gzip,
+    ]
+    .into_iter()
+    .find_map(|coding| {
+        path.strip_suffix(coding.extension())
+            .and_then(|stem| stem.strip_suffix('.'))
+            .filter(|original| exists(original))
+    })
+    .unwrap_or(path);
+    MimeGuess::from_path(original)
+        .first_or_octet_stream()
+        .to_string()
+}
+
+fn not_utf8(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} is not valid UTF-8", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    static SORTED: EmbeddedAssets = EmbeddedAssets::new(&[
+        EmbeddedAsset::new("a/b.js", b"b", "tag-b", "text/javascript"),
+        EmbeddedAsset::new("a/c.css", b"c", "tag-c", "text/css"),
+        EmbeddedAsset::new("app.js", b"app", "tag-app", "text/javascript"),
+        EmbeddedAsset::new("z.wasm", b"z", "tag-z", "application/wasm"),
+    ]);
+
+    static UNSORTED: EmbeddedAssets = EmbeddedAssets::new(&[
+        EmbeddedAsset::new("z.wasm", b"z", "tag-z", "application/wasm"),
+        EmbeddedAsset::new("app.js", b"app", "tag-app", "text/javascript"),
+    ]);
+
+    #[test]
+    fn finds_assets_by_binary_search() {
+        assert!(SORTED.sorted);
+        for asset in SORTED.assets() {
+            assert_eq!(SORTED.get(asset.path()), Some(asset));
+        }
+        assert_eq!(SORTED.get("a"), None);
+        assert_eq!(SORTED.get("app.jsx"), None);
+    }
+
+    #[test]
+    fn unsorted_tables_fall_back_to_a_scan() {
+        assert!(!UNSORTED.sorted);
+        assert_eq!(
+            UNSORTED.get("app.js").map(EmbeddedAsset::bytes),
+            Some(&b"app"[..])
+        );
+    }
+
+    #[test]
+    fn served_assets_carry_the_precomputed_metadata() {
+        let asset = SORTED.asset("a/c.css").expect("asset should exist");
+
+        assert_eq!(asset.etag.as_deref(), Some("tag-c"));
+        assert_eq!(asset.content_type.as_deref(), Some("text/css"));
+    }
+
+    #[test]
+    fn precompressed_siblings_take_the_original_media_type() {
+        let exists = |path: &str| ["app.js", "app.wasm"].contains(&path);
+        assert_eq!(
+            embedded_content_type("app.js.br", exists),
+            "text/javascript"
+        );
+        assert_eq!(
+            embedded_content_type("app.wasm.gz", exists),
+            "application/wasm"
+        );
+        assert_eq!(
+            embedded_content_type("archive.tar.gz", exists),
+            "application/gzip"
+        );
+    }
+
+    #[test]
+    fn generates_a_sorted_table() {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let scratch = std::env::temp_dir().join(format!(
+            "leptos-wasi-embed-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(scratch.join("pkg/nested"))
+            .expect("scratch directory should be creatable");
+        fs::write(scratch.join("pkg/z.wasm"), b"z").expect("writable");
+        fs::write(scratch.join("pkg/app.js"), b"app").expect("writable");
+        fs::write(scratch.join("pkg/nested/a.css"), b"a").expect("writable");
+        let out = scratch.join("assets.rs");
+
+        let result = write_embedded_assets(scratch.join("pkg"), &out);
+        let source = fs::read_to_string(&out);
+        let _ = fs::remove_dir_all(&scratch);
+        result.expect("generation should succeed");
+        let source = source.expect("generated file should exist");
+
+        let positions = ["\"app.js\"", "\"nested/a.css\"", "\"z.wasm\""]
+            .map(|path| source.find(path).expect("every file is listed"));
+        assert!(positions.is_sorted(), "{source}");
+        assert!(source.contains(&format!("{:?}", content_tag(b"app"))));
+        assert!(source.contains("\"application/wasm\""));
+        assert!(source.trim_end().ends_with("])"));
+    }
+}
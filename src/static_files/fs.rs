@@ -0,0 +1,443 @@
+//! A static-asset provider backed by a preopened directory.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use bytes::Bytes;
+
+use super::{SeekableSource, StaticAsset};
+use crate::response::Body;
+
+/// The most symbolic links one lookup may traverse, matching the `ELOOP`
+/// limit common Unix kernels apply.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// The size of each chunk a file is streamed in.
+const READ_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Serves static assets from a directory, refusing anything that resolves
+/// outside it.
+///
+/// The handler already rejects traversal in the request path, but a symbolic
+/// link inside the directory can still point anywhere the component can
+/// read. This provider resolves every link in the root and in each candidate
+/// itself, since `std::fs::canonicalize` is unavailable on WASI, and serves
+/// the candidate only when the fully resolved path is a regular file below
+/// the resolved root. Files are streamed in 64 KiB chunks rather than read
+/// whole, and only the bytes a `Range` request asks for are read.
+///
+/// Each asset carries `Last-Modified` from the file's modification time and
+/// an `ETag` derived from that time and the file's length, so conditional
+/// requests are answered without reading the file at all.
+///
+/// The containment check runs before the file is opened. A process that can
+/// replace a directory with a symbolic link between the two can still race
+/// it, so keep the root read-only to everything except deployment.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use leptos_wasi::static_files::FsStaticProvider;
+///
+/// # fn main() -> std::io::Result<()> {
+/// // `/site/pkg` is the directory preopened for the component.
+/// let provider = FsStaticProvider::new("/site/pkg")?;
+/// // handler.static_assets_handler("/pkg", provider.handler())?
+/// let serve = provider.handler();
+/// # let _ = serve;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FsStaticProvider {
+    root: Arc<PathBuf>,
+}
+
+impl FsStaticProvider {
+    /// Resolves `root` and checks that it is a directory.
+    ///
+    /// A relative root is taken relative to the current directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error from resolving `root`, or an
+    /// [`io::ErrorKind::NotADirectory`] error when it resolves to anything
+    /// other than a directory.
+    pub fn new(root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = resolve(PathBuf::new(), &std::path::absolute(root)?)?;
+        if !fs::metadata(&root)?.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", root.display()),
+            ));
+        }
+        Ok(Self {
+            root: Arc::new(root),
+        })
+    }
+
+    /// The resolved root directory.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Looks up the asset at the normalized relative `path` the handler
+    /// passes to its callback.
+    ///
+    /// Returns `None` for anything that does not resolve to a regular file
+    /// inside the root, including a missing file, a directory, and a
+    /// symbolic link that leaves the root.
+    #[must_use]
+    pub fn asset(&self, path: &str) -> Option<StaticAsset> {
+        let file = self.locate(path)?;
+        let metadata = fs::metadata(&file).ok()?;
+        let length = metadata.len();
+        let modified = metadata.modified().ok();
+        let asset = StaticAsset::seekable(FsFile { path: file, length });
+        let Some(modified) = modified else {
+            return Some(asset);
+        };
+        let since_epoch =
+            modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Some(
+            asset
+                .with_etag(format!(
+                    "{:x}.{:x}-{length:x}",
+                    since_epoch.as_secs(),
+                    since_epoch.subsec_nanos()
+                ))
+                .with_last_modified(modified),
+        )
+    }
+
+    /// Looks up the asset at `path` as a bare streamed body, for
+    /// `static_files_handler`.
+    ///
+    /// Prefer [`Self::asset`] through `static_assets_handler`: a bare body
+    /// carries no validators and cannot be read by range.
+    #[must_use]
+    pub fn body(&self, path: &str) -> Option<Body> {
+        let file = self.locate(path)?;
+        let length = fs::metadata(&file).ok()?.len();
+        Some(FsFile { path: file, length }.read_range(0..length))
+    }
+
+    /// A callback for `static_assets_handler` that serves [`Self::asset`].
+    pub fn handler(
+        &self,
+    ) -> impl Fn(String) -> Option<StaticAsset> + Clone + Send + 'static {
+        let provider = self.clone();
+        move |path| provider.asset(&path)
+    }
+
+    /// A callback for `static_files_handler` that serves [`Self::body`].
+    pub fn body_handler(
+        &self,
+    ) -> impl Fn(String) -> Option<Body> + Clone + Send + 'static {
+        let provider = self.clone();
+        move |path| provider.body(&path)
+    }
+
+    /// Resolves `path` below the root and returns it only if the result is a
+    /// regular file that is still inside the root.
+    fn locate(&self, path: &str) -> Option<PathBuf> {
+        let relative = Path::new(path);
+        // The handler has already normalized the path; this only keeps the
+        // provider safe when it is called directly.
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        let resolved = resolve(self.root.to_path_buf(), relative).ok()?;
+        if !resolved.starts_with(self.root.as_path()) {
+            return None;
+        }
+        fs::metadata(&resolved)
+            .is_ok_and(|metadata| metadata.is_file())
+            .then_some(resolved)
+    }
+}
+
+/// Resolves every symbolic link in `path`, taken relative to `start`.
+///
+/// Components are walked one at a time; a link's target replaces it in the
+/// walk, relative to the link's own directory, so links inside links and `..`
+/// inside a target resolve the way the kernel would resolve them.
+fn resolve(start: PathBuf, path: &Path) -> io::Result<PathBuf> {
+    let mut resolved = start;
+    let mut pending = path
+        .components()
+        .rev()
+        .map(|component| PathBuf::from(component.as_os_str()))
+        .collect::<Vec<_>>();
+    let mut hops = 0;
+    while let Some(next) = pending.pop() {
+        match next.components().next() {
+            None | Some(Component::CurDir) => {}
+            Some(Component::RootDir | Component::Prefix(_)) => {
+                resolved.push(&next);
+            }
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            }
+            Some(Component::Normal(name)) => {
+                let candidate = resolved.join(name);
+                if fs::symlink_metadata(&candidate)?.is_symlink() {
+                    hops += 1;
+                    if hops > MAX_SYMLINK_HOPS {
+                        return Err(io::Error::other(
+                            "too many levels of symbolic links",
+                        ));
+                    }
+                    let target = fs::read_link(&candidate)?;
+                    pending.extend(
+                        target.components().rev().map(|component| {
+                            PathBuf::from(component.as_os_str())
+                        }),
+                    );
+                } else {
+                    resolved = candidate;
+                }
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// A file inside the root whose ranges are read on demand.
+struct FsFile {
+    path: PathBuf,
+    length: u64,
+}
+
+impl SeekableSource for FsFile {
+    fn len(&self) -> u64 {
+        self.length
+    }
+
+    fn read_range(&self, range: Range<u64>) -> Body {
+        let path = self.path.clone();
+        Body::Async(Box::pin(async_stream::stream! {
+            let mut file = match open_at(&path, range.start) {
+                Ok(file) => file,
+                Err(error) => {
+                    yield Err(throw_error::Error::from(error));
+                    return;
+                }
+            };
+            let mut remaining = range.end.saturating_sub(range.start);
+            while remaining > 0 {
+                match read_chunk(&mut file, remaining) {
+                    Ok(chunk) => {
+                        remaining -= chunk.len() as u64;
+                        yield Ok(chunk);
+                    }
+                    Err(error) => {
+                        yield Err(throw_error::Error::from(error));
+                        return;
+                    }
+                }
+            }
+        }))
+    }
+}
+
+fn open_at(path: &Path, offset: u64) -> io::Result<File> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    Ok(file)
+}
+
+/// Reads the next chunk of at most `remaining` bytes.
+///
+/// A file that ends early has changed since its length was announced, so
+/// that is an error rather than a short body the host would reject anyway.
+fn read_chunk(file: &mut File, remaining: u64) -> io::Result<Bytes> {
+    let size = usize::try_from(remaining.min(READ_CHUNK_SIZE))
+        .map_err(io::Error::other)?;
+    let mut buffer = vec![0; size];
+    let read = file.read(&mut buffer)?;
+    if read == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "static file is shorter than its announced length",
+        ));
+    }
+    buffer.truncate(read);
+    Ok(Bytes::from(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::StreamExt;
+
+    use super::*;
+
+    /// A scratch directory under the system temporary directory, removed on
+    /// drop.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new() -> Self {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "leptos-wasi-fs-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(path.join("site/pkg/nested"))
+                .expect("scratch directory should be creatable");
+            fs::write(path.join("site/pkg/app.js"), b"console.log(1)")
+                .expect("scratch file should be writable");
+            fs::write(path.join("site/pkg/nested/style.css"), b"body {}")
+                .expect("scratch file should be writable");
+            fs::write(path.join("secret.txt"), b"secret")
+                .expect("scratch file should be writable");
+            Self(path)
+        }
+
+        fn provider(&self) -> FsStaticProvider {
+            FsStaticProvider::new(self.0.join("site/pkg"))
+                .expect("scratch root should resolve")
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn read_all(body: Body) -> Vec<u8> {
+        let Body::Async(mut stream) = body else {
+            return Vec::new();
+        };
+        let mut collected = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            collected.extend_from_slice(
+                &chunk.expect("file stream should not fail"),
+            );
+        }
+        collected
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn serves_files_inside_the_root() {
+        let scratch = Scratch::new();
+        let provider = scratch.provider();
+
+        let body = provider.body("nested/style.css").expect("file exists");
+        assert_eq!(read_all(body).await, b"body {}");
+
+        let asset = provider.asset("app.js").expect("file exists");
+        assert!(asset.etag.is_some());
+        assert!(asset.last_modified.is_some());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn reads_only_the_requested_range() {
+        let scratch = Scratch::new();
+        let file = FsFile {
+            path: scratch.0.join("site/pkg/app.js"),
+            length: 14,
+        };
+
+        assert_eq!(read_all(file.read_range(8..11)).await, b"log");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn a_file_that_shrank_fails_the_stream() {
+        let scratch = Scratch::new();
+        let file = FsFile {
+            path: scratch.0.join("site/pkg/app.js"),
+            length: 20,
+        };
+        let Body::Async(mut stream) = file.read_range(10..20) else {
+            unreachable!("file bodies always stream");
+        };
+
+        assert!(matches!(stream.next().await, Some(Ok(_))));
+        assert!(matches!(stream.next().await, Some(Err(_))));
+    }
+
+    #[test]
+    fn refuses_missing_files_directories_and_unnormalized_paths() {
+        let scratch = Scratch::new();
+        let provider = scratch.provider();
+
+        for path in ["missing.js", "nested", "", "../secret.txt", "/etc/passwd"]
+        {
+            assert!(provider.asset(path).is_none(), "{path}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_root_that_is_not_a_directory() {
+        let scratch = Scratch::new();
+
+        let error = FsStaticProvider::new(scratch.0.join("secret.txt"))
+            .expect_err("a file is not a root");
+        assert_eq!(error.kind(), io::ErrorKind::NotADirectory);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_symlinks_that_escape_the_root() {
+        use std::os::unix::fs::symlink;
+
+        let scratch = Scratch::new();
+        let pkg = scratch.0.join("site/pkg");
+        symlink(scratch.0.join("secret.txt"), pkg.join("absolute.txt"))
+            .expect("symlink should be creatable");
+        symlink("../../secret.txt", pkg.join("relative.txt"))
+            .expect("symlink should be creatable");
+        symlink("../../..", pkg.join("nested/up"))
+            .expect("symlink should be creatable");
+        symlink("loop", pkg.join("loop")).expect("symlink should be creatable");
+        let provider = scratch.provider();
+
+        for path in [
+            "absolute.txt",
+            "relative.txt",
+            "nested/up/secret.txt",
+            "loop",
+        ] {
+            assert!(provider.asset(path).is_none(), "{path}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_that_stay_inside_the_root() {
+        use std::os::unix::fs::symlink;
+
+        let scratch = Scratch::new();
+        let pkg = scratch.0.join("site/pkg");
+        symlink("nested/style.css", pkg.join("linked.css"))
+            .expect("symlink should be creatable");
+        symlink("../app.js", pkg.join("nested/app.js"))
+            .expect("symlink should be creatable");
+        symlink(scratch.0.join("site"), scratch.0.join("site-link"))
+            .expect("symlink should be creatable");
+        let provider = scratch.provider();
+        let through_link =
+            FsStaticProvider::new(scratch.0.join("site-link/pkg"))
+                .expect("a linked root should resolve");
+
+        assert!(provider.asset("linked.css").is_some());
+        assert!(provider.asset("nested/app.js").is_some());
+        assert_eq!(through_link.root(), provider.root());
+        assert!(through_link.asset("app.js").is_some());
+    }
+}
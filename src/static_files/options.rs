@@ -0,0 +1,338 @@
+//! Per-prefix options for a static-asset registration.
+
+use http::HeaderValue;
+
+/// The `Cache-Control` value for file names carrying a content hash.
+const HASHED_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// The `Cache-Control` value for every other file name.
+const UNHASHED_CACHE_CONTROL: &str = "no-cache";
+
+/// A content coding a static asset may be stored in alongside the original.
+///
+/// Each variant names the file extension the compressed sibling is looked up
+/// under, which is what Cargo Leptos and common build tools emit.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Precompressed {
+    /// Brotli, looked up as `<path>.br`.
+    Brotli,
+    /// Zstandard, looked up as `<path>.zst`.
+    Zstd,
+    /// Gzip, looked up as `<path>.gz`.
+    Gzip,
+}
+
+impl Precompressed {
+    /// The `Content-Encoding` token for this coding.
+    #[must_use]
+    pub const fn content_coding(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// The extension appended to an asset path to find this sibling.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Zstd => "zst",
+            Self::Gzip => "gz",
+        }
+    }
+
+    /// Equivalent tokens a request may use for this coding.
+    pub(crate) const fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Self::Gzip => &["x-gzip"],
+            Self::Brotli | Self::Zstd => &[],
+        }
+    }
+}
+
+/// Options for one static-asset prefix, passed when it is registered.
+///
+/// The default sends `Cache-Control: public, max-age=31536000, immutable` for
+/// a file name that carries a content hash, which is what Cargo Leptos
+/// `hash-files` produces, and `Cache-Control: no-cache` for every other file,
+/// which matches the `spin-fileserver` configuration in the Spin manifests.
+/// A hashed name changes whenever its content does, so a cache can keep it
+/// forever; an unhashed name can change content under the same URL, so a
+/// cache must revalidate it, which the `ETag` makes cheap.
+///
+/// A file name counts as hashed when the dot-separated segment before its
+/// extension is at least eight characters of `[A-Za-z0-9_-]` and contains a
+/// digit, as in `app.4f2a9c1b7d.js`. A name that only looks hashed, such as
+/// `release.20240101.js`, would be cached as immutable; register such files
+/// under a prefix with an explicit policy.
+///
+/// # Example
+///
+/// ```rust
+/// use http::HeaderValue;
+/// use leptos_wasi::static_files::{Precompressed, StaticRouteOptions};
+///
+/// // Everything under this prefix is versioned by its directory name.
+/// let versioned = StaticRouteOptions::default().with_cache_control(
+///     HeaderValue::from_static("public, max-age=86400"),
+/// );
+///
+/// // Leave caching to an ingress that sets its own policy.
+/// let delegated = StaticRouteOptions::default().without_cache_control();
+///
+/// // Prefer `app.js.br`, then `app.js.gz`, when the client accepts them.
+/// let compressed = StaticRouteOptions::default()
+///     .with_precompressed([Precompressed::Brotli, Precompressed::Gzip]);
+///
+/// // A client-side-routed docs site mounted under `/docs`.
+/// let docs = StaticRouteOptions::default()
+///     .with_directory_index("index.html")
+///     .with_directory_redirect()
+///     .with_fallback("index.html");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StaticRouteOptions {
+    hashed_cache_control: Option<HeaderValue>,
+    unhashed_cache_control: Option<HeaderValue>,
+    precompressed: Vec<Precompressed>,
+    directory_index: Option<String>,
+    fallback: Option<String>,
+    directory_redirect: bool,
+}
+
+impl Default for StaticRouteOptions {
+    fn default() -> Self {
+        Self {
+            hashed_cache_control: Some(HeaderValue::from_static(
+                HASHED_CACHE_CONTROL,
+            )),
+            unhashed_cache_control: Some(HeaderValue::from_static(
+                UNHASHED_CACHE_CONTROL,
+            )),
+            precompressed: Vec::new(),
+            directory_index: None,
+            fallback: None,
+            directory_redirect: false,
+        }
+    }
+}
+
+impl StaticRouteOptions {
+    /// Returns a copy that sends `value` for every asset under the prefix,
+    /// hashed or not.
+    #[must_use]
+    pub fn with_cache_control(mut self, value: HeaderValue) -> Self {
+        self.hashed_cache_control = Some(value.clone());
+        self.unhashed_cache_control = Some(value);
+        self
+    }
+
+    /// Returns a copy that sends `value` for file names carrying a content
+    /// hash.
+    #[must_use]
+    pub fn with_hashed_cache_control(mut self, value: HeaderValue) -> Self {
+        self.hashed_cache_control = Some(value);
+        self
+    }
+
+    /// Returns a copy that sends `value` for file names without a content
+    /// hash.
+    #[must_use]
+    pub fn with_unhashed_cache_control(mut self, value: HeaderValue) -> Self {
+        self.unhashed_cache_control = Some(value);
+        self
+    }
+
+    /// Returns a copy that sends no `Cache-Control` under the prefix, which
+    /// was the behaviour before these options existed.
+    #[must_use]
+    pub fn without_cache_control(mut self) -> Self {
+        self.hashed_cache_control = None;
+        self.unhashed_cache_control = None;
+        self
+    }
+
+    /// Returns a copy that serves compressed siblings of each asset.
+    ///
+    /// For a request whose `Accept-Encoding` accepts one of `codings`, the
+    /// callback is first asked for `<path>.<extension>`, in the order of the
+    /// client's quality values and then of `codings`, before it is asked for
+    /// `path` itself. A sibling it returns is sent with `Content-Encoding`,
+    /// the `Content-Type` and `Cache-Control` of the original path, and an
+    /// `ETag` distinct from the original's. Every response under the prefix
+    /// then carries `Vary: Accept-Encoding`.
+    ///
+    /// Off by default, since each negotiated request can cost the callback
+    /// one extra lookup per coding that does not exist.
+    #[must_use]
+    pub fn with_precompressed(
+        mut self,
+        codings: impl IntoIterator<Item = Precompressed>,
+    ) -> Self {
+        self.precompressed.clear();
+        for coding in codings {
+            if !self.precompressed.contains(&coding) {
+                self.precompressed.push(coding);
+            }
+        }
+        self
+    }
+
+    /// Returns a copy that serves `file_name` for directory paths.
+    ///
+    /// A request for the prefix root, or for any path ending in `/`, asks
+    /// the callback for `<directory>/<file_name>` first, such as
+    /// `guide/index.html` for `/docs/guide/`. When the callback has no such
+    /// file, it is asked for the directory path itself, as it is without
+    /// this option.
+    #[must_use]
+    pub fn with_directory_index(
+        mut self,
+        file_name: impl Into<String>,
+    ) -> Self {
+        self.directory_index = Some(file_name.into());
+        self
+    }
+
+    /// Returns a copy that redirects a directory requested without its
+    /// trailing slash to the path with one.
+    ///
+    /// `/docs` is answered with `308 Permanent Redirect` to `/docs/` when
+    /// `/docs` is the prefix itself, so that relative links in the index
+    /// document resolve below the prefix. With a directory index configured,
+    /// `/docs/guide` is also redirected to `/docs/guide/` when the callback has
+    /// nothing at `guide` but has `guide/<index>`. The query string is kept.
+    #[must_use]
+    pub const fn with_directory_redirect(mut self) -> Self {
+        self.directory_redirect = true;
+        self
+    }
+
+    /// Returns a copy that serves `document` for paths the callback has
+    /// nothing for, for a client-side-routed application mounted under the
+    /// prefix.
+    ///
+    /// `document` is a path relative to the prefix, such as `index.html`,
+    /// and is served with `200 OK` and its own validators and
+    /// `Cache-Control`. Only requests whose `Accept` admits HTML receive it;
+    /// a missing script, stylesheet, or image still gets a `404` rather
+    /// than an HTML page its loader cannot parse.
+    #[must_use]
+    pub fn with_fallback(mut self, document: impl Into<String>) -> Self {
+        self.fallback = Some(document.into());
+        self
+    }
+
+    /// The file served for directory paths, if any.
+    pub(crate) fn directory_index(&self) -> Option<&str> {
+        self.directory_index.as_deref()
+    }
+
+    /// The document served for paths the callback has nothing for, if any.
+    pub(crate) fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    /// Whether directories requested without a trailing slash redirect.
+    pub(crate) const fn directory_redirect(&self) -> bool {
+        self.directory_redirect
+    }
+
+    /// The compressed siblings to look for, in server preference order.
+    pub(crate) fn precompressed(&self) -> &[Precompressed] {
+        &self.precompressed
+    }
+
+    /// The `Cache-Control` value for the asset at the normalized relative
+    /// `path`, if any.
+    pub(crate) fn cache_control(&self, path: &str) -> Option<&HeaderValue> {
+        if is_hashed_file_name(path) {
+            self.hashed_cache_control.as_ref()
+        } else {
+            self.unhashed_cache_control.as_ref()
+        }
+    }
+}
+
+/// Whether the final component of `path` carries a content hash in the
+/// `name.hash.ext` shape Cargo Leptos `hash-files` produces.
+fn is_hashed_file_name(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let mut segments = file_name.rsplit('.');
+    let (Some(_extension), Some(hash), Some(_stem)) =
+        (segments.next(), segments.next(), segments.next())
+    else {
+        return false;
+    };
+    hash.len() >= 8
+        && hash.bytes().any(|byte| byte.is_ascii_digit())
+        && hash.bytes().all(|byte| {
+            byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-'
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_hashed_file_names() {
+        for path in [
+            "app.4f2a9c1b7d.js",
+            "pkg/app.Xq3-_k9LmN0pQr.wasm",
+            "nested/dir/style.0123abcd.css",
+        ] {
+            assert!(is_hashed_file_name(path), "{path}");
+        }
+    }
+
+    #[test]
+    fn leaves_ordinary_file_names_unhashed() {
+        for path in [
+            "app.js",
+            "app_bg.wasm",
+            "jquery-3.7.1.min.js",
+            "app.abcdefghij.js",
+            "app.1234567.js",
+            "4f2a9c1b7d.js",
+            "app.4f2a9c1b.7d/app.js",
+            // Only the segment before the final extension is considered.
+            "app.4f2a9c1b7d.js.br",
+            "",
+        ] {
+            assert!(!is_hashed_file_name(path), "{path}");
+        }
+    }
+
+    #[test]
+    fn default_policy_splits_on_the_hash() {
+        let options = StaticRouteOptions::default();
+
+        assert_eq!(
+            options.cache_control("app.4f2a9c1b7d.js"),
+            Some(&HeaderValue::from_static(HASHED_CACHE_CONTROL))
+        );
+        assert_eq!(
+            options.cache_control("app.js"),
+            Some(&HeaderValue::from_static(UNHASHED_CACHE_CONTROL))
+        );
+    }
+
+    #[test]
+    fn explicit_policies_replace_the_defaults() {
+        let value = HeaderValue::from_static("private, max-age=60");
+        let options =
+            StaticRouteOptions::default().with_cache_control(value.clone());
+        assert_eq!(options.cache_control("app.4f2a9c1b7d.js"), Some(&value));
+        assert_eq!(options.cache_control("app.js"), Some(&value));
+
+        let options = StaticRouteOptions::default()
+            .without_cache_control()
+            .with_unhashed_cache_control(value.clone());
+        assert_eq!(options.cache_control("app.4f2a9c1b7d.js"), None);
+        assert_eq!(options.cache_control("app.js"), Some(&value));
+    }
+}
@@ -0,0 +1,55 @@
+//! Asset bodies the handler can read a byte range of.
+
+use std::ops::Range;
+
+use crate::response::Body;
+
+/// An asset body of known length that can be read from any offset.
+///
+/// A [`Body::Sync`] asset is already in memory, so the handler can slice it
+/// for a `Range` request on its own. A streamed body cannot be sliced without
+/// reading everything before the range, so an asset that lives in a file or
+/// object store implements this trait instead and is returned through
+/// [`StaticAsset::seekable`](super::StaticAsset::seekable). The handler then
+/// asks only for the bytes each response needs.
+///
+/// # Example
+///
+/// ```rust
+/// use std::ops::Range;
+///
+/// use bytes::Bytes;
+/// use leptos_wasi::{response::Body, static_files::SeekableSource};
+///
+/// struct Chunked(Bytes);
+///
+/// impl SeekableSource for Chunked {
+///     fn len(&self) -> u64 {
+///         self.0.len() as u64
+///     }
+///
+///     fn read_range(&self, range: Range<u64>) -> Body {
+///         let start = usize::try_from(range.start).unwrap_or(usize::MAX);
+///         let end = usize::try_from(range.end).unwrap_or(usize::MAX);
+///         Body::Sync(self.0.slice(start..end))
+///     }
+/// }
+/// ```
+pub trait SeekableSource: Send + Sync + 'static {
+    /// The total length of the asset in bytes.
+    fn len(&self) -> u64;
+
+    /// Whether the asset is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a body producing exactly the bytes in `range`.
+    ///
+    /// The handler only asks for non-empty ranges within `0..self.len()`, and
+    /// it has already announced `range.end - range.start` as the length of
+    /// that part of the response. A body that ends early or runs long is sent
+    /// as it is and the host rejects the mismatch, so report a read failure as
+    /// a stream error rather than by truncating.
+    fn read_range(&self, range: Range<u64>) -> Body;
+}
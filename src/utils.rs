@@ -1,15 +1,84 @@
 //! Utilities for manipulating Leptos responses from reactive context.
 
-use crate::response::ResponseOptions;
+#[cfg(feature = "cookies")]
+pub mod cookies;
+#[cfg(feature = "multipart")]
+pub mod multipart;
+
+use crate::{RedirectPolicy, response::ResponseOptions};
 use http::{HeaderName, HeaderValue, StatusCode, header, request::Parts};
 use leptos::prelude::use_context;
 use server_fn::redirect::REDIRECT_HEADER;
+use thiserror::Error;
+
+/// The status a [`redirect_with`] answers an HTML request with.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RedirectKind {
+    /// `302 Found`, what [`redirect`] uses.
+    #[default]
+    Found,
+    /// `301 Moved Permanently`. Clients may cache it and may turn a `POST`
+    /// into a `GET`.
+    MovedPermanently,
+    /// `303 See Other`, the answer to a form `POST`: the client follows it
+    /// with a `GET`.
+    SeeOther,
+    /// `307 Temporary Redirect`, which keeps the method and body.
+    TemporaryRedirect,
+    /// `308 Permanent Redirect`, which keeps the method and body and may be
+    /// cached.
+    PermanentRedirect,
+}
+
+impl RedirectKind {
+    /// Returns the status code this kind is sent with.
+    #[must_use]
+    pub const fn status(self) -> StatusCode {
+        match self {
+            Self::Found => StatusCode::FOUND,
+            Self::MovedPermanently => StatusCode::MOVED_PERMANENTLY,
+            Self::SeeOther => StatusCode::SEE_OTHER,
+            Self::TemporaryRedirect => StatusCode::TEMPORARY_REDIRECT,
+            Self::PermanentRedirect => StatusCode::PERMANENT_REDIRECT,
+        }
+    }
+
+    /// Returns whether clients may remember the redirect.
+    #[must_use]
+    pub const fn is_permanent(self) -> bool {
+        matches!(self, Self::MovedPermanently | Self::PermanentRedirect)
+    }
+}
+
+/// Errors returned by [`redirect_with`]. The response is left untouched.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RedirectError {
+    /// `Parts` or `ResponseOptions` was not found in the current reactive
+    /// context.
+    #[error(
+        "no request Parts or ResponseOptions in the current reactive context"
+    )]
+    MissingContext,
+    /// The path is not a valid `Location` header value.
+    #[error("redirect path `{0}` is not a valid header value")]
+    InvalidPath(String),
+    /// The handler's [`RedirectPolicy`] does not allow the target.
+    #[error("redirect target is not allowed by the redirect policy")]
+    Disallowed,
+}
 
 /// Allows returning an HTTP redirection from components.
 ///
 /// Inspects the current Leptos context for `Parts` and `ResponseOptions` to insert the
 /// `Location` header or set a 302 status code.
 ///
+/// A target that is not a valid header value, or that the handler's
+/// [`RedirectPolicy`] does not allow, is rejected: no `Location` is written
+/// and the status is set to 400. Use [`redirect_with`] to choose the status
+/// and to handle those errors yourself.
+///
 /// # Example
 ///
 /// ```ignore
@@ -25,58 +94,113 @@ use server_fn::redirect::REDIRECT_HEADER;
 /// }
 /// ```
 pub fn redirect(path: &str) {
-    if let (Some(req), Some(res)) =
-        (use_context::<Parts>(), use_context::<ResponseOptions>())
-    {
-        // insert the Location header in any case
-        match header::HeaderValue::from_str(path) {
-            Ok(value) => {
-                res.insert_header(header::LOCATION, value);
-            }
-            Err(e) => {
-                eprintln!("Invalid redirect path: {path}, error: {e:?}");
+    match redirect_with(path, RedirectKind::Found) {
+        Ok(()) => {}
+        Err(RedirectError::MissingContext) => {
+            eprintln!(
+                "Couldn't retrieve either Parts or ResponseOptions while \
+                 trying to redirect()."
+            );
+        }
+        Err(error) => {
+            eprintln!("Invalid redirect path: {path}, error: {error}");
+            if let Some(res) = use_context::<ResponseOptions>() {
                 res.set_status(StatusCode::BAD_REQUEST);
-                return;
             }
         }
+    }
+}
 
-        let accepts_html = req
-            .headers
-            .get(header::ACCEPT)
-            .and_then(|v| v.to_str().ok())
-            .is_some_and(|v| v.contains("text/html"));
-        if accepts_html {
-            // if the request accepts text/html, it's a plain form request and needs
-            // to have the 302 code set
-            res.set_status(StatusCode::FOUND);
-        } else {
-            // otherwise, we sent it from the server fn client and actually don't want
-            // to set a real redirect, as this will break the ability to return data
-            // instead, set the REDIRECT_HEADER to indicate that the client should redirect
-            res.insert_header(
-                HeaderName::from_static(REDIRECT_HEADER),
-                HeaderValue::from_static(""),
-            );
-        }
+/// Redirects to `path` with the status `kind` names.
+///
+/// An HTML request, a plain form submission, receives `kind`'s status and a
+/// `Location`. Any other caller is the server-function client, which receives
+/// the `Location` and the server-function redirect header without a
+/// redirect status, so it can still read the response body.
+///
+/// # Errors
+///
+/// Returns [`RedirectError`] without touching the response when the reactive
+/// context is missing, when `path` is not a valid header value, or when the
+/// handler's [`RedirectPolicy`] does not allow it. A rejected target is also
+/// traced.
+///
+/// # Example
+///
+/// ```rust
+/// use leptos_wasi::utils::{RedirectError, RedirectKind, redirect_with};
+///
+/// // Called from the server function a form posts to.
+/// fn show_confirmation() -> Result<(), RedirectError> {
+///     redirect_with("/orders/confirmation", RedirectKind::SeeOther)
+/// }
+/// ```
+pub fn redirect_with(
+    path: &str,
+    kind: RedirectKind,
+) -> Result<(), RedirectError> {
+    let (Some(req), Some(res)) =
+        (use_context::<Parts>(), use_context::<ResponseOptions>())
+    else {
+        return Err(RedirectError::MissingContext);
+    };
+    let value = HeaderValue::from_str(path)
+        .map_err(|_| RedirectError::InvalidPath(path.to_string()))?;
+    if let Some(policy) = use_context::<RedirectPolicy>()
+        && !policy.check(path, "redirect")
+    {
+        return Err(RedirectError::Disallowed);
+    }
+    res.insert_header(header::LOCATION, value);
+
+    let accepts_html = req
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+    if accepts_html {
+        // if the request accepts text/html, it's a plain form request and needs
+        // to have the redirect status set
+        res.set_status(kind.status());
     } else {
-        eprintln!(
-            "Couldn't retrieve either Parts or ResponseOptions while trying \
-             to redirect()."
+        // otherwise, we sent it from the server fn client and actually don't want
+        // to set a real redirect, as this will break the ability to return data
+        // instead, set the REDIRECT_HEADER to indicate that the client should redirect
+        res.insert_header(
+            HeaderName::from_static(REDIRECT_HEADER),
+            HeaderValue::from_static(""),
         );
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::redirect;
-    use crate::response::{ResponseOptions, ResponseParts};
+    use super::{RedirectError, RedirectKind, redirect, redirect_with};
+    use crate::{
+        RedirectPolicy,
+        response::{ResponseOptions, ResponseParts},
+    };
     use http::{HeaderValue, Request, StatusCode, header};
     use leptos::prelude::{Owner, provide_context};
 
-    fn redirect_with(
+    fn redirected(accept: Option<&'static str>, path: &str) -> ResponseParts {
+        redirect_under(accept, path, &RedirectPolicy::default())
+    }
+
+    fn redirect_under(
         accept: Option<&'static str>,
         path: &str,
+        policy: &RedirectPolicy,
     ) -> ResponseParts {
+        in_request(accept, policy, || redirect(path)).0
+    }
+
+    fn in_request<R>(
+        accept: Option<&'static str>,
+        policy: &RedirectPolicy,
+        act: impl FnOnce() -> R,
+    ) -> (ResponseParts, R) {
         let mut builder = Request::builder().uri("/current");
         if let Some(accept) = accept {
             builder = builder.header(header::ACCEPT, accept);
@@ -87,19 +211,19 @@ mod tests {
             .into_parts();
 
         let owner = Owner::new();
-        let options = owner.with(|| {
+        let (options, result) = owner.with(|| {
             let options = ResponseOptions::default();
             provide_context(parts);
             provide_context(options.clone());
-            redirect(path);
-            options
+            provide_context(policy.clone());
+            (options.clone(), act())
         });
-        options.snapshot()
+        (options.snapshot(), result)
     }
 
     #[test]
     fn html_requests_receive_a_found_status_and_location() {
-        let parts = redirect_with(Some("text/html"), "/target-page");
+        let parts = redirected(Some("text/html"), "/target-page");
 
         assert_eq!(parts.status(), Some(StatusCode::FOUND));
         assert_eq!(
@@ -110,7 +234,7 @@ mod tests {
 
     #[test]
     fn client_requests_receive_the_redirect_header_instead_of_a_status() {
-        let parts = redirect_with(Some("application/json"), "/target-page");
+        let parts = redirected(Some("application/json"), "/target-page");
 
         assert_eq!(parts.status(), None);
         assert_eq!(
@@ -126,7 +250,7 @@ mod tests {
 
     #[test]
     fn requests_without_an_accept_header_use_the_client_redirect_protocol() {
-        let parts = redirect_with(None, "/target-page");
+        let parts = redirected(None, "/target-page");
 
         assert_eq!(parts.status(), None);
         assert!(
@@ -138,7 +262,7 @@ mod tests {
 
     #[test]
     fn header_injection_attempts_are_rejected_with_bad_request() {
-        let parts = redirect_with(
+        let parts = redirected(
             Some("text/html"),
             "/target-page\r\nLocation: http://evil.example.com",
         );
@@ -147,6 +271,116 @@ mod tests {
         assert!(!parts.headers().contains_key(header::LOCATION));
     }
 
+    #[test]
+    fn targets_outside_the_redirect_policy_are_rejected() {
+        let parts = redirected(Some("text/html"), "https://evil.example.com/");
+
+        assert_eq!(parts.status(), Some(StatusCode::BAD_REQUEST));
+        assert!(!parts.headers().contains_key(header::LOCATION));
+    }
+
+    #[test]
+    fn allowlisted_origins_can_be_redirected_to() {
+        const AUTHORIZE: &str = "https://accounts.example.com/authorize";
+        let policy = RedirectPolicy::same_origin()
+            .allow_origin("https://accounts.example.com");
+
+        let parts = redirect_under(Some("text/html"), AUTHORIZE, &policy);
+
+        assert_eq!(parts.status(), Some(StatusCode::FOUND));
+        assert_eq!(
+            parts.headers().get(header::LOCATION),
+            Some(&HeaderValue::from_static(AUTHORIZE))
+        );
+    }
+
+    #[test]
+    fn each_kind_sets_its_status_for_html_requests() {
+        for (kind, status) in [
+            (RedirectKind::Found, StatusCode::FOUND),
+            (
+                RedirectKind::MovedPermanently,
+                StatusCode::MOVED_PERMANENTLY,
+            ),
+            (RedirectKind::SeeOther, StatusCode::SEE_OTHER),
+            (
+                RedirectKind::TemporaryRedirect,
+                StatusCode::TEMPORARY_REDIRECT,
+            ),
+            (
+                RedirectKind::PermanentRedirect,
+                StatusCode::PERMANENT_REDIRECT,
+            ),
+        ] {
+            let (parts, result) = in_request(
+                Some("text/html"),
+                &RedirectPolicy::default(),
+                || redirect_with("/target-page", kind),
+            );
+
+            assert_eq!(result, Ok(()));
+            assert_eq!(parts.status(), Some(status));
+            assert_eq!(
+                parts.headers().get(header::LOCATION),
+                Some(&HeaderValue::from_static("/target-page"))
+            );
+        }
+        assert!(RedirectKind::PermanentRedirect.is_permanent());
+        assert!(!RedirectKind::SeeOther.is_permanent());
+    }
+
+    #[test]
+    fn server_fn_clients_keep_the_redirect_header_protocol_for_every_kind() {
+        let (parts, result) = in_request(
+            Some("application/json"),
+            &RedirectPolicy::default(),
+            || redirect_with("/target-page", RedirectKind::PermanentRedirect),
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(parts.status(), None);
+        assert!(
+            parts
+                .headers()
+                .contains_key(server_fn::redirect::REDIRECT_HEADER)
+        );
+    }
+
+    #[test]
+    fn rejected_targets_are_returned_and_leave_the_response_untouched() {
+        let policy = RedirectPolicy::default();
+        let (invalid, invalid_result) =
+            in_request(Some("text/html"), &policy, || {
+                redirect_with("/a\r\nb", RedirectKind::SeeOther)
+            });
+        let (disallowed, disallowed_result) =
+            in_request(Some("text/html"), &policy, || {
+                redirect_with(
+                    "https://evil.example.com/",
+                    RedirectKind::SeeOther,
+                )
+            });
+
+        assert_eq!(
+            invalid_result,
+            Err(RedirectError::InvalidPath("/a\r\nb".to_owned()))
+        );
+        assert_eq!(disallowed_result, Err(RedirectError::Disallowed));
+        for parts in [invalid, disallowed] {
+            assert_eq!(parts.status(), None);
+            assert!(parts.headers().is_empty());
+        }
+    }
+
+    #[test]
+    fn redirect_with_reports_a_missing_context() {
+        let owner = Owner::new();
+        let result =
+            owner.with(|| redirect_with("/target-page", RedirectKind::Found));
+
+        assert_eq!(result, Err(RedirectError::MissingContext));
+    }
+
     #[test]
     fn redirecting_without_context_does_not_panic() {
         let owner = Owner::new();
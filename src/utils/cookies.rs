@@ -0,0 +1,527 @@
+//! Reading and writing cookies from reactive context.
+//!
+//! Every function here reads the request's [`Parts`] and writes the
+//! response's [`ResponseOptions`] through `use_context`, so the same calls
+//! work in an SSR component and in a server function. Signed and private
+//! cookies use the [`CookieKey`] configured through
+//! [`HandlerConfig::with_cookie_key`](crate::HandlerConfig::with_cookie_key).
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use leptos_wasi::utils::cookies::{self, SetCookie};
+//!
+//! fn remember_theme(theme: &str) -> Result<(), cookies::CookieError> {
+//!     cookies::set(
+//!         SetCookie::new("theme", theme)
+//!             .with_max_age(Duration::from_secs(30 * 24 * 60 * 60)),
+//!     )
+//! }
+//!
+//! fn current_user() -> Option<String> {
+//!     cookies::get_private("session")
+//! }
+//! ```
+
+use std::{fmt, time::Duration};
+
+use cookie::{Cookie, CookieJar, Key};
+use http::{HeaderValue, header, request::Parts};
+use leptos::prelude::use_context;
+use thiserror::Error;
+
+use crate::response::ResponseOptions;
+
+/// Errors returned while writing a cookie.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CookieError {
+    /// No `ResponseOptions` was found in the current reactive context.
+    #[error("no ResponseOptions in the current reactive context")]
+    MissingContext,
+    /// A signed or private cookie was written without a configured key.
+    #[error("no cookie key is configured")]
+    MissingKey,
+    /// The cookie could not be represented as a `Set-Cookie` header.
+    #[error("cookie `{0}` is not a valid Set-Cookie header")]
+    InvalidCookie(String),
+}
+
+/// Error returned when key material is too short to build a [`CookieKey`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[error("cookie key material must be at least {required} bytes, got {actual}")]
+pub struct CookieKeyError {
+    required: usize,
+    actual: usize,
+}
+
+/// The secret that signs and encrypts cookies.
+///
+/// It holds a 256-bit HMAC-SHA256 signing key and a 256-bit AES-256-GCM
+/// encryption key. Load it from deployment configuration, never from source:
+/// anyone holding it can forge signed cookies and read private ones, and
+/// rotating it invalidates every cookie issued under it.
+#[derive(Clone)]
+pub struct CookieKey(Key);
+
+impl CookieKey {
+    /// Uses 64 bytes of random key material directly: the first 32 for
+    /// signing and the next 32 for encryption.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CookieKeyError`] if `bytes` holds fewer than 64 bytes.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, CookieKeyError> {
+        Key::try_from(bytes).map(Self).map_err(|_| CookieKeyError {
+            required: 64,
+            actual: bytes.len(),
+        })
+    }
+
+    /// Derives both keys from a random master secret of at least 32 bytes
+    /// with HKDF-SHA256.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CookieKeyError`] if `master` holds fewer than 32 bytes.
+    pub fn derive_from(master: &[u8]) -> Result<Self, CookieKeyError> {
+        if master.len() < 32 {
+            return Err(CookieKeyError {
+                required: 32,
+                actual: master.len(),
+            });
+        }
+        Ok(Self(Key::derive_from(master)))
+    }
+}
+
+impl fmt::Debug for CookieKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CookieKey").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for CookieKey {
+    fn eq(&self, other: &Self) -> bool {
+        // `Key` compares in constant time.
+        self.0 == other.0
+    }
+}
+
+impl Eq for CookieKey {}
+
+/// The `SameSite` attribute of a [`SetCookie`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SameSite {
+    /// Sent only with same-site requests.
+    Strict,
+    /// Also sent with top-level cross-site navigations. The default.
+    Lax,
+    /// Sent with every request. Browsers require `Secure` with it.
+    None,
+}
+
+/// A typed `Set-Cookie` header.
+///
+/// The defaults are the safe ones: `HttpOnly`, `Secure`, `SameSite=Lax`, and
+/// `Path=/`, with no `Max-Age`, which makes a session cookie. Loosen them
+/// explicitly where a cookie needs it; `Secure` in particular must be turned
+/// off for a cookie to survive plain-HTTP local development in some
+/// browsers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    http_only: bool,
+    secure: bool,
+    same_site: SameSite,
+}
+
+impl SetCookie {
+    /// Returns a cookie with the secure defaults described on [`SetCookie`].
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            path: Some(String::from("/")),
+            domain: None,
+            max_age: None,
+            http_only: true,
+            secure: true,
+            same_site: SameSite::Lax,
+        }
+    }
+
+    /// Returns a cookie that removes `name` from the browser.
+    ///
+    /// A browser only removes a cookie whose path and domain match, so set
+    /// those to the values the cookie was issued with.
+    pub fn removal(name: impl Into<String>) -> Self {
+        Self::new(name, "").with_max_age(Duration::ZERO)
+    }
+
+    /// Returns a copy scoped to `path` instead of `/`.
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Returns a copy that is also sent to subdomains of `domain`.
+    #[must_use]
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Returns a copy that expires after `max_age`.
+    #[must_use]
+    pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns a copy with the given `SameSite` attribute.
+    #[must_use]
+    pub const fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Returns a copy readable from client-side JavaScript.
+    #[must_use]
+    pub const fn without_http_only(mut self) -> Self {
+        self.http_only = false;
+        self
+    }
+
+    /// Returns a copy that is also sent over plain HTTP.
+    #[must_use]
+    pub const fn without_secure(mut self) -> Self {
+        self.secure = false;
+        self
+    }
+
+    fn into_cookie(self) -> Cookie<'static> {
+        let mut cookie = Cookie::new(self.name, self.value);
+        if let Some(path) = self.path {
+            cookie.set_path(path);
+        }
+        if let Some(domain) = self.domain {
+            cookie.set_domain(domain);
+        }
+        if let Some(max_age) = self.max_age {
+            let seconds = i64::try_from(max_age.as_secs()).unwrap_or(i64::MAX);
+            cookie.set_max_age(cookie::time::Duration::seconds(seconds));
+        }
+        cookie.set_http_only(self.http_only);
+        cookie.set_secure(self.secure);
+        cookie.set_same_site(match self.same_site {
+            SameSite::Strict => cookie::SameSite::Strict,
+            SameSite::Lax => cookie::SameSite::Lax,
+            SameSite::None => cookie::SameSite::None,
+        });
+        cookie
+    }
+}
+
+/// Returns the value of the request cookie `name`.
+#[must_use]
+pub fn get(name: &str) -> Option<String> {
+    request_jar()?
+        .get(name)
+        .map(|cookie| cookie.value().to_string())
+}
+
+/// Returns the value of the signed request cookie `name`, if its signature
+/// verifies under the configured key.
+///
+/// The value is readable by the client; signing only stops it being
+/// changed. Use [`get_private`] for a value the client must not read.
+#[must_use]
+pub fn get_signed(name: &str) -> Option<String> {
+    let key = use_context::<CookieKey>()?;
+    request_jar()?
+        .signed(&key.0)
+        .get(name)
+        .map(|cookie| cookie.value().to_string())
+}
+
+/// Returns the value of the private request cookie `name`, if it decrypts
+/// and authenticates under the configured key.
+#[must_use]
+pub fn get_private(name: &str) -> Option<String> {
+    let key = use_context::<CookieKey>()?;
+    request_jar()?
+        .private(&key.0)
+        .get(name)
+        .map(|cookie| cookie.value().to_string())
+}
+
+/// Appends `cookie` to the response as a `Set-Cookie` header.
+///
+/// # Errors
+///
+/// Returns [`CookieError::MissingContext`] outside an SSR render or server
+/// function, and [`CookieError::InvalidCookie`] if the cookie cannot be
+/// written as a header.
+pub fn set(cookie: SetCookie) -> Result<(), CookieError> {
+    append(&cookie.into_cookie())
+}
+
+/// Appends `cookie` with its value signed by the configured key.
+///
+/// # Errors
+///
+/// Returns [`CookieError::MissingKey`] when no key is configured, and the
+/// errors of [`set`].
+pub fn set_signed(cookie: SetCookie) -> Result<(), CookieError> {
+    let key = use_context::<CookieKey>().ok_or(CookieError::MissingKey)?;
+    let mut jar = CookieJar::new();
+    jar.signed_mut(&key.0).add(cookie.into_cookie());
+    append_delta(&jar)
+}
+
+/// Appends `cookie` with its value encrypted and authenticated by the
+/// configured key.
+///
+/// # Errors
+///
+/// Returns [`CookieError::MissingKey`] when no key is configured, and the
+/// errors of [`set`].
+pub fn set_private(cookie: SetCookie) -> Result<(), CookieError> {
+    let key = use_context::<CookieKey>().ok_or(CookieError::MissingKey)?;
+    let mut jar = CookieJar::new();
+    jar.private_mut(&key.0).add(cookie.into_cookie());
+    append_delta(&jar)
+}
+
+/// Removes the cookie `name`, issued with `Path=/` and no domain.
+///
+/// Use [`SetCookie::removal`] with [`set`] for a cookie issued with another
+/// path or a domain.
+///
+/// # Errors
+///
+/// Returns the errors of [`set`].
+pub fn remove(name: &str) -> Result<(), CookieError> {
+    set(SetCookie::removal(name))
+}
+
+fn request_jar() -> Option<CookieJar> {
+    let parts = use_context::<Parts>()?;
+    let mut jar = CookieJar::new();
+    for value in parts.headers.get_all(header::COOKIE) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for cookie in Cookie::split_parse_encoded(value).flatten() {
+            jar.add_original(cookie.into_owned());
+        }
+    }
+    Some(jar)
+}
+
+fn append_delta(jar: &CookieJar) -> Result<(), CookieError> {
+    jar.delta().try_for_each(append)
+}
+
+fn append(cookie: &Cookie<'_>) -> Result<(), CookieError> {
+    let response =
+        use_context::<ResponseOptions>().ok_or(CookieError::MissingContext)?;
+    let value = HeaderValue::from_str(&cookie.encoded().to_string())
+        .map_err(|_| CookieError::InvalidCookie(cookie.name().to_string()))?;
+    response.append_header(header::SET_COOKIE, value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderMap, Request};
+    use leptos::prelude::{Owner, provide_context};
+
+    use super::*;
+
+    fn key() -> CookieKey {
+        CookieKey::derive_from(&[7; 32]).expect("32 bytes should be enough")
+    }
+
+    /// Runs `body` with the standard contexts for a request carrying
+    /// `cookies`, and returns the `Set-Cookie` headers it wrote.
+    fn with_request<T>(
+        cookies: &[&str],
+        key: Option<CookieKey>,
+        body: impl FnOnce() -> T,
+    ) -> (T, Vec<String>) {
+        let mut builder = Request::builder().uri("/");
+        for cookie in cookies {
+            builder = builder.header(header::COOKIE, *cookie);
+        }
+        let (parts, ()) = builder
+            .body(())
+            .expect("test request should be valid")
+            .into_parts();
+        let owner = Owner::new();
+        let (result, options) = owner.with(|| {
+            let options = ResponseOptions::default();
+            provide_context(parts);
+            provide_context(options.clone());
+            if let Some(key) = key {
+                provide_context(key);
+            }
+            (body(), options)
+        });
+        (result, set_cookies(options.snapshot().headers()))
+    }
+
+    fn set_cookies(headers: &HeaderMap) -> Vec<String> {
+        headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok().map(str::to_string))
+            .collect()
+    }
+
+    /// The `name=value` pair of a `Set-Cookie` header, as a browser would
+    /// send it back.
+    fn pair(set_cookie: &str) -> &str {
+        set_cookie.split(';').next().unwrap_or_default()
+    }
+
+    #[test]
+    fn plain_cookies_are_read_from_every_cookie_header() {
+        let ((theme, lang, missing), _) =
+            with_request(&["theme=dark; a=b", "lang=en%20GB"], None, || {
+                (get("theme"), get("lang"), get("missing"))
+            });
+
+        assert_eq!(theme.as_deref(), Some("dark"));
+        assert_eq!(lang.as_deref(), Some("en GB"));
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn set_cookie_has_secure_defaults() {
+        let (result, headers) =
+            with_request(&[], None, || set(SetCookie::new("theme", "dark")));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(headers.len(), 1);
+        let header = &headers[0];
+        assert!(header.starts_with("theme=dark"));
+        for attribute in ["HttpOnly", "Secure", "SameSite=Lax", "Path=/"] {
+            assert!(header.contains(attribute), "{header} lacks {attribute}");
+        }
+        assert!(!header.contains("Max-Age"));
+    }
+
+    #[test]
+    fn defaults_can_be_loosened() {
+        let (_, headers) = with_request(&[], None, || {
+            set(SetCookie::new("prefs", "1")
+                .without_http_only()
+                .without_secure()
+                .with_same_site(SameSite::Strict)
+                .with_path("/app")
+                .with_domain("example.com")
+                .with_max_age(Duration::from_secs(90)))
+        });
+
+        let header = &headers[0];
+        assert!(!header.contains("HttpOnly"));
+        assert!(!header.contains("Secure"));
+        for attribute in [
+            "SameSite=Strict",
+            "Path=/app",
+            "Domain=example.com",
+            "Max-Age=90",
+        ] {
+            assert!(header.contains(attribute), "{header} lacks {attribute}");
+        }
+    }
+
+    #[test]
+    fn removal_expires_the_cookie() {
+        let (_, headers) = with_request(&[], None, || remove("session"));
+
+        assert!(headers[0].starts_with("session=;"));
+        assert!(headers[0].contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn signed_cookies_round_trip_and_reject_tampering() {
+        let (_, headers) = with_request(&[], Some(key()), || {
+            set_signed(SetCookie::new("user", "42"))
+        });
+        let signed = pair(&headers[0]).to_string();
+        assert!(signed.ends_with("42"), "signed values stay readable");
+
+        let (value, _) =
+            with_request(&[&signed], Some(key()), || get_signed("user"));
+        assert_eq!(value.as_deref(), Some("42"));
+
+        let tampered = format!("{}43", &signed[..signed.len() - 2]);
+        let (value, _) =
+            with_request(&[&tampered], Some(key()), || get_signed("user"));
+        assert_eq!(value, None);
+
+        let (value, _) = with_request(&[&signed], None, || get_signed("user"));
+        assert_eq!(value, None, "no key, no trust");
+    }
+
+    #[test]
+    fn private_cookies_hide_their_value_and_need_the_same_key() {
+        let (_, headers) = with_request(&[], Some(key()), || {
+            set_private(SetCookie::new("session", "secret-token"))
+        });
+        let private = pair(&headers[0]).to_string();
+        assert!(!private.contains("secret-token"));
+
+        let (value, _) =
+            with_request(&[&private], Some(key()), || get_private("session"));
+        assert_eq!(value.as_deref(), Some("secret-token"));
+
+        let other = CookieKey::derive_from(&[8; 32])
+            .expect("32 bytes should be enough");
+        let (value, _) =
+            with_request(&[&private], Some(other), || get_private("session"));
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn keyed_cookies_need_a_key() {
+        let (result, headers) = with_request(&[], None, || {
+            set_signed(SetCookie::new("user", "42"))
+        });
+
+        assert_eq!(result, Err(CookieError::MissingKey));
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn writing_outside_a_request_is_an_error() {
+        let owner = Owner::new();
+        let result = owner.with(|| set(SetCookie::new("theme", "dark")));
+
+        assert_eq!(result, Err(CookieError::MissingContext));
+    }
+
+    #[test]
+    fn short_key_material_is_rejected() {
+        assert!(CookieKey::derive_from(&[0; 31]).is_err());
+        assert!(CookieKey::try_from_bytes(&[0; 63]).is_err());
+        assert!(CookieKey::try_from_bytes(&[0; 64]).is_ok());
+        assert_eq!(
+            CookieKey::derive_from(&[0; 16])
+                .expect_err("16 bytes is too short")
+                .to_string(),
+            "cookie key material must be at least 32 bytes, got 16"
+        );
+    }
+}
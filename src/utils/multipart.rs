@@ -0,0 +1,236 @@
+//! Reading `multipart/form-data` uploads in a server function.
+//!
+//! [`MultipartForm`] wraps the [`multer::Multipart`] a server function using
+//! `server_fn`'s `MultipartFormData` encoding receives, and sorts its fields
+//! into buffered text fields and streamed file fields. With
+//! [`HandlerConfig::with_multipart_limits`](crate::HandlerConfig::with_multipart_limits)
+//! the body reaches it unbuffered, so a file can be written out chunk by
+//! chunk, and every field is bounded by the configured part size.
+//!
+//! # Example
+//!
+//! ```rust
+//! use leptos_wasi::utils::multipart::{FormField, MultipartError, MultipartForm};
+//!
+//! async fn save(
+//!     multipart: multer::Multipart<'static>,
+//! ) -> Result<u64, MultipartError> {
+//!     let mut form = MultipartForm::new(multipart);
+//!     let mut written = 0;
+//!     while let Some(field) = form.next_field().await? {
+//!         match field {
+//!             FormField::Text(text) => {
+//!                 eprintln!("{} = {}", text.name(), text.value());
+//!             }
+//!             FormField::File(mut file) => {
+//!                 while let Some(chunk) = file.chunk().await? {
+//!                     written += chunk.len() as u64;
+//!                 }
+//!             }
+//!         }
+//!     }
+//!     Ok(written)
+//! }
+//! ```
+//!
+//! In the server function itself, `MultipartData::into_inner` returns the
+//! `multer::Multipart` to pass to [`MultipartForm::new`].
+
+use bytes::Bytes;
+use thiserror::Error;
+
+/// Errors returned while reading a multipart body.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum MultipartError {
+    /// The body was not valid `multipart/form-data`, or ended early. A body
+    /// cut off by a multipart limit ends early.
+    #[error("failed to read multipart body")]
+    Parse(#[from] multer::Error),
+}
+
+/// The fields of a `multipart/form-data` body, read in order.
+pub struct MultipartForm {
+    inner: multer::Multipart<'static>,
+}
+
+impl MultipartForm {
+    /// Wraps the body a server function received.
+    #[must_use]
+    pub fn new(multipart: multer::Multipart<'static>) -> Self {
+        Self { inner: multipart }
+    }
+
+    /// Returns the next field, or `None` after the last one.
+    ///
+    /// A field with a file name is returned as a [`FileField`] to be read
+    /// chunk by chunk. Any other field is read in full and returned as a
+    /// [`TextField`]. A [`FileField`] must be dropped before the next field
+    /// is requested.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MultipartError::Parse`] if the body is malformed, a text
+    /// field is not UTF-8, or the body ends early.
+    pub async fn next_field(
+        &mut self,
+    ) -> Result<Option<FormField>, MultipartError> {
+        let Some(field) = self.inner.next_field().await? else {
+            return Ok(None);
+        };
+        if field.file_name().is_some() {
+            return Ok(Some(FormField::File(FileField {
+                inner: Box::new(field),
+            })));
+        }
+        let name = field.name().unwrap_or_default().to_owned();
+        let value = field.text().await?;
+        Ok(Some(FormField::Text(TextField { name, value })))
+    }
+}
+
+/// One field of a [`MultipartForm`].
+#[derive(Debug)]
+pub enum FormField {
+    /// A field without a file name, read in full.
+    Text(TextField),
+    /// A file upload, read chunk by chunk.
+    File(FileField),
+}
+
+/// A text field of a multipart body.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextField {
+    name: String,
+    value: String,
+}
+
+impl TextField {
+    /// Returns the field name, or an empty string when it has none.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the field value.
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the field value, consuming the field.
+    #[must_use]
+    pub fn into_value(self) -> String {
+        self.value
+    }
+}
+
+/// A file field of a multipart body, streamed as it arrives.
+#[derive(Debug)]
+pub struct FileField {
+    inner: Box<multer::Field<'static>>,
+}
+
+impl FileField {
+    /// Returns the field name, or an empty string when it has none.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.inner.name().unwrap_or_default()
+    }
+
+    /// Returns the file name the client sent. Treat it as untrusted input,
+    /// never as a path.
+    #[must_use]
+    pub fn file_name(&self) -> &str {
+        self.inner.file_name().unwrap_or_default()
+    }
+
+    /// Returns the content type the client declared, if any.
+    #[must_use]
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner.content_type().map(AsRef::as_ref)
+    }
+
+    /// Returns the next chunk of the file, or `None` after the last one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MultipartError::Parse`] if the body is malformed or ends
+    /// early.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, MultipartError> {
+        Ok(self.inner.chunk().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use futures::stream;
+
+    use super::*;
+
+    fn form(body: &'static [u8]) -> MultipartForm {
+        let chunks = body
+            .chunks(5)
+            .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk)));
+        MultipartForm::new(multer::Multipart::new(
+            stream::iter(chunks.collect::<Vec<_>>()),
+            "x",
+        ))
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn text_fields_are_buffered_and_files_streamed() {
+        let mut form = form(
+            b"--x\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            hello\r\n\
+            --x\r\n\
+            Content-Disposition: form-data; name=\"avatar\"; filename=\"a.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            0123456789\r\n\
+            --x--\r\n",
+        );
+
+        let Some(FormField::Text(text)) =
+            form.next_field().await.expect("the body is valid")
+        else {
+            unreachable!("the first field is text");
+        };
+        assert_eq!((text.name(), text.value()), ("title", "hello"));
+
+        let Some(FormField::File(mut file)) =
+            form.next_field().await.expect("the body is valid")
+        else {
+            unreachable!("the second field is a file");
+        };
+        assert_eq!(file.name(), "avatar");
+        assert_eq!(file.file_name(), "a.png");
+        assert_eq!(file.content_type(), Some("image/png"));
+        let mut contents = Vec::new();
+        while let Some(chunk) = file.chunk().await.expect("the body is valid") {
+            contents.extend_from_slice(&chunk);
+        }
+        assert_eq!(contents, b"0123456789");
+        drop(file);
+
+        assert!(
+            form.next_field()
+                .await
+                .expect("the body is valid")
+                .is_none()
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn a_truncated_body_is_an_error() {
+        let mut form = form(
+            b"--x\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            hel",
+        );
+
+        assert!(form.next_field().await.is_err());
+    }
+}